@@ -0,0 +1,81 @@
+use crate::errors::*;
+use crate::pacman::Package;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+pub const ARCHWEB_URL: &str = "https://archlinux.org";
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+    #[serde(default)]
+    pub num_pages: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchResult {
+    pub pkgname: String,
+    pub pkgbase: String,
+    #[serde(default)]
+    pub depends: Vec<String>,
+    #[serde(default)]
+    pub makedepends: Vec<String>,
+    #[serde(default)]
+    pub checkdepends: Vec<String>,
+    #[serde(default)]
+    pub provides: Vec<String>,
+}
+
+impl From<SearchResult> for Package {
+    fn from(result: SearchResult) -> Package {
+        Package {
+            name: result.pkgname,
+            base: result.pkgbase,
+            depends: result.depends,
+            makedepends: result.makedepends,
+            checkdepends: result.checkdepends,
+            provides: result.provides,
+        }
+    }
+}
+
+/// Lookup a package by its exact pkgname, archweb has no search for provides
+pub async fn find_package(client: &Client, pkgname: &str) -> Result<Option<Package>> {
+    let url = format!("{}/packages/search/json/", ARCHWEB_URL);
+    info!("Url={} (name={:?})", url, pkgname);
+    let json = client
+        .get(url)
+        .query(&[("name", pkgname)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SearchResults>()
+        .await?;
+    Ok(json.results.into_iter().next().map(Package::from))
+}
+
+/// List the pkgbases of all packages maintained by this archweb user
+pub async fn maintained_pkgbases(client: &Client, maintainer: &str) -> Result<BTreeSet<String>> {
+    let url = format!("{}/packages/search/json/", ARCHWEB_URL);
+    let mut pkgbases = BTreeSet::new();
+    let mut page = 1;
+    loop {
+        info!("Url={} (maintainer={:?}, page={})", url, maintainer, page);
+        let json = client
+            .get(&url)
+            .query(&[("maintainer", maintainer), ("page", &page.to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SearchResults>()
+            .await?;
+        pkgbases.extend(json.results.into_iter().map(|result| result.pkgbase));
+
+        if page >= json.num_pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(pkgbases)
+}
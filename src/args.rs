@@ -1,19 +1,51 @@
+use crate::archweb;
 use crate::asp;
+use crate::aur;
+use crate::baseline::Baseline;
+use crate::cache::Cache;
+use crate::checkpoint::Checkpoint;
+use crate::config::Config;
+use crate::db::Database;
+use crate::deps::Resolver;
+use crate::disk;
 use crate::errors::*;
 use crate::fsck;
-use crate::fsck::{Finding, Target};
+use crate::fsck::{Finding, PackageFindings, Severity, Target};
+use crate::git;
+use crate::github;
+use crate::incremental::{self, History};
+use crate::makepkg::Evaluator;
+use crate::metrics::{self, METRICS};
 use crate::osv;
+use crate::pacman;
+use crate::pgp;
+use crate::progress::Progress;
+use crate::report::{self, Format, Report};
+use crate::sandbox::Sandbox;
+use crate::sbom;
+use crate::srcpkg;
+use crate::state;
+use crate::stats::Stats;
+use crate::suppress::SuppressionFile;
+use crate::template::Template;
+use crate::term::Renderer;
 use async_trait::async_trait;
-use clap::{builder::PossibleValuesParser, ArgAction, Parser, Subcommand};
-use std::collections::HashSet;
+use clap::{builder::PossibleValuesParser, ArgAction, Parser, Subcommand, ValueEnum};
+use glob::Pattern;
 use std::collections::VecDeque;
+use std::collections::{BTreeSet, HashSet};
+use std::env;
 use std::fs;
+use std::io::{self, Read};
+use std::net::SocketAddr;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::time::{Duration, Instant};
 use strum::VariantNames;
 use tokio::process::Command;
 use tokio::task::JoinSet;
+use tokio::time::{self, MissedTickBehavior};
 
 #[derive(Debug, Parser)]
 pub struct Args {
@@ -31,21 +63,125 @@ pub struct Args {
 pub enum SubCommand {
     Check(Check),
     Vulns(Vulns),
+    Sbom(Sbom),
     SupportedIssues,
 }
 
 #[derive(Debug, Parser, Clone)]
 pub struct Check {
+    /// Directories that contain a PKGBUILD or `.src.tar.gz` source packages, use `-` to read a PKGBUILD from stdin
     pub paths: Vec<PathBuf>,
     /// Scan directory for PKGBUILDs or specify the work directory to clone packages into (eg. ./svntogit-packages)
     #[arg(short = 'W', short_alias = 'S', long, value_name = "PATH")]
     pub scan_directory: Vec<PathBuf>,
-    /// Checkout PKGBUILD with asp from devtools into a temporary directory
+    /// Read targets from a file (or `-` for stdin), one path, pkgbase, `aur/<name>` or url per line
+    #[arg(long, value_name = "PATH")]
+    pub from_file: Vec<PathBuf>,
+    /// Skip packages from `--scan-directory` and path arguments if the pkgbase or path matches this glob (eg. `*-bin`)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<Pattern>,
+    /// Checkout PKGBUILD of an official package into a temporary directory, see `--backend`
     #[arg(short = 'B', long, value_name = "PKG_NAME")]
     pub arch_build_system: Vec<String>,
+    /// The tool used to checkout packages for `--arch-build-system`
+    #[arg(long, value_enum, default_value_t = asp::Backend::Auto)]
+    pub backend: asp::Backend,
+    /// Checkout this version (eg. 6.1.1-1) of the packages for `--arch-build-system` instead of the latest, not supported by asp
+    #[arg(long, value_name = "VERSION")]
+    pub package_version: Option<String>,
+    /// Check paths in a git repository as they are in this commit, tag or branch instead of the working tree
+    #[arg(long, value_name = "REF")]
+    pub rev: Option<String>,
+    /// Check every pkgbase in the pacman sync databases of this directory (or a single `.db` file), see `--backend`
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = pacman::SYNC_DIR)]
+    pub sync_db: Option<PathBuf>,
+    /// Check every released pkgbase at its current version, as recorded in a checkout of the
+    /// archlinux/packaging/state repository, the repository is cloned if no path is given, see `--backend`
+    #[arg(long, value_name = "PATH")]
+    pub state: Option<Option<PathBuf>>,
+    /// Check a package and every pkgbase needed to build it (depends, makedepends, checkdepends and their runtime dependencies), see `--backend`
+    #[arg(long, value_name = "PKG_NAME")]
+    pub with_deps: Vec<String>,
+    /// The sync databases used to resolve `--with-deps`, the archweb api is used if this doesn't exist [default: /var/lib/pacman/sync]
+    #[arg(long, value_name = "PATH")]
+    pub deps_db: Option<PathBuf>,
+    /// Check all packages maintained by this archweb user, see `--backend`
+    #[arg(long, value_name = "NAME")]
+    pub maintainer: Vec<String>,
+    /// Clone the PKGBUILD of an AUR package into a temporary directory
+    #[arg(long, value_name = "PKG_NAME")]
+    pub aur: Vec<String>,
+    /// Check every package in the AUR, the list of packages is downloaded from aur.archlinux.org
+    #[arg(long)]
+    pub aur_all: bool,
+    /// Download a PKGBUILD (and the .SRCINFO next to it) from a url, eg. https://aur.archlinux.org/cgit/aur.git/tree/PKGBUILD?h=foo
+    #[arg(long, value_name = "URL")]
+    pub url: Vec<String>,
+    /// Wait at least this many milliseconds between starting two AUR clones, to avoid overloading aur.archlinux.org
+    #[arg(long, value_name = "MS", default_value_t = 100)]
+    pub aur_clone_interval: u64,
+    /// Don't start new checkouts while the temporary directory has less free space than this (in MiB)
+    #[arg(long, value_name = "MIB")]
+    pub min_free_space: Option<u64>,
     /// Filter only for specific findings
     #[arg(long)]
     pub discover_sigs: bool,
+    /// Authenticate the GitHub api requests of `--discover-sigs`, this raises the rate limit from 60 to 5000 requests per hour
+    ///
+    /// The token only needs to read public repositories: a fine-grained token with
+    /// "Public Repositories (read-only)" access and no permissions, or a classic token without any scopes.
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "GITHUB_TOKEN",
+        hide_env_values = true
+    )]
+    pub github_token: Option<github::Token>,
+    /// Check if sources using http, ftp or another unauthenticated scheme are also served over https, and suggest the https url
+    #[arg(long)]
+    pub probe_https: bool,
+    /// Send a request for every url source and report sources that are gone, time out or changed their size
+    #[arg(long)]
+    pub check_availability: bool,
+    /// Download every url source and verify it with all declared checksums
+    #[arg(long)]
+    pub verify_sums: bool,
+    /// Fetch pinned git commits to confirm they exist upstream and match the named tag
+    #[arg(long)]
+    pub verify_git_pins: bool,
+    /// Fetch signed git tags and verify them with the keys in validpgpkeys (downloaded from `--keyserver`)
+    #[arg(long)]
+    pub verify_signed_tags: bool,
+    /// Fetch `.gitmodules` of pinned git sources, unpinned git sources are only accepted if they're a submodule
+    #[arg(long)]
+    pub check_submodules: bool,
+    /// How to read the variables and functions of the PKGBUILD [default: bash]
+    #[arg(long, value_enum)]
+    pub evaluator: Option<Evaluator>,
+    /// Only read `.SRCINFO` instead of sourcing the PKGBUILD with bash, same as `--evaluator srcinfo`
+    #[arg(long, conflicts_with = "evaluator")]
+    pub srcinfo: bool,
+    /// How to isolate the PKGBUILD while it's sourced with bash
+    #[arg(long, value_enum, default_value_t = Sandbox::Auto)]
+    pub sandbox: Sandbox,
+    /// Keep running and check a package again whenever its PKGBUILD or .SRCINFO changes, only for paths and `--scan-directory`
+    #[arg(long)]
+    pub watch: bool,
+    /// Disable all network access, checks that need it are reported as skipped and packages that need to be downloaded fail
+    #[arg(long)]
+    pub offline: bool,
+    /// Read the checksum policy, defaults and other settings from this toml file [default: ~/.config/archlinux-inputs-fsck/config.toml]
+    #[arg(long, value_name = "PATH", value_parser = parse_config)]
+    pub config: Option<Config>,
+    /// Compare sources with the metadata published by package registries, this sends requests to eg. pypi.org
+    #[arg(long)]
+    pub check_registries: bool,
+    /// Lookup the fingerprints in validpgpkeys on keyservers and report keys that are expired, revoked or missing
+    #[arg(long)]
+    pub check_keys: bool,
+    /// The keyserver to use for `--check-keys`, can be used multiple times
+    #[arg(long, value_name = "URL", default_value = pgp::DEFAULT_KEYSERVER)]
+    pub keyserver: Vec<String>,
     /// Filter only for specific findings
     #[arg(
         short,
@@ -53,11 +189,71 @@ pub struct Check {
         value_parser(PossibleValuesParser::new(Finding::VARIANTS))
     )]
     pub filters: Vec<String>,
+    /// Hide findings below this severity
+    #[arg(long, value_enum, value_name = "SEVERITY")]
+    pub min_severity: Option<Severity>,
     /// Print package names with findings to stdout
     #[arg(short, long)]
     pub report: bool,
+    /// The template for `--format template`, eg. '{target}: {kind} ({severity})'. Supported variables: target, pkgbase, kind, severity, message, description, url
+    #[arg(long)]
+    pub template: Option<Template>,
+    /// Read the template for `--format template` from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "template")]
+    pub template_file: Option<PathBuf>,
+    /// Exit with status 3 if a finding of this type or at least this severity was reported, packages that failed to be checked result in status 4 (even if there are also matching findings)
+    #[arg(long, value_name = "FINDING|SEVERITY", value_parser = parse_fail_on)]
+    pub fail_on: Vec<FailOn>,
+    /// Print a summary of the scan once all packages have been checked
+    #[arg(long)]
+    pub summary: bool,
+    /// Only report findings that are not part of this baseline file
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+    /// Write all current findings to the baseline file instead of hiding known ones
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
+    /// Suppress known findings listed in this toml file, with a reason and an optional expiry date
+    #[arg(long, value_name = "PATH")]
+    pub suppressions: Option<PathBuf>,
+    /// Write the findings of each package into a separate file in this directory, using json with `--format json` and text otherwise
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+    /// Reuse the results of packages whose PKGBUILD, local files and options didn't change since they were cached in this directory
+    #[arg(long, value_name = "DIR")]
+    pub cache: Option<PathBuf>,
+    /// Record the findings of this run in a sqlite database
+    #[arg(long, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+    /// Write the result of every checked package to this file as soon as it's done, see `--resume`
+    #[arg(long, value_name = "PATH", conflicts_with = "watch")]
+    pub checkpoint: Option<PathBuf>,
+    /// Skip packages that were already checked according to `--checkpoint`, to continue an interrupted scan
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+    /// Only check the packages of `--scan-directory` that changed since the commit recorded in this
+    /// file, the scanned commits are recorded if all packages could be checked
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "scan_directory",
+        conflicts_with = "watch"
+    )]
+    pub incremental: Option<PathBuf>,
+    /// Expose prometheus metrics about the scan on this address (eg. 127.0.0.1:9090)
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_listen: Option<SocketAddr>,
+    /// Compare the findings with a previous run in the database and report them as new, fixed or unchanged
+    #[arg(long, value_name = "RUN_ID", requires = "db")]
+    pub diff_against: Option<i64>,
+    /// The output format to use for findings
+    #[arg(long, value_enum, default_value_t = Format::Text, conflicts_with = "report")]
+    pub format: Format,
     #[arg(short = 'j', long)]
     pub concurrency: Option<usize>,
+    /// Give up on a package if checking it (checkout, evaluating the PKGBUILD and network checks) takes longer than this many seconds, it's reported as timed out
+    #[arg(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -72,18 +268,552 @@ pub struct Vulns {
     pub check: Check,
 }
 
-fn read_pkgs_from_dir(out: &mut VecDeque<Target>, path: &Path) -> Result<()> {
-    for entry in fs::read_dir(path)? {
-        let entry = entry?;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailOn {
+    Finding(String),
+    Severity(Severity),
+}
+
+impl FailOn {
+    pub fn matches(&self, finding: &Finding) -> bool {
+        match self {
+            FailOn::Finding(key) => {
+                let kind: &'static str = finding.into();
+                kind == key
+            }
+            FailOn::Severity(severity) => finding.severity() >= *severity,
+        }
+    }
+}
+
+fn parse_config(s: &str) -> Result<Config> {
+    // clap only displays the outermost error, include the causes too
+    Config::load(Path::new(s)).map_err(|err| anyhow!("{:#}", err))
+}
+
+fn parse_fail_on(s: &str) -> Result<FailOn> {
+    if let Ok(severity) = Severity::from_str(s, false) {
+        Ok(FailOn::Severity(severity))
+    } else if Finding::VARIANTS.contains(&s) {
+        Ok(FailOn::Finding(s.to_string()))
+    } else {
+        bail!("Expected a finding type or a severity (info, low, medium, high)")
+    }
+}
+
+/// The exit status of a scan, used to make the process usable as a CI gate
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub failed: bool,
+    pub errors: usize,
+    /// The packages that failed to be checked, they only count as errors with `--fail-on`
+    pub unchecked: usize,
+}
+
+impl Outcome {
+    pub const EXIT_FINDINGS: i32 = 3;
+    pub const EXIT_ERRORS: i32 = 4;
+
+    /// Errors take precedence over findings, so packages that couldn't be checked are never hidden
+    pub fn exit_code(&self) -> i32 {
+        if self.errors > 0 {
+            Self::EXIT_ERRORS
+        } else if self.failed {
+            Self::EXIT_FINDINGS
+        } else {
+            0
+        }
+    }
+}
+
+/// Generate a CycloneDX sbom of the inputs declared in a PKGBUILD
+#[derive(Debug, Clone, Parser)]
+pub struct Sbom {
+    /// Directories that contain a PKGBUILD or `.src.tar.gz` source packages, use `-` to read a PKGBUILD from stdin
+    pub paths: Vec<PathBuf>,
+    /// Checkout PKGBUILD of an official package into a temporary directory, see `--backend`
+    #[arg(short = 'B', long, value_name = "PKG_NAME")]
+    pub arch_build_system: Vec<String>,
+    /// The tool used to checkout packages for `--arch-build-system`
+    #[arg(long, value_enum, default_value_t = asp::Backend::Auto)]
+    pub backend: asp::Backend,
+    /// Checkout this version (eg. 6.1.1-1) of the packages for `--arch-build-system` instead of the latest, not supported by asp
+    #[arg(long, value_name = "VERSION")]
+    pub package_version: Option<String>,
+    /// Check paths in a git repository as they are in this commit, tag or branch instead of the working tree
+    #[arg(long, value_name = "REF")]
+    pub rev: Option<String>,
+    /// Clone the PKGBUILD of an AUR package into a temporary directory
+    #[arg(long, value_name = "PKG_NAME")]
+    pub aur: Vec<String>,
+    /// How to isolate the PKGBUILD while it's sourced with bash
+    #[arg(long, value_enum, default_value_t = Sandbox::Auto)]
+    pub sandbox: Sandbox,
+    /// Write one document per pkgbase into this directory, this is required for more than one package
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Sbom {
+    pub fn checkout_options(&self) -> fsck::CheckoutOptions {
+        fsck::CheckoutOptions {
+            backend: self.backend,
+            package_version: self.package_version.clone(),
+            rev: self.rev.clone(),
+            offline: false,
+        }
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let targets = self
+            .arch_build_system
+            .iter()
+            .map(|pkg| Target::ArchBuildSystem(pkg.clone()))
+            .chain(self.aur.iter().map(|pkg| Target::Aur(pkg.clone())))
+            .map(Ok)
+            .chain(self.paths.iter().map(|path| path_target(path)))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(dir) = &self.output_dir {
+            fs::create_dir_all(dir)
+                .with_context(|| anyhow!("Failed to create output directory: {:?}", dir))?;
+        } else if targets.len() > 1 {
+            bail!("Generating an sbom for multiple packages requires --output-dir");
+        }
+
+        let client = fsck::http_client()?;
+        for target in targets {
+            let (_temp_dir, path) =
+                fsck::checkout(&client, &target, &self.checkout_options()).await?;
+            let bom = sbom::generate(&path, self.sandbox)
+                .await
+                .with_context(|| anyhow!("Failed to generate sbom for {:?}", target.display()))?;
+
+            if let Some(dir) = &self.output_dir {
+                let path = dir.join(format!("{}.cdx.json", target.file_name()));
+                let json = serde_json::to_string_pretty(&bom)?;
+                fs::write(&path, json + "\n")
+                    .with_context(|| anyhow!("Failed to write sbom: {:?}", path))?;
+                info!("Wrote sbom to {:?}", path);
+            } else {
+                let json = serde_json::to_string_pretty(&bom)?;
+                println!("{}", json);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Check {
+    pub fn checkout_options(&self) -> fsck::CheckoutOptions {
+        fsck::CheckoutOptions {
+            backend: self.backend,
+            package_version: self.package_version.clone(),
+            rev: self.rev.clone(),
+            offline: self.offline,
+        }
+    }
+
+    /// Resolve the current commit of every `--scan-directory` for `--incremental`
+    pub async fn scan_directory_commits(&self) -> Result<Vec<(PathBuf, String)>> {
+        let mut commits = Vec::new();
+        if self.incremental.is_none() {
+            return Ok(commits);
+        }
+        let rev = self.rev.as_deref().unwrap_or("HEAD");
+        for dir in &self.scan_directory {
+            let commit = git::resolve_commit(dir, rev).await?;
+            commits.push((dir.clone(), commit));
+        }
+        Ok(commits)
+    }
+
+    /// Load the config file and use it for the options that weren't set on the command line
+    pub fn apply_config(&mut self) -> Result<()> {
+        if self.config.is_none() {
+            self.config = Config::load_default()?;
+        }
+        let Some(config) = &self.config else {
+            return Ok(());
+        };
+
+        if self.filters.is_empty() {
+            self.filters = config.filters.clone();
+        }
+        self.min_severity = self.min_severity.or(config.min_severity);
+        self.concurrency = self.concurrency.or(config.concurrency);
+        self.timeout = self.timeout.or(config.timeout);
+        if !self.srcinfo {
+            self.evaluator = self.evaluator.or(config.evaluator);
+        }
+        if !config.severity.is_empty() {
+            fsck::override_severity(config.severity.clone())?;
+        }
+        Ok(())
+    }
+
+    pub fn fsck(&self, client: &reqwest::Client) -> fsck::Fsck {
+        let builder = fsck::Fsck::builder()
+            .client(client.clone())
+            .discover_sigs(self.discover_sigs)
+            .github_token(self.github_token.clone())
+            .check_registries(self.check_registries)
+            .check_keys(self.check_keys)
+            .keyservers(self.keyserver.clone())
+            .probe_https(self.probe_https)
+            .check_availability(self.check_availability)
+            .verify_sums(self.verify_sums)
+            .verify_git_pins(self.verify_git_pins)
+            .verify_signed_tags(self.verify_signed_tags)
+            .check_submodules(self.check_submodules)
+            .checksum_policy(
+                self.config
+                    .as_ref()
+                    .map(|config| config.checksums.clone())
+                    .unwrap_or_default(),
+            )
+            .backend(self.backend)
+            .package_version(self.package_version.clone())
+            .rev(self.rev.clone())
+            .offline(self.offline)
+            .evaluator(if self.srcinfo {
+                Evaluator::Srcinfo
+            } else {
+                self.evaluator.unwrap_or_default()
+            })
+            .sandbox(self.sandbox)
+            .source_policy(
+                self.config
+                    .as_ref()
+                    .map(|config| config.sources.clone())
+                    .unwrap_or_default(),
+            );
+        if let Some(dir) = &self.cache {
+            builder.cache(Cache::new(dir.clone())).build()
+        } else {
+            builder.build()
+        }
+    }
+
+    pub fn load_template(&self) -> Result<Option<Template>> {
+        if self.format != Format::Template {
+            return Ok(None);
+        }
+
+        let template = if let Some(template) = &self.template {
+            template.clone()
+        } else if let Some(path) = &self.template_file {
+            let template = fs::read_to_string(path)
+                .with_context(|| anyhow!("Failed to read template file: {:?}", path))?;
+            template
+                .trim_end_matches('\n')
+                .parse()
+                .with_context(|| anyhow!("Failed to parse template file: {:?}", path))?
+        } else {
+            bail!("--format template requires --template or --template-file");
+        };
+
+        Ok(Some(template))
+    }
+
+    /// Collect all targets selected on the command line
+    pub async fn targets(&self, client: &reqwest::Client) -> Result<VecDeque<Target>> {
+        if self.offline && (self.aur_all || !self.maintainer.is_empty()) {
+            bail!(
+                "--aur-all and --maintainer need network access, they can't be used with --offline"
+            );
+        }
+        if self.offline && self.state == Some(None) {
+            bail!("Cloning the state repository needs network access, use `--state <PATH>` with an existing checkout");
+        }
+
+        let mut queue = VecDeque::new();
+
+        let history = self.incremental.as_deref().map(History::load).transpose()?;
+        for dir in &self.scan_directory {
+            let mut pkgs = VecDeque::new();
+            read_pkgs_from_dir(&mut pkgs, dir, &self.exclude)
+                .context("Failed to scan directory for PKGBUILDs")?;
+
+            let since = history
+                .as_ref()
+                .map(|history| history.last_commit(dir))
+                .transpose()?
+                .flatten();
+            if let Some(since) = since {
+                let files = git::changed_files(dir, since, self.rev.as_deref()).await?;
+                let changed = incremental::changed_pkgbases(&files);
+                let total = pkgs.len();
+                pkgs.retain(|target| incremental::is_changed(dir, &changed, target));
+                info!(
+                    "Checking {} of {} packages in {:?} that changed since {}",
+                    pkgs.len(),
+                    total,
+                    dir,
+                    since
+                );
+            }
+            queue.extend(pkgs);
+        }
+
+        for pkg in &self.arch_build_system {
+            queue.push_back(Target::ArchBuildSystem(pkg.clone()));
+        }
+
+        if let Some(path) = &self.sync_db {
+            let pkgbases = pacman::list_pkgbases(path)?;
+            info!("Found {} pkgbases in sync databases", pkgbases.len());
+            for pkg in pkgbases {
+                queue.push_back(Target::ArchBuildSystem(pkg));
+            }
+        }
+
+        if let Some(path) = &self.state {
+            let (_temp_dir, path) = if let Some(path) = path {
+                (None, path.clone())
+            } else {
+                let tmp = tempfile::Builder::new()
+                    .prefix("archlinux-inputs-fsck")
+                    .tempdir()?;
+                let path = state::clone(tmp.path()).await?;
+                (Some(tmp), path)
+            };
+            let packages = state::read_state(&path)?;
+            info!("Found {} pkgbases in state repository", packages.len());
+            for (pkgbase, state) in packages {
+                queue.push_back(Target::ArchBuildSystemRelease {
+                    pkgbase,
+                    version: state.version,
+                });
+            }
+        }
+
+        if !self.with_deps.is_empty() {
+            let sync_dir = Path::new(pacman::SYNC_DIR);
+            let mut resolver = if let Some(path) = &self.deps_db {
+                Resolver::from_packages(pacman::read_packages(path)?)
+            } else if sync_dir.exists() {
+                Resolver::from_packages(pacman::read_packages(sync_dir)?)
+            } else if self.offline {
+                bail!(
+                    "No sync databases in {:?}, resolving dependencies with archweb needs network access",
+                    sync_dir
+                );
+            } else {
+                info!(
+                    "No sync databases in {:?}, resolving dependencies with archweb",
+                    sync_dir
+                );
+                Resolver::archweb(client.clone())
+            };
+
+            let mut pkgbases = BTreeSet::new();
+            for pkg in &self.with_deps {
+                let closure = resolver.build_closure(pkg).await?;
+                info!(
+                    "Resolved {} pkgbases needed to build {:?}",
+                    closure.len(),
+                    pkg
+                );
+                pkgbases.extend(closure);
+            }
+            for pkg in pkgbases {
+                queue.push_back(Target::ArchBuildSystem(pkg));
+            }
+        }
+
+        if !self.maintainer.is_empty() {
+            let mut pkgbases = BTreeSet::new();
+            for maintainer in &self.maintainer {
+                let maintained = archweb::maintained_pkgbases(client, maintainer).await?;
+                info!(
+                    "Found {} pkgbases maintained by {:?}",
+                    maintained.len(),
+                    maintainer
+                );
+                pkgbases.extend(maintained);
+            }
+            for pkg in pkgbases {
+                queue.push_back(Target::ArchBuildSystem(pkg));
+            }
+        }
+
+        for pkg in &self.aur {
+            queue.push_back(Target::Aur(pkg.clone()));
+        }
+
+        if self.aur_all {
+            for pkg in aur::list_pkgbases(client).await? {
+                queue.push_back(Target::Aur(pkg));
+            }
+        }
+
+        for url in &self.url {
+            queue.push_back(Target::Url(url.clone()));
+        }
+
+        for path in &self.from_file {
+            let text = if path == Path::new("-") {
+                if self.paths.iter().any(|path| path == Path::new("-")) {
+                    bail!("Can't read both the PKGBUILD and the list of targets from stdin");
+                }
+                let mut text = String::new();
+                io::stdin()
+                    .read_to_string(&mut text)
+                    .context("Failed to read targets from stdin")?;
+                text
+            } else {
+                fs::read_to_string(path)
+                    .with_context(|| anyhow!("Failed to read targets file: {:?}", path))?
+            };
+
+            for (i, line) in text.lines().enumerate() {
+                let target = parse_target_line(line)
+                    .with_context(|| anyhow!("Invalid target in {:?} line {}", path, i + 1))?;
+                let Some(target) = target else {
+                    continue;
+                };
+                if is_excluded(&self.exclude, &target) {
+                    debug!("Skipping excluded target: {:?}", target.display());
+                    continue;
+                }
+                queue.push_back(target);
+            }
+        }
+
+        for path in &self.paths {
+            let target = path_target(path)?;
+            if is_excluded(&self.exclude, &target) {
+                debug!("Skipping excluded path: {:?}", path);
+                continue;
+            }
+            queue.push_back(target);
+        }
+
+        Ok(queue)
+    }
+}
+
+/// Parse a line of a `--from-file` targets file, eg. `./paru`, `linux version=6.1.1.arch1-1`,
+/// `aur/paru` or `https://example.com/PKGBUILD`, empty lines and comments are skipped
+fn parse_target_line(line: &str) -> Result<Option<Target>> {
+    let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+    let mut fields = line.split_whitespace();
+    let Some(name) = fields.next() else {
+        return Ok(None);
+    };
+
+    let mut version = None;
+    for option in fields {
+        match option.split_once('=') {
+            Some(("version", value)) => version = Some(value.to_string()),
+            _ => bail!("Unknown option: {:?}", option),
+        }
+    }
+
+    let target = if name.starts_with("https://") || name.starts_with("http://") {
+        Target::Url(name.to_string())
+    } else if let Some(pkg) = name.strip_prefix("aur/") {
+        Target::Aur(pkg.to_string())
+    } else if name.contains('/')
+        || name.starts_with('.')
+        || srcpkg::is_source_package(name.as_ref())
+    {
+        if name == "-" {
+            bail!("Reading a PKGBUILD from stdin is not supported in a targets file");
+        }
+        path_target(Path::new(name))?
+    } else if let Some(version) = version.take() {
+        Target::ArchBuildSystemRelease {
+            pkgbase: name.to_string(),
+            version,
+        }
+    } else {
+        Target::ArchBuildSystem(name.to_string())
+    };
+
+    if version.is_some() {
+        bail!("The version option is only supported for official packages");
+    }
+    Ok(Some(target))
+}
+
+/// Turn a path argument into a target, `-` reads the PKGBUILD from stdin and `.src.tar.gz` files are extracted
+fn path_target(path: &Path) -> Result<Target> {
+    if path == Path::new("-") {
+        let mut pkgbuild = String::new();
+        io::stdin()
+            .read_to_string(&mut pkgbuild)
+            .context("Failed to read PKGBUILD from stdin")?;
+        Ok(Target::Stdin(pkgbuild))
+    } else if srcpkg::is_source_package(path) {
+        Ok(Target::SourcePackage(path.to_path_buf()))
+    } else {
+        Ok(Target::BuildPath(path.to_path_buf()))
+    }
+}
+
+/// Find the PKGBUILDs of a package directory, they're either at the top (AUR,
+/// gitlab), in `trunk/` or in `repos/*/` (svntogit)
+fn package_build_paths(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.join("PKGBUILD").exists() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let trunk = path.join("trunk");
+    if trunk.join("PKGBUILD").exists() {
+        return Ok(vec![trunk]);
+    }
+
+    let mut paths = Vec::new();
+    let repos = path.join("repos");
+    if repos.is_dir() {
+        for entry in fs::read_dir(&repos)? {
+            let path = entry?.path();
+            if path.join("PKGBUILD").exists() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+    }
+    Ok(paths)
+}
+
+/// Check if the pkgbase or path of a target matches any of the `--exclude` globs
+pub fn is_excluded(exclude: &[Pattern], target: &Target) -> bool {
+    let pkgbase = target.pkgbase();
+    let display = target.display();
+    exclude
+        .iter()
+        .any(|pattern| pattern.matches(&pkgbase) || pattern.matches(&display))
+}
+
+fn read_pkgs_from_dir(out: &mut VecDeque<Target>, path: &Path, exclude: &[Pattern]) -> Result<()> {
+    let mut entries = fs::read_dir(path)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
         let filename = entry
             .file_name()
             .into_string()
             .map_err(|_| anyhow!("Failed to convert directory name to string"))?;
-        if filename == ".git" {
+        if filename.starts_with('.') || !entry.path().is_dir() {
             continue;
         }
-        let path = entry.path().join("trunk");
-        out.push_back(Target::BuildPath(path));
+
+        let paths = package_build_paths(&entry.path())?;
+        if paths.is_empty() {
+            warn!("Directory contains no PKGBUILD: {:?}", entry.path());
+        }
+        for path in paths {
+            let target = Target::BuildPath(path);
+            if is_excluded(exclude, &target) {
+                debug!("Skipping excluded package: {:?}", target.display());
+                continue;
+            }
+            out.push_back(target);
+        }
     }
 
     Ok(())
@@ -94,37 +824,143 @@ pub trait Scan: Send + Clone
 where
     Self: 'static,
 {
-    async fn scan(&self, target: &Target) -> Result<Vec<Finding>>;
+    async fn scan(&self, client: &reqwest::Client, target: &Target) -> Result<PackageFindings>;
 
-    async fn run(&self, check: &Check) -> Result<()> {
-        let mut queue = VecDeque::new();
+    async fn run(&self, check: &Check) -> Result<Outcome> {
+        // one client for the whole run, so connections are reused across packages
+        let client = fsck::http_client()?;
 
-        for dir in &check.scan_directory {
-            read_pkgs_from_dir(&mut queue, dir)
-                .context("Failed to scan directory for PKGBUILDs")?;
-        }
+        // resolve the commits before scanning, changes that are committed meanwhile are checked next time
+        let commits = check.scan_directory_commits().await?;
+        let queue = check.targets(&client).await?;
 
-        for pkg in &check.arch_build_system {
-            queue.push_back(Target::ArchBuildSystem(pkg.clone()));
+        METRICS.scan_started();
+        if let Some(addr) = check.metrics_listen {
+            metrics::listen(addr).await?;
         }
 
-        for path in &check.paths {
-            queue.push_back(Target::BuildPath(path.clone()));
+        let outcome = self.run_targets(check, &client, queue).await?;
+
+        if let Some(path) = &check.incremental {
+            if outcome.unchecked > 0 {
+                warn!(
+                    "Not recording the scanned commits, {} packages failed to be checked",
+                    outcome.unchecked
+                );
+            } else {
+                let mut history = History::load(path)?;
+                for (dir, commit) in commits {
+                    history.record(&dir, commit)?;
+                }
+                history.save(path)?;
+            }
         }
 
+        Ok(outcome)
+    }
+
+    /// Check the given targets with bounded concurrency and report the findings
+    async fn run_targets(
+        &self,
+        check: &Check,
+        client: &reqwest::Client,
+        mut queue: VecDeque<Target>,
+    ) -> Result<Outcome> {
         let filters = HashSet::<String>::from_iter(check.filters.iter().cloned());
+        let template = check.load_template()?;
 
+        let mut outcome = Outcome::default();
+        let mut report = Report::default();
+        let mut stats = Stats::default();
+        let mut renderer = Renderer::new();
+        if check.output_dir.is_some() || check.diff_against.is_some() {
+            renderer = renderer.hide_packages();
+        }
+        let mut db = check.db.as_deref().map(Database::open).transpose()?;
+        let baseline = match &check.baseline {
+            Some(path) if !check.update_baseline => Some(Baseline::load(path)?),
+            _ => None,
+        };
+        let mut new_baseline = check.update_baseline.then(Baseline::default);
+        let suppressions = check
+            .suppressions
+            .as_deref()
+            .map(SuppressionFile::load)
+            .transpose()?;
+        let mut checkpoint = check
+            .checkpoint
+            .as_deref()
+            .map(|path| Checkpoint::open(path, check.resume))
+            .transpose()?;
+        if let Some(checkpoint) = &checkpoint {
+            let total = queue.len();
+            queue.retain(|target| !checkpoint.is_completed(target));
+            if total > queue.len() {
+                info!(
+                    "Skipping {} packages that were already checked according to the checkpoint",
+                    total - queue.len()
+                );
+            }
+            if checkpoint.completed().any(|entry| entry.failed) {
+                outcome.failed = true;
+            }
+        }
+        if let Some(dir) = &check.output_dir {
+            fs::create_dir_all(dir)
+                .with_context(|| anyhow!("Failed to create output directory: {:?}", dir))?;
+        }
+
+        let mut progress = Progress::new(queue.len());
         let mut pool = JoinSet::new();
 
         let concurrency = check.concurrency.unwrap_or_else(|| num_cpus::get() * 2);
+        let check_timeout = check.timeout.map(Duration::from_secs);
+        let mut clone_interval = (check.aur_clone_interval > 0).then(|| {
+            let mut interval = time::interval(Duration::from_millis(check.aur_clone_interval));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let temp_dir = env::temp_dir();
         loop {
-            while pool.len() < concurrency {
+            while pool.len() < concurrency && !queue.is_empty() {
+                if let Some(min_free_space) = check.min_free_space {
+                    let available = disk::available_space(&temp_dir)?;
+                    if available < min_free_space * 1024 * 1024 {
+                        if !pool.is_empty() {
+                            // wait for running checks to release their checkouts
+                            break;
+                        }
+                        warn!(
+                            "Only {} MiB of free space left in {:?}, continuing one package at a time",
+                            available / 1024 / 1024,
+                            temp_dir
+                        );
+                    }
+                }
+
                 if let Some(target) = queue.pop_front() {
+                    if let (Target::Aur(_), Some(interval)) = (&target, &mut clone_interval) {
+                        interval.tick().await;
+                    }
                     // pkg, work_dir
                     let check = self.clone();
+                    let client = client.clone();
+                    let timeout = check_timeout;
                     pool.spawn(async move {
-                        let findings = check.scan(&target).await;
-                        (target, findings)
+                        let started = Instant::now();
+                        let findings = match timeout {
+                            Some(timeout) => time::timeout(timeout, check.scan(&client, &target))
+                                .await
+                                .with_context(|| {
+                                    anyhow!(
+                                        "Checking the package timed out after {}s",
+                                        timeout.as_secs()
+                                    )
+                                })
+                                .and_then(|findings| findings),
+                            None => check.scan(&client, &target).await,
+                        };
+                        (target, findings, started.elapsed())
                     });
                 } else {
                     // no more tasks to schedule
@@ -133,62 +969,161 @@ where
             }
 
             if let Some(join) = pool.join_next().await {
-                let (target, findings) = join.context("Failed to join task")?;
+                let (target, findings, duration) = join.context("Failed to join task")?;
+                progress.clear();
+                progress.finished_one();
                 match findings {
-                    Ok(findings) => {
-                        let has_findings = Finding::audit_list(&target, &findings, &filters);
+                    Ok(mut pkg) => {
+                        let kinds = pkg.findings.iter().map(|f| f.into()).collect::<Vec<_>>();
+                        METRICS.package_checked(duration, &kinds);
+
+                        if let Some(suppressions) = &suppressions {
+                            suppressions.apply(&target, &mut pkg);
+                        }
+                        pkg.findings
+                            .retain(|finding| finding.is_selected(&filters, check.min_severity));
+                        pkg.suppressed.retain(|suppressed| {
+                            suppressed.finding.is_selected(&filters, check.min_severity)
+                        });
+
+                        if let Some(db) = &mut db {
+                            db.insert_findings(&target, &pkg.findings)?;
+                        }
 
-                        if check.report && has_findings {
+                        if let Some(new_baseline) = &mut new_baseline {
+                            new_baseline.record(&target, &pkg.findings)?;
+                        }
+                        if let Some(baseline) = &baseline {
+                            pkg.findings = baseline.retain_new(&target, pkg.findings)?;
+                        }
+
+                        let failed = pkg
+                            .findings
+                            .iter()
+                            .any(|finding| check.fail_on.iter().any(|f| f.matches(finding)));
+                        if failed {
+                            outcome.failed = true;
+                        }
+                        if let Some(checkpoint) = &mut checkpoint {
+                            checkpoint.record(&target, Ok(&pkg.findings), failed)?;
+                        }
+
+                        stats.add_findings(&pkg.findings);
+                        stats.add_suppressed(&pkg.suppressed);
+
+                        if check.report && !pkg.findings.is_empty() {
                             println!("{}", target.display());
                         }
+
+                        if let Some(dir) = &check.output_dir {
+                            report::write_package_file(dir, check.format, &target, Ok(&pkg))?;
+                        }
+
+                        match check.format {
+                            Format::Text => renderer.package(&target, &pkg.findings),
+                            Format::Ndjson => {
+                                report::write_ndjson(io::stdout().lock(), &target, Ok(&pkg))?
+                            }
+                            Format::Template => {
+                                if let Some(template) = &template {
+                                    template.write(io::stdout().lock(), &target, &pkg.findings)?;
+                                }
+                            }
+                            _ => report.add_findings(&target, pkg),
+                        }
                     }
                     Err(err) => {
                         error!("Failed to check package: {:?} => {:#}", target, err);
+                        METRICS.package_failed(duration);
+                        if let Some(dir) = &check.output_dir {
+                            report::write_package_file(dir, check.format, &target, Err(&err))?;
+                        }
+                        if check.format == Format::Ndjson {
+                            report::write_ndjson(io::stdout().lock(), &target, Err(&err))?;
+                        } else {
+                            report.add_error(&target, &err);
+                        }
+                        stats.add_error(&err);
+                        renderer.error();
+                        if let Some(db) = &mut db {
+                            db.insert_error(&target, &err)?;
+                        }
+                        if let Some(checkpoint) = &mut checkpoint {
+                            checkpoint.record(&target, Err(&err), false)?;
+                        }
+                        outcome.unchecked += 1;
+                        if !check.fail_on.is_empty() {
+                            outcome.errors += 1;
+                        }
                     }
                 }
             } else {
                 // no more tasks in pool
                 break;
             }
+
+            progress.draw(pool.len());
         }
+        progress.clear();
 
-        Ok(())
+        if let Some(db) = &mut db {
+            db.finish()?;
+
+            if let Some(against) = check.diff_against {
+                let diff = db.diff(against)?;
+                if check.format == Format::Json {
+                    report.diff = Some(diff);
+                } else {
+                    diff.print();
+                }
+            }
+        }
+
+        if let (Some(path), Some(new_baseline)) = (&check.baseline, new_baseline) {
+            new_baseline.save(path)?;
+        }
+
+        if check.format == Format::Text {
+            renderer.finish();
+        }
+
+        if check.summary {
+            if check.format == Format::Json {
+                report.summary = Some(stats);
+            } else {
+                stats.print();
+            }
+        }
+
+        report.print(check.format)?;
+
+        Ok(outcome)
     }
 }
 
 #[async_trait]
 impl Scan for Check {
-    async fn scan(&self, target: &Target) -> Result<Vec<Finding>> {
-        info!("Checking {:?}", target.display());
-        let findings = fsck::check_pkg(target, self.discover_sigs).await?;
+    async fn scan(&self, client: &reqwest::Client, target: &Target) -> Result<PackageFindings> {
+        debug!("Checking {:?}", target.display());
+        let findings = self.fsck(client).check(target).await?;
         Ok(findings)
     }
 }
 
 #[async_trait]
 impl Scan for Vulns {
-    async fn scan(&self, target: &Target) -> Result<Vec<Finding>> {
-        info!("Scanning {:?}", target.display());
+    async fn scan(&self, client: &reqwest::Client, target: &Target) -> Result<PackageFindings> {
+        debug!("Scanning {:?}", target.display());
+        if self.check.offline {
+            bail!("Scanning for vulnerabilities needs network access to download sources and query osv.dev");
+        }
 
-        let (_temp_dir, path) = match &target {
-            Target::ArchBuildSystem(pkg) => {
-                let tmp = tempfile::Builder::new()
-                    .prefix("archlinux-inputs-fsck")
-                    .tempdir()?;
-                let path = asp::checkout_package(tmp.path(), pkg).await?;
-                (Some(tmp), path)
-            }
-            Target::BuildPath(path) => (None, PathBuf::from(path)),
-        };
+        let (_temp_dir, path) =
+            fsck::checkout(client, target, &self.check.checkout_options()).await?;
 
         let resolved_working_dir = fs::canonicalize(&path)
             .with_context(|| anyhow!("Failed to resolve path to a canonical path: {:?}", path))?;
 
-        let pkgbuild_path = path.join("PKGBUILD");
-        if !pkgbuild_path.exists() {
-            bail!("Missing PKGBUILD: {:?}", pkgbuild_path);
-        }
-
         let makepkg_args = if self.prepare {
             vec!["--skippgpcheck", "--nobuild"]
         } else {
@@ -198,6 +1133,7 @@ impl Scan for Vulns {
         let mut child = Command::new("makepkg")
             .args(&makepkg_args)
             .current_dir(&path)
+            .kill_on_drop(true)
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
@@ -212,6 +1148,7 @@ impl Scan for Vulns {
             .arg("--json")
             .arg("-r")
             .arg(&resolved_working_dir)
+            .kill_on_drop(true)
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
             .spawn()
@@ -240,6 +1177,7 @@ impl Scan for Vulns {
             let status = Command::new("git")
                 .args(["clean", "-qdfx", "."])
                 .current_dir(&path)
+                .kill_on_drop(true)
                 .spawn()
                 .context("Failed to spawn git")?
                 .wait()
@@ -251,6 +1189,88 @@ impl Scan for Vulns {
             }
         }
 
-        Ok(findings)
+        Ok(findings.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target_line() -> Result<()> {
+        assert_eq!(parse_target_line("")?, None);
+        assert_eq!(parse_target_line("  # a comment")?, None);
+        assert_eq!(
+            parse_target_line("linux")?,
+            Some(Target::ArchBuildSystem("linux".to_string()))
+        );
+        assert_eq!(
+            parse_target_line("linux version=6.1.1.arch1-1 # pinned")?,
+            Some(Target::ArchBuildSystemRelease {
+                pkgbase: "linux".to_string(),
+                version: "6.1.1.arch1-1".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_target_line("aur/paru")?,
+            Some(Target::Aur("paru".to_string()))
+        );
+        assert_eq!(
+            parse_target_line("./paru")?,
+            Some(Target::BuildPath(PathBuf::from("./paru")))
+        );
+        assert_eq!(
+            parse_target_line("paru-2.0.4-1.src.tar.gz")?,
+            Some(Target::SourcePackage(PathBuf::from(
+                "paru-2.0.4-1.src.tar.gz"
+            )))
+        );
+        assert_eq!(
+            parse_target_line("https://example.com/PKGBUILD")?,
+            Some(Target::Url("https://example.com/PKGBUILD".to_string()))
+        );
+        assert!(parse_target_line("aur/paru version=2.0.4-1").is_err());
+        assert!(parse_target_line("linux rev=main").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_pkgs_from_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for path in [
+            "aur/PKGBUILD",
+            "svn/trunk/PKGBUILD",
+            "svn/repos/extra-x86_64/PKGBUILD",
+            "repos-only/repos/core-x86_64/PKGBUILD",
+            "repos-only/repos/testing-x86_64/PKGBUILD",
+            "empty/README",
+            ".git/HEAD",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, "")?;
+        }
+
+        let mut out = VecDeque::new();
+        read_pkgs_from_dir(&mut out, dir.path(), &[])?;
+        assert_eq!(
+            out.into_iter().collect::<Vec<_>>(),
+            &[
+                Target::BuildPath(dir.path().join("aur")),
+                Target::BuildPath(dir.path().join("repos-only/repos/core-x86_64")),
+                Target::BuildPath(dir.path().join("repos-only/repos/testing-x86_64")),
+                Target::BuildPath(dir.path().join("svn/trunk")),
+            ]
+        );
+
+        let mut out = VecDeque::new();
+        let exclude = ["repos-*".parse()?, "*/svn/*".parse()?];
+        read_pkgs_from_dir(&mut out, dir.path(), &exclude)?;
+        assert_eq!(
+            out.into_iter().collect::<Vec<_>>(),
+            &[Target::BuildPath(dir.path().join("aur"))]
+        );
+        Ok(())
     }
 }
@@ -1,12 +1,86 @@
 use crate::errors::*;
+use crate::packaging;
+use crate::pkgctl;
+use clap::ValueEnum;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::process::Command;
 
+/// The tool used to fetch the PKGBUILD of an official package
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Try pkgctl, then gitlab and fall back to asp if they fail
+    #[default]
+    Auto,
+    /// Clone the packaging repository from gitlab.archlinux.org with pkgctl from devtools
+    Pkgctl,
+    /// Download the files of the packaging repository with the gitlab.archlinux.org api, this needs no external tools
+    Gitlab,
+    /// Checkout the package from the deprecated svntogit mirrors with asp
+    Asp,
+}
+
+impl Backend {
+    /// The backends to try, in order
+    fn candidates(self) -> &'static [Backend] {
+        match self {
+            Backend::Auto => &[Backend::Pkgctl, Backend::Gitlab, Backend::Asp],
+            Backend::Pkgctl => &[Backend::Pkgctl],
+            Backend::Gitlab => &[Backend::Gitlab],
+            Backend::Asp => &[Backend::Asp],
+        }
+    }
+}
+
+/// Fetch the PKGBUILD of an official package, optionally at a specific version (eg. `6.1.1-1`)
+pub async fn checkout(
+    client: &reqwest::Client,
+    backend: Backend,
+    version: Option<&str>,
+    directory: &Path,
+    pkgbase: &str,
+) -> Result<PathBuf> {
+    let mut errors = Vec::new();
+    for backend in backend.candidates() {
+        let result = match backend {
+            Backend::Pkgctl => pkgctl::checkout_package(directory, pkgbase, version).await,
+            Backend::Gitlab => {
+                packaging::checkout_package(client, directory, pkgbase, version).await
+            }
+            Backend::Asp if version.is_some() => Err(anyhow!(
+                "asp does not support checking out a specific version"
+            )),
+            Backend::Asp => checkout_package(directory, pkgbase).await,
+            Backend::Auto => continue,
+        };
+
+        match result {
+            Ok(path) => return Ok(path),
+            Err(err) => {
+                debug!("Checkout with {:?} failed: {:#}", backend, err);
+                errors.push(err);
+                // remove a partial checkout so the next backend has the directory available
+                let _ = fs::remove_dir_all(directory.join(pkgbase));
+            }
+        }
+    }
+
+    if errors.len() == 1 {
+        return Err(errors.remove(0));
+    }
+    let errors = errors
+        .iter()
+        .map(|err| format!("{:#}", err))
+        .collect::<Vec<_>>();
+    bail!("All checkout backends failed: {}", errors.join(", "))
+}
+
 pub async fn checkout_package(directory: &Path, pkgbase: &str) -> Result<PathBuf> {
     debug!("Checkout out {:?} to {:?}", pkgbase, directory);
     let cmd = Command::new("asp")
         .args(["checkout", pkgbase])
+        .kill_on_drop(true)
         // TODO: find a better way to make it silent without discarding stderr
         .stderr(Stdio::null())
         .current_dir(directory)
@@ -0,0 +1,126 @@
+use crate::errors::*;
+use flate2::read::GzDecoder;
+use reqwest::Client;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+pub const AUR_URL: &str = "https://aur.archlinux.org";
+
+/// Only accept characters the AUR allows in a pkgbase, so the name can't change the meaning of the url or the git command
+pub fn is_valid_pkgbase(pkgbase: &str) -> bool {
+    !pkgbase.is_empty()
+        && !pkgbase.starts_with(['-', '.'])
+        && pkgbase
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c))
+}
+
+/// Parse the `pkgbase.gz` metadata archive, a list with one pkgbase per line
+pub fn parse_pkgbase_list(bytes: &[u8]) -> Result<Vec<String>> {
+    let mut text = String::new();
+    // the http client may have already decoded the file if it was served with `Content-Encoding: gzip`
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        GzDecoder::new(bytes)
+            .read_to_string(&mut text)
+            .context("Failed to decompress AUR package list")?;
+    } else {
+        text = String::from_utf8(bytes.to_vec()).context("AUR package list is not utf-8")?;
+    }
+
+    let mut pkgbases = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !is_valid_pkgbase(line) {
+            warn!("Skipping invalid AUR package name: {:?}", line);
+            continue;
+        }
+        pkgbases.push(line.to_string());
+    }
+    Ok(pkgbases)
+}
+
+/// Download the list of all pkgbases in the AUR
+pub async fn list_pkgbases(client: &Client) -> Result<Vec<String>> {
+    let url = format!("{}/pkgbase.gz", AUR_URL);
+    info!("Downloading AUR package list from {:?}", url);
+    let bytes = client
+        .get(&url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let pkgbases = parse_pkgbase_list(&bytes)?;
+    info!("Found {} packages in the AUR", pkgbases.len());
+    Ok(pkgbases)
+}
+
+pub fn clone_url(pkgbase: &str) -> String {
+    format!("{}/{}.git", AUR_URL, pkgbase)
+}
+
+pub async fn checkout_package(directory: &Path, pkgbase: &str) -> Result<PathBuf> {
+    if !is_valid_pkgbase(pkgbase) {
+        bail!("Invalid AUR package name: {:?}", pkgbase);
+    }
+
+    let url = clone_url(pkgbase);
+    debug!("Cloning {:?} to {:?}", url, directory);
+    let cmd = Command::new("git")
+        .args(["clone", "--depth=1", "--quiet", &url, pkgbase])
+        .kill_on_drop(true)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .current_dir(directory)
+        .spawn()
+        .with_context(|| anyhow!("Failed to run git clone {:?}", url))?;
+
+    let out = cmd.wait_with_output().await?;
+    if !out.status.success() {
+        bail!(
+            "Process (git clone {:?}) exited with error: {:?}",
+            url,
+            out.status
+        );
+    }
+
+    // the AUR has no trunk/ or repos/ directories, the PKGBUILD is at the top
+    Ok(directory.join(pkgbase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_pkgbase() {
+        assert!(is_valid_pkgbase("paru"));
+        assert!(is_valid_pkgbase("python-foo_bar+baz@1.2"));
+        assert!(!is_valid_pkgbase(""));
+        assert!(!is_valid_pkgbase("--upload-pack=touch"));
+        assert!(!is_valid_pkgbase("../paru"));
+        assert!(!is_valid_pkgbase("foo/bar"));
+        assert!(!is_valid_pkgbase("Paru"));
+    }
+
+    #[test]
+    fn test_parse_pkgbase_list() -> Result<()> {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let text = "# AUR package base list, generated on Thu, 15 Oct 2026 09:00:00 GMT\nparu\nyay\n\n../evil\nzoxide-git\n";
+        let expected = vec!["paru", "yay", "zoxide-git"];
+        assert_eq!(parse_pkgbase_list(text.as_bytes())?, expected);
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(text.as_bytes())?;
+        assert_eq!(parse_pkgbase_list(&gz.finish()?)?, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use crate::errors::*;
+use crate::fsck::{Finding, Target};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BaselineFile {
+    findings: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    target: String,
+    finding: Value,
+}
+
+impl Entry {
+    fn new(target: &Target, finding: &Finding) -> Result<Entry> {
+        Ok(Entry {
+            target: target.display().into_owned(),
+            finding: serde_json::to_value(finding)?,
+        })
+    }
+
+    fn key(&self) -> (String, String) {
+        // serde_json keeps object keys sorted, this is stable across struct field reordering
+        (self.target.clone(), self.finding.to_string())
+    }
+}
+
+/// A set of already known findings, eg. to adopt the checks on a large repository
+#[derive(Debug, Default)]
+pub struct Baseline {
+    known: HashSet<(String, String)>,
+    recorded: Vec<Entry>,
+}
+
+impl Baseline {
+    /// Load a baseline file, a file that doesn't exist yet is treated as an empty baseline
+    pub fn load(path: &Path) -> Result<Baseline> {
+        let buf = match fs::read(path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("Baseline file {:?} doesn't exist yet", path);
+                return Ok(Baseline::default());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| anyhow!("Failed to read baseline: {:?}", path))
+            }
+        };
+
+        let file = serde_json::from_slice::<BaselineFile>(&buf)
+            .with_context(|| anyhow!("Failed to parse baseline: {:?}", path))?;
+        let known = file.findings.iter().map(Entry::key).collect();
+        Ok(Baseline {
+            known,
+            recorded: Vec::new(),
+        })
+    }
+
+    pub fn contains(&self, target: &Target, finding: &Finding) -> Result<bool> {
+        let key = Entry::new(target, finding)?.key();
+        Ok(self.known.contains(&key))
+    }
+
+    /// Remove all findings that are already part of the baseline
+    pub fn retain_new(&self, target: &Target, findings: Vec<Finding>) -> Result<Vec<Finding>> {
+        let mut new = Vec::new();
+        for finding in findings {
+            if !self.contains(target, &finding)? {
+                new.push(finding);
+            }
+        }
+        Ok(new)
+    }
+
+    pub fn record(&mut self, target: &Target, findings: &[Finding]) -> Result<()> {
+        for finding in findings {
+            self.recorded.push(Entry::new(target, finding)?);
+        }
+        Ok(())
+    }
+
+    /// Write all recorded findings to the baseline file
+    pub fn save(mut self, path: &Path) -> Result<()> {
+        self.recorded.sort_by_key(Entry::key);
+        let file = BaselineFile {
+            findings: self.recorded,
+        };
+        let json = serde_json::to_string_pretty(&file)?;
+        fs::write(path, json + "\n")
+            .with_context(|| anyhow!("Failed to write baseline: {:?}", path))?;
+        info!(
+            "Recorded {} findings in baseline {:?}",
+            file.findings.len(),
+            path
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use crate::errors::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagUrl {
+    pub owner: String,
+    pub name: String,
+    pub tag: String,
+}
+
+impl TagUrl {
+    pub fn clone_url(&self) -> String {
+        format!("https://bitbucket.org/{}/{}.git", self.owner, self.name)
+    }
+}
+
+pub fn detect_signed_tag_from_url(url: &str) -> Result<Option<TagUrl>> {
+    let re =
+        Regex::new(r"^https://bitbucket.org/([^/]+)/([^/]+)/get/(.+)\.(tar\.gz|tar\.bz2|zip)$")?;
+    if let Some(caps) = re.captures(url) {
+        let owner = &caps[1];
+        let name = &caps[2];
+        let tag = &caps[3];
+
+        return Ok(Some(TagUrl {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            tag: tag.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitbucket_url_matching() -> Result<()> {
+        let x = detect_signed_tag_from_url(
+            "https://bitbucket.org/multicoreware/x265_git/get/3.5.tar.gz",
+        )?;
+        assert_eq!(
+            x,
+            Some(TagUrl {
+                owner: "multicoreware".to_string(),
+                name: "x265_git".to_string(),
+                tag: "3.5".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
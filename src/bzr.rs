@@ -1,12 +1,23 @@
 use crate::errors::*;
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BzrSource {
     url: String,
     revision: Option<String>,
 }
 
+impl BzrSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+}
+
 impl FromStr for BzrSource {
     type Err = Error;
 
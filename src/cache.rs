@@ -0,0 +1,212 @@
+use crate::errors::*;
+use crate::fsck::{Finding, PackageFindings};
+use crate::golang::hex;
+use crate::suppress::Suppressed;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Entries of a package directory that are created by makepkg or git and don't influence the findings
+const IGNORED: &[&str] = &[".git", "src", "pkg"];
+
+#[derive(Serialize, Deserialize)]
+struct CachedSuppression {
+    finding: Finding,
+    reason: Option<String>,
+    line: Option<usize>,
+    expires: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    findings: Vec<Finding>,
+    #[serde(default)]
+    suppressed: Vec<CachedSuppression>,
+}
+
+/// Hash the files of a directory, sorted by their relative path
+fn hash_files(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = path.strip_prefix(root)?.to_string_lossy().into_owned();
+        if IGNORED.contains(&name.as_str()) {
+            continue;
+        }
+
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            hash_files(root, &path, files)?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(&path)?;
+            let hash = Sha256::digest(target.to_string_lossy().as_bytes());
+            files.push((name, format!("symlink:{}", hex(&hash))));
+        } else {
+            let buf = fs::read(&path).with_context(|| anyhow!("Failed to read {:?}", path))?;
+            files.push((name, hex(&Sha256::digest(&buf))));
+        }
+    }
+    Ok(())
+}
+
+/// Calculate the cache key of a package checkout, `settings` describes everything else that
+/// influences the findings, like the target and the check options
+pub fn key(path: &Path, settings: &str) -> Result<String> {
+    let mut files = Vec::new();
+    hash_files(path, path, &mut files)
+        .with_context(|| anyhow!("Failed to hash package directory: {:?}", path))?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(concat!(env!("CARGO_PKG_VERSION"), "\n"));
+    hasher.update(settings);
+    hasher.update("\n");
+    for (name, hash) in files {
+        hasher.update(format!("{}  {}\n", hash, name));
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The results of checked packages, so packages that didn't change since the last run don't need to be checked again
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Use a directory for the cache, it's created when the first result is stored
+    pub fn new(dir: PathBuf) -> Cache {
+        Cache { dir }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Lookup the results for a cache key, invalid entries are treated like a cache miss
+    pub fn get(&self, key: &str) -> Result<Option<PackageFindings>> {
+        let path = self.path(key);
+        let buf = match fs::read(&path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| anyhow!("Failed to read cache entry: {:?}", path))
+            }
+        };
+
+        match serde_json::from_slice::<Entry>(&buf) {
+            Ok(entry) => Ok(Some(PackageFindings {
+                findings: entry.findings,
+                suppressed: entry
+                    .suppressed
+                    .into_iter()
+                    .map(|suppressed| Suppressed {
+                        finding: suppressed.finding,
+                        reason: suppressed.reason,
+                        line: suppressed.line,
+                        expires: suppressed.expires,
+                    })
+                    .collect(),
+            })),
+            Err(err) => {
+                warn!("Ignoring invalid cache entry {:?}: {:#}", path, err);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Store the results for a cache key, the file is replaced atomically so concurrent scans can share the cache
+    pub fn put(&self, key: &str, pkg: &PackageFindings) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| anyhow!("Failed to create cache directory: {:?}", self.dir))?;
+
+        let entry = Entry {
+            findings: pkg.findings.clone(),
+            suppressed: pkg
+                .suppressed
+                .iter()
+                .map(|suppressed| CachedSuppression {
+                    finding: suppressed.finding.clone(),
+                    reason: suppressed.reason.clone(),
+                    line: suppressed.line,
+                    expires: suppressed.expires.clone(),
+                })
+                .collect(),
+        };
+
+        let mut file = tempfile::NamedTempFile::new_in(&self.dir)?;
+        serde_json::to_writer(&mut file, &entry)?;
+        file.flush()?;
+        let path = self.path(key);
+        file.persist(&path)
+            .with_context(|| anyhow!("Failed to write cache entry: {:?}", path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::GitSource;
+    use crate::makepkg::Source;
+
+    #[test]
+    fn test_cache_roundtrip() -> Result<()> {
+        let pkg = tempfile::tempdir()?;
+        fs::write(pkg.path().join("PKGBUILD"), "pkgname=foo\n")?;
+        fs::create_dir(pkg.path().join("src"))?;
+        fs::write(pkg.path().join("src/foo-1.0.tar.gz"), "extracted")?;
+        let before = key(pkg.path(), "foo")?;
+        assert_eq!(before.len(), 64);
+
+        // build directories don't change the key, but the settings and local files do
+        fs::write(pkg.path().join("src/foo-1.0.tar.gz"), "modified")?;
+        assert_eq!(key(pkg.path(), "foo")?, before);
+        assert_ne!(key(pkg.path(), "bar")?, before);
+        fs::write(pkg.path().join("foo.install"), "post_install() { :; }\n")?;
+        assert_ne!(key(pkg.path(), "foo")?, before);
+
+        let dir = tempfile::tempdir()?;
+        let cache = Cache::new(dir.path().join("cache"));
+        assert!(cache.get(&before)?.is_none());
+
+        let source = "git+https://example.com/foo.git#tag=v1.0".parse::<GitSource>()?;
+        let findings = vec![
+            Finding::GitTagInsecurePin(source.clone()),
+            Finding::InsecureScheme {
+                scheme: "http".to_string(),
+                source: Source::UrlWithFilename((
+                    "http://example.com/foo-1.0.tar.gz".to_string(),
+                    "foo.tar.gz".to_string(),
+                )),
+                suggestion: None,
+            },
+        ];
+        cache.put(
+            &before,
+            &PackageFindings {
+                findings: findings.clone(),
+                suppressed: vec![Suppressed {
+                    finding: Finding::GitHeadInsecurePin(source),
+                    reason: Some("upstream has no tags".to_string()),
+                    line: Some(3),
+                    expires: None,
+                }],
+            },
+        )?;
+
+        let cached = cache.get(&before)?.unwrap();
+        assert_eq!(
+            serde_json::to_value(&cached.findings)?,
+            serde_json::to_value(&findings)?
+        );
+        assert_eq!(cached.suppressed.len(), 1);
+        assert_eq!(cached.suppressed[0].line, Some(3));
+
+        fs::write(cache.path(&before), "{\"findings\":")?;
+        assert!(cache.get(&before)?.is_none());
+        Ok(())
+    }
+}
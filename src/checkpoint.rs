@@ -0,0 +1,161 @@
+use crate::errors::*;
+use crate::fsck::{Finding, Target};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The result of a checked package, stored as one json line per package
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub target: String,
+    #[serde(default)]
+    pub findings: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// If any of the findings matched `--fail-on` when the package was checked
+    #[serde(default)]
+    pub failed: bool,
+}
+
+/// Keep track of checked packages, so an interrupted scan can be resumed later
+pub struct Checkpoint {
+    completed: HashMap<String, Entry>,
+    file: BufWriter<File>,
+}
+
+/// Parse a checkpoint file, packages that failed to be checked are left out so they're tried again
+fn parse_entries(buf: &str) -> HashMap<String, Entry> {
+    let mut completed = HashMap::new();
+    for (i, line) in buf.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Entry>(line) {
+            Ok(entry) if entry.error.is_none() => {
+                completed.insert(entry.target.clone(), entry);
+            }
+            Ok(_) => (),
+            // the last line might be incomplete if the process was killed
+            Err(err) => warn!(
+                "Ignoring invalid checkpoint entry in line {}: {:#}",
+                i + 1,
+                err
+            ),
+        }
+    }
+    completed
+}
+
+impl Checkpoint {
+    /// Open a checkpoint file, the previous results are only kept if the scan is resumed
+    pub fn open(path: &Path, resume: bool) -> Result<Checkpoint> {
+        let completed = if resume {
+            match fs::read_to_string(path) {
+                Ok(buf) => parse_entries(&buf),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    debug!("Checkpoint file {:?} doesn't exist yet", path);
+                    HashMap::new()
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| anyhow!("Failed to read checkpoint: {:?}", path))
+                }
+            }
+        } else {
+            HashMap::new()
+        };
+
+        let file = File::create(path)
+            .with_context(|| anyhow!("Failed to create checkpoint: {:?}", path))?;
+        let mut checkpoint = Checkpoint {
+            completed: HashMap::new(),
+            file: BufWriter::new(file),
+        };
+        // rewrite the valid entries, so new entries aren't appended to a partially written line
+        for entry in completed.values() {
+            checkpoint.write_entry(entry)?;
+        }
+        checkpoint.completed = completed;
+        Ok(checkpoint)
+    }
+
+    /// The packages that were already checked in a previous run
+    pub fn completed(&self) -> impl Iterator<Item = &Entry> {
+        self.completed.values()
+    }
+
+    pub fn is_completed(&self, target: &Target) -> bool {
+        self.completed.contains_key(target.display().as_ref())
+    }
+
+    fn write_entry(&mut self, entry: &Entry) -> Result<()> {
+        serde_json::to_writer(&mut self.file, entry)?;
+        writeln!(self.file)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Record the result of a package as soon as it's done
+    pub fn record(
+        &mut self,
+        target: &Target,
+        findings: Result<&[Finding], &Error>,
+        failed: bool,
+    ) -> Result<()> {
+        let entry = match findings {
+            Ok(findings) => Entry {
+                target: target.display().into_owned(),
+                findings: findings
+                    .iter()
+                    .map(|finding| serde_json::to_value(finding.record()))
+                    .collect::<Result<_, _>>()?,
+                error: None,
+                failed,
+            },
+            Err(err) => Entry {
+                target: target.display().into_owned(),
+                findings: Vec::new(),
+                error: Some(format!("{:#}", err)),
+                failed,
+            },
+        };
+        self.write_entry(&entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_checkpoint() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("checkpoint.ndjson");
+
+        let mut checkpoint = Checkpoint::open(&path, false)?;
+        checkpoint.record(&Target::ArchBuildSystem("linux".to_string()), Ok(&[]), true)?;
+        checkpoint.record(
+            &Target::ArchBuildSystem("zstd".to_string()),
+            Err(&anyhow!("checkout failed")),
+            false,
+        )?;
+        drop(checkpoint);
+        // simulate a process that was killed while writing
+        let mut buf = fs::read_to_string(&path)?;
+        buf.push_str("{\"target\":\"pyth");
+        fs::write(&path, buf)?;
+
+        let checkpoint = Checkpoint::open(&path, true)?;
+        assert!(checkpoint.is_completed(&Target::ArchBuildSystem("linux".to_string())));
+        assert!(!checkpoint.is_completed(&Target::ArchBuildSystem("zstd".to_string())));
+        assert!(checkpoint.completed().all(|entry| entry.failed));
+        drop(checkpoint);
+
+        let checkpoint = Checkpoint::open(&path, false)?;
+        assert_eq!(checkpoint.completed().count(), 0);
+        Ok(())
+    }
+}
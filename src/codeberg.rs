@@ -0,0 +1,84 @@
+use crate::errors::*;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub id: String,
+    pub message: Option<String>,
+    pub commit: TagCommit,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagCommit {
+    pub sha: String,
+}
+
+impl Tag {
+    /// Lightweight tags point to the commit directly, only annotated tags can be signed
+    pub fn is_annotated(&self) -> bool {
+        self.id != self.commit.sha
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagUrl {
+    pub owner: String,
+    pub name: String,
+    pub tag: String,
+}
+
+pub fn detect_signed_tag_from_url(url: &str) -> Result<Option<TagUrl>> {
+    let re = Regex::new(r"^https://codeberg.org/([^/]+)/([^/]+)/archive/(.+)\.(tar\.gz|zip)$")?;
+    if let Some(caps) = re.captures(url) {
+        let owner = &caps[1];
+        let name = &caps[2];
+        let tag = &caps[3];
+
+        return Ok(Some(TagUrl {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            tag: tag.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+pub async fn fetch_tag(client: &Client, owner: &str, name: &str, tag: &str) -> Result<Option<Tag>> {
+    let url = format!(
+        "https://codeberg.org/api/v1/repos/{}/{}/tags/{}",
+        owner, name, tag
+    );
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let json = response.error_for_status()?.json().await?;
+
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeberg_url_matching() -> Result<()> {
+        let x = detect_signed_tag_from_url("https://codeberg.org/dnkl/foot/archive/1.13.1.tar.gz")?;
+        assert_eq!(
+            x,
+            Some(TagUrl {
+                owner: "dnkl".to_string(),
+                name: "foot".to_string(),
+                tag: "1.13.1".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
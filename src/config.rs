@@ -0,0 +1,256 @@
+use crate::errors::*;
+use crate::fsck::{Checksum, Finding, Severity};
+use crate::makepkg::{self, Evaluator};
+use crate::stats;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use strum::VariantNames;
+
+const DEFAULT_SECURE_ALGS: &[&str] = &[
+    "sha256sums",
+    "sha512sums",
+    "sha224sums",
+    "sha384sums",
+    "b2sums",
+];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Default for `--filter`
+    #[serde(default)]
+    pub filters: Vec<String>,
+    /// Default for `--min-severity`
+    pub min_severity: Option<Severity>,
+    /// Default for `--concurrency`
+    pub concurrency: Option<usize>,
+    /// Default for `--timeout`
+    pub timeout: Option<u64>,
+    /// Default for `--evaluator`
+    pub evaluator: Option<Evaluator>,
+    /// Change the severity of a finding type, eg. `unversioned-url = "info"`
+    #[serde(default)]
+    pub severity: BTreeMap<String, Severity>,
+    #[serde(default)]
+    pub checksums: ChecksumPolicy,
+    #[serde(default)]
+    pub sources: SourcePolicy,
+}
+
+/// Which checksum algorithms count as securely pinning a url source
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChecksumPolicy {
+    pub secure: Vec<String>,
+}
+
+impl Default for ChecksumPolicy {
+    fn default() -> Self {
+        ChecksumPolicy {
+            secure: DEFAULT_SECURE_ALGS.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+impl ChecksumPolicy {
+    pub fn is_secure(&self, checksum: &Checksum) -> bool {
+        self.secure.iter().any(|alg| alg == checksum.alg())
+    }
+}
+
+/// Sources that are accepted even though they'd be reported otherwise
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SourcePolicy {
+    /// Schemes that aren't reported as insecure or unknown, eg. `http` for an internal mirror
+    #[serde(default)]
+    pub allowed_schemes: Vec<String>,
+    #[serde(default)]
+    pub domains: Vec<DomainPolicy>,
+}
+
+/// Exceptions for sources hosted on a domain and its subdomains
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DomainPolicy {
+    pub domain: String,
+    /// Schemes that aren't reported as insecure or unknown for this domain
+    #[serde(default)]
+    pub allowed_schemes: Vec<String>,
+    /// Finding types that aren't reported for sources on this domain
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl DomainPolicy {
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let domain = self.domain.to_ascii_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    }
+}
+
+impl SourcePolicy {
+    /// If a finding is about a source that is accepted by this policy
+    pub fn accepts(&self, finding: &Finding) -> bool {
+        let scheme = match finding {
+            Finding::InsecureScheme { scheme, .. } => Some(scheme.as_str()),
+            Finding::UnknownScheme { scheme, .. } => Some(scheme.as_str()),
+            _ => None,
+        };
+        if scheme.is_some_and(|scheme| self.allowed_schemes.iter().any(|s| s == scheme)) {
+            return true;
+        }
+
+        let Some(host) = finding.url().and_then(stats::host) else {
+            return false;
+        };
+        let kind: &'static str = finding.into();
+        self.domains
+            .iter()
+            .filter(|policy| policy.matches(host))
+            .any(|policy| {
+                policy.ignore.iter().any(|ignore| ignore == kind)
+                    || scheme
+                        .is_some_and(|scheme| policy.allowed_schemes.iter().any(|s| s == scheme))
+            })
+    }
+}
+
+fn validate_finding(kind: &str) -> Result<()> {
+    if !Finding::VARIANTS.contains(&kind) {
+        bail!("Unknown finding type in config: {:?}", kind);
+    }
+    Ok(())
+}
+
+/// The config file that is used if `--config` isn't set, `~/.config/archlinux-inputs-fsck/config.toml`
+pub fn default_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+
+impl Config {
+    pub fn parse(s: &str) -> Result<Self> {
+        let config = toml::from_str::<Config>(s)?;
+        for alg in &config.checksums.secure {
+            if !makepkg::SUPPORTED_ALGS.contains(&alg.as_str()) {
+                bail!("Unknown checksum algorithm in config: {:?}", alg);
+            }
+        }
+        for kind in config.filters.iter().chain(config.severity.keys()) {
+            validate_finding(kind)?;
+        }
+        for policy in &config.sources.domains {
+            for kind in &policy.ignore {
+                validate_finding(kind)?;
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = fs::read_to_string(path)
+            .with_context(|| anyhow!("Failed to read config file: {:?}", path))?;
+        Self::parse(&s).with_context(|| anyhow!("Failed to parse config file: {:?}", path))
+    }
+
+    /// Load the config file in the default location, if it exists
+    pub fn load_default() -> Result<Option<Self>> {
+        match default_path() {
+            Some(path) if path.exists() => {
+                debug!("Loading config file: {:?}", path);
+                Self::load(&path).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_policy() -> Result<()> {
+        let config = Config::parse("")?;
+        assert!(config
+            .checksums
+            .is_secure(&Checksum::Sha256("aaaa".to_string())));
+        assert!(!config
+            .checksums
+            .is_secure(&Checksum::Sha1("aaaa".to_string())));
+
+        let config = Config::parse(
+            r#"
+[checksums]
+secure = ["sha512sums", "b2sums"]
+"#,
+        )?;
+        assert!(!config
+            .checksums
+            .is_secure(&Checksum::Sha256("aaaa".to_string())));
+        assert!(config
+            .checksums
+            .is_secure(&Checksum::B2("aaaa".to_string())));
+
+        assert!(Config::parse("[checksums]\nsecure = [\"crc32sums\"]\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_source_policy() -> Result<()> {
+        let config = Config::parse(
+            r#"
+min-severity = "medium"
+evaluator = "native"
+
+[severity]
+unversioned-url = "info"
+
+[sources]
+allowed-schemes = ["ftp"]
+
+[[sources.domains]]
+domain = "mirror.example.com"
+allowed-schemes = ["http"]
+ignore = ["unversioned-url"]
+"#,
+        )?;
+        assert_eq!(config.min_severity, Some(Severity::Medium));
+        assert_eq!(config.evaluator, Some(Evaluator::Native));
+        assert_eq!(
+            config.severity.get("unversioned-url"),
+            Some(&Severity::Info)
+        );
+
+        let insecure = |url: &str| Finding::InsecureScheme {
+            scheme: url.split_once("://").unwrap().0.to_string(),
+            source: makepkg::Source::Url(url.to_string()),
+            suggestion: None,
+        };
+        let policy = &config.sources;
+        assert!(policy.accepts(&insecure("ftp://ftp.gnu.org/gnu/foo-1.0.tar.gz")));
+        assert!(policy.accepts(&insecure("http://mirror.example.com/foo-1.0.tar.gz")));
+        assert!(policy.accepts(&insecure("http://a.mirror.example.com/foo-1.0.tar.gz")));
+        assert!(!policy.accepts(&insecure("http://badmirror.example.com/foo-1.0.tar.gz")));
+        assert!(policy.accepts(&Finding::UnversionedUrl {
+            url: "https://mirror.example.com/foo-latest.tar.gz".to_string(),
+            keyword: Some("latest".to_string()),
+        }));
+        assert!(!policy.accepts(&Finding::UnversionedUrl {
+            url: "https://example.com/foo-latest.tar.gz".to_string(),
+            keyword: Some("latest".to_string()),
+        }));
+
+        assert!(Config::parse("filters = [\"insecure-schema\"]\n").is_err());
+        assert!(Config::parse("[severity]\nfoo = \"high\"\n").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use crate::errors::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct DarcsSource {
+    url: String,
+    tag: Option<String>,
+}
+
+impl DarcsSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+}
+
+impl FromStr for DarcsSource {
+    type Err = Error;
+
+    fn from_str(mut s: &str) -> Result<Self> {
+        let mut tag = None;
+
+        if let Some((remaining, value)) = s.rsplit_once("#tag=") {
+            tag = Some(value.to_string());
+            s = remaining;
+        }
+
+        Ok(Self {
+            url: s.to_string(),
+            tag,
+        })
+    }
+}
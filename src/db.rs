@@ -0,0 +1,211 @@
+use crate::errors::*;
+use crate::fsck::{Finding, Target};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER,
+    version TEXT NOT NULL,
+    args TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS packages (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    target TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS findings (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    target TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    details TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS packages_run_id ON packages(run_id);
+CREATE INDEX IF NOT EXISTS findings_run_id ON findings(run_id);
+";
+
+fn now() -> Result<i64> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok(secs as i64)
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    pub target: String,
+    pub kind: String,
+    pub severity: String,
+    pub details: Value,
+}
+
+/// The findings of the current run compared to a previous one
+#[derive(Debug, Default, Serialize)]
+pub struct Diff {
+    pub against: i64,
+    pub new: Vec<DiffEntry>,
+    pub fixed: Vec<DiffEntry>,
+    pub unchanged: Vec<DiffEntry>,
+}
+
+impl Diff {
+    pub fn print(&self) {
+        let sections = [("New", "+", &self.new), ("Fixed", "-", &self.fixed)];
+        for (title, prefix, entries) in sections {
+            eprintln!(
+                "{} findings compared to run #{}: {}",
+                title,
+                self.against,
+                entries.len()
+            );
+            for entry in entries {
+                eprintln!(
+                    "  {} {}: {} {}",
+                    prefix, entry.target, entry.kind, entry.details
+                );
+            }
+        }
+        eprintln!("Unchanged findings: {}", self.unchanged.len());
+    }
+}
+
+/// Select the findings of run `a`, limited to packages that have been checked
+/// successfully in both runs. Findings of other packages are neither new nor fixed.
+fn findings_query(a: &str, b: &str) -> String {
+    format!(
+        "SELECT target, kind, severity, details FROM findings WHERE run_id = {a}
+            AND target IN (SELECT target FROM packages WHERE run_id = {a} AND error IS NULL)
+            AND target IN (SELECT target FROM packages WHERE run_id = {b} AND error IS NULL)"
+    )
+}
+
+/// A sqlite database that keeps the results of every run
+pub struct Database {
+    conn: Connection,
+    run_id: i64,
+}
+
+impl Database {
+    /// Open (or create) the database and register a new run
+    pub fn open(path: &Path) -> Result<Database> {
+        let conn = Connection::open(path)
+            .with_context(|| anyhow!("Failed to open database: {:?}", path))?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to setup database schema")?;
+
+        let args = serde_json::to_string(&std::env::args().collect::<Vec<_>>())?;
+        conn.execute(
+            "INSERT INTO runs (started_at, version, args) VALUES (?1, ?2, ?3)",
+            params![now()?, env!("CARGO_PKG_VERSION"), args],
+        )?;
+        let run_id = conn.last_insert_rowid();
+        debug!("Recording results in database as run #{}", run_id);
+
+        Ok(Database { conn, run_id })
+    }
+
+    pub fn run_id(&self) -> i64 {
+        self.run_id
+    }
+
+    pub fn insert_findings(&mut self, target: &Target, findings: &[Finding]) -> Result<()> {
+        let target = target.display();
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO packages (run_id, target) VALUES (?1, ?2)",
+            params![self.run_id, target],
+        )?;
+        for finding in findings {
+            let kind: &'static str = finding.into();
+            let severity: &'static str = finding.severity().into();
+            let details = serde_json::to_string(finding)?;
+            tx.execute(
+                "INSERT INTO findings (run_id, target, kind, severity, details) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![self.run_id, target, kind, severity, details],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn insert_error(&mut self, target: &Target, err: &Error) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO packages (run_id, target, error) VALUES (?1, ?2, ?3)",
+            params![self.run_id, target.display(), format!("{:#}", err)],
+        )?;
+        Ok(())
+    }
+
+    fn query_diff(&self, query: &str, a: i64, b: i64) -> Result<Vec<DiffEntry>> {
+        let mut stmt = self.conn.prepare(query)?;
+        let rows = stmt.query_map(params![a, b], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (target, kind, severity, details) = row?;
+            entries.push(DiffEntry {
+                target,
+                kind,
+                severity,
+                details: serde_json::from_str(&details)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Compare the findings of this run with a previous run
+    pub fn diff(&self, against: i64) -> Result<Diff> {
+        let exists = self
+            .conn
+            .query_row("SELECT id FROM runs WHERE id = ?1", [against], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()?;
+        if exists.is_none() {
+            bail!("Run #{} does not exist in database", against);
+        }
+
+        let new = format!(
+            "{} EXCEPT {}",
+            findings_query("?1", "?2"),
+            findings_query("?2", "?1")
+        );
+        let unchanged = format!(
+            "{} INTERSECT {}",
+            findings_query("?1", "?2"),
+            findings_query("?2", "?1")
+        );
+
+        Ok(Diff {
+            against,
+            new: self.query_diff(&new, self.run_id, against)?,
+            fixed: self.query_diff(&new, against, self.run_id)?,
+            unchanged: self.query_diff(&unchanged, self.run_id, against)?,
+        })
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        self.conn.execute(
+            "UPDATE runs SET finished_at = ?1 WHERE id = ?2",
+            params![now()?, self.run_id],
+        )?;
+        info!(
+            "Results have been recorded in database as run #{}",
+            self.run_id
+        );
+        Ok(())
+    }
+}
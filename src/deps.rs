@@ -0,0 +1,134 @@
+use crate::archweb;
+use crate::errors::*;
+use crate::pacman::{self, Package};
+use reqwest::Client;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Find packages by name or by something they provide, either from local sync databases or archweb
+pub struct Resolver {
+    client: Option<Client>,
+    packages: HashMap<String, Package>,
+    providers: HashMap<String, String>,
+}
+
+impl Resolver {
+    pub fn from_packages(pkgs: Vec<Package>) -> Self {
+        let mut resolver = Resolver {
+            client: None,
+            packages: HashMap::new(),
+            providers: HashMap::new(),
+        };
+        for pkg in pkgs {
+            resolver.add(pkg);
+        }
+        resolver
+    }
+
+    pub fn archweb(client: Client) -> Self {
+        Resolver {
+            client: Some(client),
+            packages: HashMap::new(),
+            providers: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, pkg: Package) {
+        for provide in &pkg.provides {
+            self.providers
+                .entry(pacman::dependency_name(provide).to_string())
+                .or_insert_with(|| pkg.name.clone());
+        }
+        self.packages.insert(pkg.name.clone(), pkg);
+    }
+
+    async fn lookup(&mut self, name: &str) -> Result<Option<&Package>> {
+        if !self.packages.contains_key(name) && !self.providers.contains_key(name) {
+            if let Some(client) = &self.client {
+                if let Some(pkg) = archweb::find_package(client, name).await? {
+                    self.add(pkg);
+                }
+            }
+        }
+
+        let name = match self.providers.get(name) {
+            Some(provider) if !self.packages.contains_key(name) => provider.as_str(),
+            _ => name,
+        };
+        Ok(self.packages.get(name))
+    }
+
+    /// The pkgbases needed to build a package: the package itself, its
+    /// depends/makedepends/checkdepends and the runtime dependencies of those
+    pub async fn build_closure(&mut self, pkgname: &str) -> Result<BTreeSet<String>> {
+        let root = self
+            .lookup(pkgname)
+            .await?
+            .with_context(|| anyhow!("Failed to find package: {:?}", pkgname))?
+            .clone();
+
+        let mut pkgbases = BTreeSet::from([root.base.clone()]);
+        let mut seen = HashSet::from([root.name.clone()]);
+        let mut queue = root
+            .depends
+            .iter()
+            .chain(&root.makedepends)
+            .chain(&root.checkdepends)
+            .map(|dep| pacman::dependency_name(dep).to_string())
+            .collect::<VecDeque<_>>();
+
+        while let Some(name) = queue.pop_front() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let Some(pkg) = self.lookup(&name).await? else {
+                warn!("Failed to resolve dependency {:?} of {:?}", name, pkgname);
+                continue;
+            };
+            seen.insert(pkg.name.clone());
+            pkgbases.insert(pkg.base.clone());
+            queue.extend(
+                pkg.depends
+                    .iter()
+                    .map(|dep| pacman::dependency_name(dep).to_string()),
+            );
+        }
+
+        Ok(pkgbases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkg(name: &str, base: &str, depends: &[&str], makedepends: &[&str]) -> Package {
+        Package {
+            name: name.to_string(),
+            base: base.to_string(),
+            depends: depends.iter().map(|x| x.to_string()).collect(),
+            makedepends: makedepends.iter().map(|x| x.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_closure() -> Result<()> {
+        let mut bash = pkg("bash", "bash", &["glibc", "readline>=7.0"], &[]);
+        bash.provides.push("sh".to_string());
+        let mut resolver = Resolver::from_packages(vec![
+            pkg("foo", "foo", &["sh"], &["rust", "cmake"]),
+            bash,
+            pkg("glibc", "glibc", &[], &["python"]),
+            pkg("readline", "readline", &["glibc"], &[]),
+            pkg("rust", "rust", &["gcc-libs"], &["llvm"]),
+            pkg("gcc-libs", "gcc", &["glibc"], &[]),
+        ]);
+
+        let closure = resolver.build_closure("foo").await?;
+        assert_eq!(
+            closure.into_iter().collect::<Vec<_>>(),
+            &["bash", "foo", "gcc", "glibc", "readline", "rust"]
+        );
+        Ok(())
+    }
+}
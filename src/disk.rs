@@ -0,0 +1,9 @@
+use crate::errors::*;
+use std::path::Path;
+
+/// The number of bytes that can still be written to the filesystem of this path
+pub fn available_space(path: &Path) -> Result<u64> {
+    let stat = rustix::fs::statvfs(path)
+        .with_context(|| anyhow!("Failed to query free disk space: {:?}", path))?;
+    Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
@@ -0,0 +1,88 @@
+use crate::errors::*;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct FossilSource {
+    url: String,
+    commit: Option<String>,
+    tag: Option<String>,
+    branch: Option<String>,
+}
+
+impl FossilSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    pub fn is_commit_securely_pinned(&self) -> bool {
+        if let Some(commit) = &self.commit {
+            is_fossil_artifact_hash(commit)
+        } else {
+            false
+        }
+    }
+}
+
+/// Fossil uses either sha1 or sha3-256 for artifact hashes
+fn is_fossil_artifact_hash(name: &str) -> bool {
+    matches!(name.len(), 40 | 64) && name.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+impl FromStr for FossilSource {
+    type Err = Error;
+
+    fn from_str(mut s: &str) -> Result<Self> {
+        let mut commit = None;
+        let mut tag = None;
+        let mut branch = None;
+
+        if let Some((remaining, value)) = s.rsplit_once("#commit=") {
+            commit = Some(value.to_string());
+            s = remaining;
+        }
+
+        if let Some((remaining, value)) = s.rsplit_once("#tag=") {
+            tag = Some(value.to_string());
+            s = remaining;
+        }
+
+        if let Some((remaining, value)) = s.rsplit_once("#branch=") {
+            branch = Some(value.to_string());
+            s = remaining;
+        }
+
+        Ok(Self {
+            url: s.to_string(),
+            commit,
+            tag,
+            branch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fossil_source() -> Result<()> {
+        let source = format!(
+            "fossil+https://www.sqlite.org/src#commit={}",
+            "e".repeat(64)
+        )
+        .parse::<FossilSource>()?;
+        assert_eq!(source.url(), "fossil+https://www.sqlite.org/src");
+        assert!(source.is_commit_securely_pinned());
+
+        let source =
+            "fossil+https://www.sqlite.org/src#tag=version-3.40.0".parse::<FossilSource>()?;
+        assert_eq!(source.tag.as_deref(), Some("version-3.40.0"));
+        assert!(!source.is_commit_securely_pinned());
+        Ok(())
+    }
+}
@@ -1,42 +1,151 @@
 use crate::asp;
+use crate::aur;
+use crate::bitbucket;
 use crate::bzr::BzrSource;
+use crate::cache::{self, Cache};
+use crate::codeberg;
+use crate::config::{ChecksumPolicy, SourcePolicy};
+use crate::darcs::DarcsSource;
 use crate::errors::*;
-use crate::git::GitSource;
+use crate::fossil::FossilSource;
+use crate::git::{self, GitSource, PinIssue};
 use crate::github;
+use crate::gitlab;
+use crate::gnu;
+use crate::golang;
 use crate::hg::HgSource;
+use crate::homograph::{self, HostIssue};
+use crate::lint::{self, ScriptletIssue, UrlIssue};
 use crate::makepkg;
-use crate::makepkg::Source;
+use crate::makepkg::{Evaluator, Pkgbuild, Source};
+use crate::npm;
 use crate::osv;
+use crate::pgp::{self, FingerprintIssue, KeyStatus, SignatureIssue};
+use crate::probe;
+use crate::pypi;
+use crate::registry::{Lint, Package, Registry};
+use crate::remote;
+use crate::sandbox::Sandbox;
+use crate::sourcehut;
+use crate::srcinfo::SrcInfo;
+use crate::srcpkg;
+use crate::stats;
+use crate::suppress::{self, Suppressed};
 use crate::svn::SvnSource;
+use crate::verify;
+use crate::version;
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
-use std::path::PathBuf;
-use strum::{EnumVariantNames, IntoStaticStr};
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use strum::{Display, EnumMessage, EnumVariantNames, IntoStaticStr};
+use tempfile::TempDir;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Target {
     ArchBuildSystem(String),
+    /// An official package at a specific version, eg. the released version from the state repository
+    ArchBuildSystemRelease {
+        pkgbase: String,
+        version: String,
+    },
+    Aur(String),
     BuildPath(PathBuf),
+    /// A source package tarball, as created by `makepkg --source`
+    SourcePackage(PathBuf),
+    /// The content of a PKGBUILD that was read from stdin
+    Stdin(String),
+    /// A PKGBUILD that is downloaded from a url, eg. the AUR cgit web interface
+    Url(String),
 }
 
 impl Target {
-    pub fn display(&self) -> Cow<str> {
+    pub fn display(&self) -> Cow<'_, str> {
         match self {
             Target::ArchBuildSystem(pkg) => Cow::Borrowed(pkg),
-            Target::BuildPath(path) => path.to_string_lossy(),
+            Target::ArchBuildSystemRelease { pkgbase, version } => {
+                Cow::Owned(format!("{}-{}", pkgbase, version))
+            }
+            Target::Aur(pkg) => Cow::Owned(format!("aur/{}", pkg)),
+            Target::BuildPath(path) | Target::SourcePackage(path) => path.to_string_lossy(),
+            Target::Stdin(_) => Cow::Borrowed("-"),
+            Target::Url(url) => Cow::Borrowed(url),
+        }
+    }
+
+    /// Guess the pkgbase of this target, eg. `svntogit-packages/linux/trunk` -> `linux`
+    pub fn pkgbase(&self) -> Cow<'_, str> {
+        match self {
+            Target::ArchBuildSystem(pkg) | Target::Aur(pkg) => Cow::Borrowed(pkg),
+            Target::ArchBuildSystemRelease { pkgbase, .. } => Cow::Borrowed(pkgbase),
+            Target::BuildPath(path) => {
+                let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+                let mut components = path
+                    .components()
+                    .rev()
+                    .filter_map(|c| c.as_os_str().to_str());
+
+                let name = match components.next() {
+                    Some("trunk") => components.next(),
+                    // repos/extra-x86_64 in svntogit
+                    Some(_)
+                        if path.parent().and_then(|p| p.file_name()) == Some("repos".as_ref()) =>
+                    {
+                        components.nth(1)
+                    }
+                    name => name,
+                };
+                Cow::Owned(name.unwrap_or("unknown").to_string())
+            }
+            Target::SourcePackage(path) => {
+                let name = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(srcpkg::pkgbase);
+                Cow::Owned(name.unwrap_or("unknown").to_string())
+            }
+            Target::Stdin(_) => Cow::Borrowed("stdin"),
+            Target::Url(url) => {
+                Cow::Owned(remote::pkgbase(url).unwrap_or_else(|| "unknown".to_string()))
+            }
+        }
+    }
+
+    /// A file name for the results of this target, the repository is appended for svntogit
+    /// `repos/*` directories so they don't collide with `trunk`, eg. `linux-core-x86_64`
+    pub fn file_name(&self) -> Cow<'_, str> {
+        let pkgbase = self.pkgbase();
+        if let Target::BuildPath(path) = self {
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            if path.parent().and_then(|p| p.file_name()) == Some("repos".as_ref()) {
+                if let Some(repo) = path.file_name().and_then(|name| name.to_str()) {
+                    return Cow::Owned(format!("{}-{}", pkgbase, repo));
+                }
+            }
         }
+        pkgbase
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum AuthedSource {
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthedSource {
     File(String),
     Url(UrlSource),
     Git(GitSource),
     Svn(SvnSource),
     Hg(HgSource),
     Bzr(BzrSource),
+    Fossil(FossilSource),
+    Darcs(DarcsSource),
 }
 
 impl AuthedSource {
@@ -45,19 +154,44 @@ impl AuthedSource {
             url: s.url().to_string(),
             filename: s.filename().map(String::from),
             checksums: Vec::new(),
+            skipped: Vec::new(),
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct UrlSource {
     url: String,
     filename: Option<String>,
     checksums: Vec<Checksum>,
+    /// The checksum arrays that have `SKIP` for this source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<String>,
 }
 
 impl UrlSource {
-    fn is_signature_file(&self) -> bool {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    pub fn checksums(&self) -> &[Checksum] {
+        &self.checksums
+    }
+
+    /// The filename makepkg downloads this source to
+    pub fn destination(&self) -> String {
+        if let Some(filename) = &self.filename {
+            Source::UrlWithFilename((self.url.clone(), filename.clone())).destination()
+        } else {
+            Source::Url(self.url.clone()).destination()
+        }
+    }
+
+    pub fn is_signature_file(&self) -> bool {
         let filename = if let Some(filename) = &self.filename {
             filename
         } else {
@@ -74,8 +208,9 @@ impl UrlSource {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-enum Checksum {
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "alg", content = "value", rename_all = "lowercase")]
+pub enum Checksum {
     Md5(String),
     Sha1(String),
     Sha256(String),
@@ -83,11 +218,12 @@ enum Checksum {
     Sha224(String),
     Sha384(String),
     B2(String),
+    Cksum(String),
 }
 
 impl Checksum {
-    fn new(alg: &str, value: String) -> Result<Checksum> {
-        Ok(match alg {
+    pub(crate) fn new(alg: &str, value: String) -> Option<Checksum> {
+        Some(match alg {
             "md5sums" => Checksum::Md5(value),
             "sha1sums" => Checksum::Sha1(value),
             "sha256sums" => Checksum::Sha256(value),
@@ -95,70 +231,650 @@ impl Checksum {
             "sha224sums" => Checksum::Sha224(value),
             "sha384sums" => Checksum::Sha384(value),
             "b2sums" => Checksum::B2(value),
-            _ => bail!("Unknown checksum algorithm: {:?}", alg),
+            "cksums" => Checksum::Cksum(value),
+            _ => return None,
         })
     }
 
-    fn is_checksum_securely_pinned(&self) -> bool {
+    /// The name of the checksum array, eg. `sha256sums`
+    pub fn alg(&self) -> &'static str {
+        match self {
+            Checksum::Md5(_) => "md5sums",
+            Checksum::Sha1(_) => "sha1sums",
+            Checksum::Sha256(_) => "sha256sums",
+            Checksum::Sha512(_) => "sha512sums",
+            Checksum::Sha224(_) => "sha224sums",
+            Checksum::Sha384(_) => "sha384sums",
+            Checksum::B2(_) => "b2sums",
+            Checksum::Cksum(_) => "cksums",
+        }
+    }
+
+    pub fn value(&self) -> &str {
         match self {
-            Checksum::Md5(_) => false,
-            Checksum::Sha1(_) => false,
-            Checksum::Sha256(_) => true,
-            Checksum::Sha512(_) => true,
-            Checksum::Sha224(_) => true,
-            Checksum::Sha384(_) => true,
-            Checksum::B2(_) => true,
+            Checksum::Md5(value) => value,
+            Checksum::Sha1(value) => value,
+            Checksum::Sha256(value) => value,
+            Checksum::Sha512(value) => value,
+            Checksum::Sha224(value) => value,
+            Checksum::Sha384(value) => value,
+            Checksum::B2(value) => value,
+            Checksum::Cksum(value) => value,
         }
     }
 }
 
-#[derive(IntoStaticStr, EnumVariantNames, Clone)]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    IntoStaticStr,
+    Display,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+static SEVERITY_OVERRIDES: OnceLock<BTreeMap<String, Severity>> = OnceLock::new();
+
+/// Change the severity of finding types for this process, this can only be done once
+pub fn override_severity(overrides: BTreeMap<String, Severity>) -> Result<()> {
+    SEVERITY_OVERRIDES
+        .set(overrides)
+        .map_err(|_| anyhow!("Severity overrides have already been configured"))
+}
+
+#[derive(IntoStaticStr, EnumVariantNames, EnumMessage, Clone, Serialize, Deserialize)]
 #[strum(serialize_all = "kebab_case")]
+#[serde(tag = "kind", rename_all = "kebab-case")]
 pub enum Finding {
+    #[strum(message = "A source uses a complex protocol over an unauthenticated connection")]
     InsecureScheme {
         scheme: String,
         source: Source,
+        /// The https url that serves the same path, if `--probe-https` found one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suggestion: Option<String>,
     },
-    UnknownScheme((String, Source)),
+    #[strum(message = "A source uses a scheme that isn't understood")]
+    UnknownScheme { scheme: String, source: Source },
+    #[strum(message = "The number of checksums doesn't match the number of sources")]
     WrongNumberOfChecksums {
         sources: usize,
-        alg: &'static str,
+        alg: String,
         sums: usize,
     },
+    #[strum(message = "A checksum array uses an algorithm that isn't understood")]
+    UnknownChecksumAlgorithm { alg: String },
+    #[strum(message = "A git source pins a commit, but not with a full commit hash")]
     GitCommitInsecurePin(GitSource),
+    #[strum(message = "A git source pins a tag name instead of a commit object")]
+    GitTagInsecurePin(GitSource),
+    #[strum(message = "A git source follows a branch instead of pinning a commit object")]
+    GitBranchInsecurePin(GitSource),
+    #[strum(message = "A git source has no fragment and builds whatever HEAD points to")]
+    GitHeadInsecurePin(GitSource),
+    #[strum(message = "A svn source can not be cryptographically pinned")]
     SvnInsecurePin(SvnSource),
+    #[strum(message = "An hg source doesn't cryptographically pin a revision object")]
     HgRevisionInsecurePin(HgSource),
+    #[strum(message = "A bzr source can not be cryptographically pinned")]
     BzrInsecurePin(BzrSource),
+    #[strum(message = "A fossil source doesn't cryptographically pin a commit")]
+    FossilCommitInsecurePin(FossilSource),
+    #[strum(message = "A darcs source can not be cryptographically pinned")]
+    DarcsInsecurePin(DarcsSource),
+    #[strum(message = "A VCS package with a pkgver() function follows a source that isn't pinned")]
+    VcsPackageInsecurePin { vcs: String, url: String },
+    #[strum(message = "A url artifact isn't secured by a cryptographically secure checksum")]
     UrlArtifactInsecurePin(UrlSource),
+    #[strum(message = "A pypi source doesn't reference a specific release")]
+    PypiSourceNotVersionPinned { url: String },
+    #[strum(message = "The checksum of a pypi source doesn't match the one published on pypi")]
+    PypiChecksumMismatch {
+        url: String,
+        pypi: Checksum,
+        pkgbuild: Checksum,
+    },
+    #[strum(message = "A go module source can't be corroborated by the go checksum database")]
+    GoSumdbMismatch {
+        url: String,
+        module: String,
+        version: String,
+        sumdb: String,
+    },
+    #[strum(message = "The checksum of an npm source doesn't match the one published on npm")]
+    NpmChecksumMismatch {
+        url: String,
+        npm: Checksum,
+        pkgbuild: Checksum,
+    },
+    #[strum(
+        message = "None of the checksums of an npm source can be compared with the ones published on npm"
+    )]
+    NpmChecksumUncorroborated { url: String },
+    #[strum(message = "Upstream publishes a signature for a source that isn't verified")]
+    UnusedUpstreamSignature { url: String, signature: String },
+    #[strum(message = "A validpgpkeys entry isn't a full fingerprint")]
+    InvalidValidpgpkeys {
+        key: String,
+        issue: FingerprintIssue,
+    },
+    #[strum(message = "A validpgpkeys entry is expired, revoked or can't be found on a keyserver")]
+    UnusableValidpgpkeys { key: String, status: KeyStatus },
+    #[strum(
+        message = "A validpgpkeys entry can't be discovered with the Web Key Directory of its email addresses"
+    )]
+    ValidpgpkeysNotInWkd { key: String, emails: Vec<String> },
+    #[strum(message = "Signature files are downloaded but no validpgpkeys are configured")]
+    SignatureWithoutValidpgpkeys { signatures: Vec<String> },
+    #[strum(message = "A git source is verified with ?signed but no validpgpkeys are configured")]
+    GitSignedWithoutValidpgpkeys(GitSource),
+    #[strum(message = "A url artifact has its checksum verification disabled with SKIP")]
+    UrlArtifactChecksumSkipped(UrlSource),
+    #[strum(message = "A url source can't be downloaded anymore")]
+    SourceUnavailable { url: String, reason: String },
+    #[strum(
+        message = "A url source is served with a different size than the file that was downloaded before"
+    )]
+    SourceSizeMismatch {
+        url: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[strum(message = "A source is downloaded from an ip address instead of a hostname")]
+    IpLiteralHost { host: String, source: Source },
+    #[strum(message = "A source hostname contains punycode or lookalike characters")]
+    ConfusableHost {
+        host: String,
+        issue: HostIssue,
+        source: Source,
+    },
+    #[strum(message = "A build function downloads something that isn't pinned in source=")]
+    NetworkFetchInBuild { function: String, command: String },
+    #[strum(message = "`eval` is used on downloaded content")]
+    EvalDownloadedContent { line: usize, code: String },
+    #[strum(message = "The PKGBUILD uses sudo")]
+    SudoInPkgbuild { line: usize, code: String },
+    #[strum(message = "The PKGBUILD writes outside of $srcdir and $pkgdir")]
+    WriteOutsideBuildDir { line: usize, code: String },
+    #[strum(message = "A download is piped into a shell")]
+    PipeDownloadToShell { line: usize, code: String },
+    #[strum(
+        message = "An .install scriptlet accesses the network or modifies the pacman configuration"
+    )]
+    DangerousInstallScriptlet {
+        file: String,
+        issue: ScriptletIssue,
+        line: usize,
+        code: String,
+    },
+    #[strum(message = "The PKGBUILD overrides the download agents of makepkg")]
+    DlagentsOverride {
+        agents: Vec<String>,
+        disables_tls: bool,
+    },
+    #[strum(message = "A url source doesn't match its declared checksum anymore")]
+    ChecksumMismatch {
+        url: String,
+        expected: Checksum,
+        actual: Checksum,
+    },
+    #[strum(message = "A pinned git commit isn't published upstream as expected")]
+    GitPinNotUpstream { source: GitSource, issue: PinIssue },
+    #[strum(message = "A signed git tag doesn't verify with the keys in validpgpkeys")]
+    GitTagSignatureInvalid {
+        source: GitSource,
+        issue: SignatureIssue,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+    },
+    #[strum(message = "The version of a pinned tag or release archive doesn't match pkgver")]
+    PkgverMismatch {
+        pkgver: String,
+        version: String,
+        url: String,
+    },
+    #[strum(message = "A url source doesn't name a specific version")]
+    UnversionedUrl {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        keyword: Option<String>,
+    },
+    #[strum(message = "A variable used to pin a source doesn't contain a full commit hash")]
+    UnpinnedSourceVariable {
+        variable: String,
+        value: String,
+        line: usize,
+    },
+    #[strum(message = "A hash pinned with #tag= isn't an annotated tag object upstream")]
+    GitTagHashNotTagObject {
+        source: GitSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    #[strum(message = "An artifact and its signature are downloaded with different schemes")]
+    SignatureSchemeMismatch { artifact: String, signature: String },
+    #[strum(message = "A signature is listed for an artifact that isn't in source=")]
+    OrphanedSignature { signature: String },
+    #[strum(message = "A source url contains credentials")]
+    CredentialsInUrl { url: String, user: String },
+    #[strum(message = "A source url contains characters that are likely a quoting bug")]
+    MalformedUrl { issue: UrlIssue, source: Source },
+    #[strum(message = "A source is listed more than once, by url or by filename")]
+    DuplicateSource {
+        var: String,
+        filename: String,
+        source: Source,
+    },
+    #[strum(message = "A local source file doesn't exist in the package directory")]
+    MissingLocalSource { file: String },
+    #[strum(message = "The pkgver doesn't show up in any source url or vcs fragment")]
+    PkgverNotInSources { pkgver: String },
+    #[strum(message = "A source uses an archive that GitHub generates on the fly")]
+    GithubGeneratedArchive { url: String },
+    #[strum(
+        message = "A url artifact that isn't extracted, but executed or installed verbatim, is not securely pinned"
+    )]
+    VerbatimArtifactInsecurePin { source: UrlSource, noextract: bool },
+    #[strum(
+        message = "A git source uses ssh, which depends on the host keys trusted by whoever builds it"
+    )]
+    GitSshTransport { source: Source },
+    #[strum(message = "The .SRCINFO declares different inputs than the PKGBUILD")]
+    SrcinfoDrift {
+        key: String,
+        pkgbuild: Vec<String>,
+        srcinfo: Vec<String>,
+    },
+    #[strum(message = "A check was skipped because network access is disabled")]
+    CheckSkipped { check: String },
+    #[strum(message = "A dependency of the checked out source code has a security advisory")]
     SecurityAdvisory {
         source: PathBuf,
         packages: osv::Packages,
     },
 }
 
+/// A serialized finding, `kind` and the fields of the finding are flattened into the same object
+#[derive(Serialize)]
+pub struct FindingRecord<'a> {
+    pub severity: Severity,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    #[serde(flatten)]
+    pub finding: &'a Finding,
+}
+
 impl Finding {
-    pub fn audit_list(target: &Target, findings: &[Self], filters: &HashSet<String>) -> bool {
-        let mut has_findings = false;
+    /// A short description of the issue type this finding belongs to
+    pub fn description(&self) -> &'static str {
+        self.get_message().unwrap_or_default()
+    }
+
+    /// The severity of the finding, this can be changed with [`override_severity`]
+    pub fn severity(&self) -> Severity {
+        let kind: &'static str = self.into();
+        SEVERITY_OVERRIDES
+            .get()
+            .and_then(|overrides| overrides.get(kind).copied())
+            .unwrap_or_else(|| self.default_severity())
+    }
+
+    /// The severity of the finding, as defined by archlinux-inputs-fsck
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            Finding::InsecureScheme { .. } => Severity::High,
+            Finding::UnknownScheme { .. } => Severity::Info,
+            Finding::WrongNumberOfChecksums { .. } => Severity::Low,
+            Finding::UnknownChecksumAlgorithm { .. } => Severity::Low,
+            Finding::GitCommitInsecurePin(_) => Severity::Medium,
+            Finding::GitTagInsecurePin(_) => Severity::Medium,
+            Finding::GitBranchInsecurePin(_) => Severity::Medium,
+            Finding::GitHeadInsecurePin(_) => Severity::Medium,
+            Finding::SvnInsecurePin(_) => Severity::Medium,
+            Finding::HgRevisionInsecurePin(_) => Severity::Medium,
+            Finding::BzrInsecurePin(_) => Severity::Medium,
+            Finding::FossilCommitInsecurePin(_) => Severity::Medium,
+            Finding::DarcsInsecurePin(_) => Severity::Medium,
+            Finding::VcsPackageInsecurePin { .. } => Severity::Info,
+            Finding::UrlArtifactInsecurePin(_) => Severity::High,
+            Finding::PypiSourceNotVersionPinned { .. } => Severity::Medium,
+            Finding::PypiChecksumMismatch { .. } => Severity::High,
+            Finding::GoSumdbMismatch { .. } => Severity::High,
+            Finding::NpmChecksumMismatch { .. } => Severity::High,
+            Finding::NpmChecksumUncorroborated { .. } => Severity::Low,
+            Finding::UnusedUpstreamSignature { .. } => Severity::Low,
+            Finding::InvalidValidpgpkeys { .. } => Severity::Medium,
+            Finding::UnusableValidpgpkeys { .. } => Severity::Medium,
+            Finding::ValidpgpkeysNotInWkd { .. } => Severity::Info,
+            Finding::SignatureWithoutValidpgpkeys { .. } => Severity::Medium,
+            Finding::GitSignedWithoutValidpgpkeys(_) => Severity::Medium,
+            Finding::UrlArtifactChecksumSkipped(_) => Severity::High,
+            Finding::SourceUnavailable { .. } => Severity::Medium,
+            Finding::SourceSizeMismatch { .. } => Severity::High,
+            Finding::IpLiteralHost { .. } => Severity::Medium,
+            Finding::ConfusableHost { .. } => Severity::Medium,
+            Finding::NetworkFetchInBuild { .. } => Severity::High,
+            Finding::EvalDownloadedContent { .. } => Severity::High,
+            Finding::SudoInPkgbuild { .. } => Severity::Medium,
+            Finding::WriteOutsideBuildDir { .. } => Severity::Medium,
+            Finding::PipeDownloadToShell { .. } => Severity::High,
+            Finding::DangerousInstallScriptlet { .. } => Severity::High,
+            Finding::DlagentsOverride {
+                disables_tls: true, ..
+            } => Severity::High,
+            Finding::DlagentsOverride { .. } => Severity::Medium,
+            Finding::ChecksumMismatch { .. } => Severity::High,
+            Finding::GitPinNotUpstream { .. } => Severity::High,
+            Finding::GitTagSignatureInvalid { .. } => Severity::High,
+            Finding::PkgverMismatch { .. } => Severity::Medium,
+            Finding::UnversionedUrl { .. } => Severity::Low,
+            Finding::UnpinnedSourceVariable { .. } => Severity::Medium,
+            Finding::GitTagHashNotTagObject { .. } => Severity::Low,
+            Finding::SignatureSchemeMismatch { .. } => Severity::Medium,
+            Finding::OrphanedSignature { .. } => Severity::Low,
+            Finding::CredentialsInUrl { .. } => Severity::High,
+            Finding::MalformedUrl { .. } => Severity::Medium,
+            Finding::DuplicateSource { .. } => Severity::Low,
+            Finding::MissingLocalSource { .. } => Severity::Medium,
+            Finding::PkgverNotInSources { .. } => Severity::Info,
+            Finding::GithubGeneratedArchive { .. } => Severity::Info,
+            Finding::VerbatimArtifactInsecurePin { .. } => Severity::High,
+            Finding::GitSshTransport { .. } => Severity::Low,
+            Finding::SrcinfoDrift { .. } => Severity::Medium,
+            Finding::CheckSkipped { .. } => Severity::Info,
+            Finding::SecurityAdvisory { .. } => Severity::High,
+        }
+    }
 
-        for finding in findings {
-            let key: &'static str = finding.into();
-            if filters.is_empty() || filters.contains(key) {
-                warn!("{:?}: {}", target.display(), finding);
-                has_findings = true;
+    /// The url of the source this finding is about, if any
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Finding::InsecureScheme { source, .. } => Some(source.url()),
+            Finding::UnknownScheme { source, .. } => Some(source.url()),
+            Finding::WrongNumberOfChecksums { .. } => None,
+            Finding::UnknownChecksumAlgorithm { .. } => None,
+            Finding::GitCommitInsecurePin(source) => Some(source.url()),
+            Finding::GitTagInsecurePin(source) => Some(source.url()),
+            Finding::GitBranchInsecurePin(source) => Some(source.url()),
+            Finding::GitHeadInsecurePin(source) => Some(source.url()),
+            Finding::SvnInsecurePin(source) => Some(source.url()),
+            Finding::HgRevisionInsecurePin(source) => Some(source.url()),
+            Finding::BzrInsecurePin(source) => Some(source.url()),
+            Finding::FossilCommitInsecurePin(source) => Some(source.url()),
+            Finding::DarcsInsecurePin(source) => Some(source.url()),
+            Finding::VcsPackageInsecurePin { url, .. } => Some(url),
+            Finding::UrlArtifactInsecurePin(source) => Some(source.url()),
+            Finding::PypiSourceNotVersionPinned { url } => Some(url),
+            Finding::PypiChecksumMismatch { url, .. } => Some(url),
+            Finding::GoSumdbMismatch { url, .. } => Some(url),
+            Finding::NpmChecksumMismatch { url, .. } => Some(url),
+            Finding::NpmChecksumUncorroborated { url } => Some(url),
+            Finding::UnusedUpstreamSignature { url, .. } => Some(url),
+            Finding::InvalidValidpgpkeys { .. } => None,
+            Finding::UnusableValidpgpkeys { .. } => None,
+            Finding::ValidpgpkeysNotInWkd { .. } => None,
+            Finding::SignatureWithoutValidpgpkeys { .. } => None,
+            Finding::GitSignedWithoutValidpgpkeys(source) => Some(source.url()),
+            Finding::UrlArtifactChecksumSkipped(source) => Some(source.url()),
+            Finding::SourceUnavailable { url, .. } => Some(url),
+            Finding::SourceSizeMismatch { url, .. } => Some(url),
+            Finding::IpLiteralHost { source, .. } => Some(source.url()),
+            Finding::ConfusableHost { source, .. } => Some(source.url()),
+            Finding::NetworkFetchInBuild { .. } => None,
+            Finding::EvalDownloadedContent { .. } => None,
+            Finding::SudoInPkgbuild { .. } => None,
+            Finding::WriteOutsideBuildDir { .. } => None,
+            Finding::PipeDownloadToShell { .. } => None,
+            Finding::DangerousInstallScriptlet { .. } => None,
+            Finding::DlagentsOverride { .. } => None,
+            Finding::ChecksumMismatch { url, .. } => Some(url),
+            Finding::GitPinNotUpstream { source, .. } => Some(source.url()),
+            Finding::GitTagSignatureInvalid { source, .. } => Some(source.url()),
+            Finding::PkgverMismatch { url, .. } => Some(url),
+            Finding::UnversionedUrl { url, .. } => Some(url),
+            Finding::UnpinnedSourceVariable { .. } => None,
+            Finding::GitTagHashNotTagObject { source, .. } => Some(source.url()),
+            Finding::SignatureSchemeMismatch { signature, .. } => Some(signature),
+            Finding::OrphanedSignature { signature } => Some(signature),
+            Finding::CredentialsInUrl { url, .. } => Some(url),
+            Finding::MalformedUrl { source, .. } => Some(source.url()),
+            Finding::DuplicateSource { source, .. } => Some(source.url()),
+            Finding::MissingLocalSource { .. } => None,
+            Finding::PkgverNotInSources { .. } => None,
+            Finding::GithubGeneratedArchive { url } => Some(url),
+            Finding::VerbatimArtifactInsecurePin { source, .. } => Some(source.url()),
+            Finding::GitSshTransport { source } => Some(source.url()),
+            Finding::SrcinfoDrift { .. } => None,
+            Finding::CheckSkipped { .. } => None,
+            Finding::SecurityAdvisory { .. } => None,
+        }
+    }
+
+    /// A suggested fix for the PKGBUILD, if there's a known way to resolve this finding
+    pub fn fix(&self) -> Option<String> {
+        Some(match self {
+            Finding::InsecureScheme {
+                suggestion: Some(suggestion),
+                ..
+            } => format!("Download the source from {:?}", suggestion),
+            Finding::InsecureScheme { .. } => {
+                "Download the source over https, or pin it with a cryptographically secure checksum"
+                    .to_string()
+            }
+            Finding::UnknownScheme { .. } => return None,
+            Finding::WrongNumberOfChecksums { alg, .. } => {
+                format!("Update {} with `updpkgsums`", alg)
+            }
+            Finding::UnknownChecksumAlgorithm { alg } => {
+                format!(
+                    "Remove {} or rename it to a checksum array makepkg supports",
+                    alg
+                )
+            }
+            Finding::GitCommitInsecurePin(_)
+            | Finding::GitTagInsecurePin(_)
+            | Finding::GitBranchInsecurePin(_)
+            | Finding::GitHeadInsecurePin(_) => {
+                "Pin the full commit hash with #commit= or the tag object hash with #tag="
+                    .to_string()
+            }
+            Finding::SvnInsecurePin(_)
+            | Finding::BzrInsecurePin(_)
+            | Finding::DarcsInsecurePin(_) => {
+                "Download a release archive and pin it with a cryptographically secure checksum"
+                    .to_string()
+            }
+            Finding::HgRevisionInsecurePin(_) => {
+                "Pin the full changeset hash with #revision=".to_string()
+            }
+            Finding::FossilCommitInsecurePin(_) => {
+                "Pin the full artifact hash with #commit=".to_string()
+            }
+            Finding::VcsPackageInsecurePin { .. } => return None,
+            Finding::UrlArtifactInsecurePin(_)
+            | Finding::UrlArtifactChecksumSkipped(_)
+            | Finding::VerbatimArtifactInsecurePin { .. } => {
+                "Pin the artifact with sha256sums or b2sums".to_string()
+            }
+            Finding::PypiSourceNotVersionPinned { .. } => {
+                "Download the sdist of a specific release from files.pythonhosted.org".to_string()
+            }
+            Finding::PypiChecksumMismatch { pypi, .. } => {
+                format!(
+                    "Verify the artifact, pypi publishes {} {}",
+                    pypi.alg(),
+                    pypi.value()
+                )
+            }
+            Finding::GoSumdbMismatch { .. } => {
+                "Verify the source code matches the module published in the go checksum database"
+                    .to_string()
+            }
+            Finding::NpmChecksumMismatch { npm, .. } => {
+                format!(
+                    "Verify the artifact, npm publishes {} {}",
+                    npm.alg(),
+                    npm.value()
+                )
+            }
+            Finding::NpmChecksumUncorroborated { .. } => {
+                "Add a sha512sums entry so the artifact can be compared with npm".to_string()
+            }
+            Finding::UnusedUpstreamSignature { signature, .. } => {
+                format!(
+                    "Add {:?} to source= and its signing key to validpgpkeys",
+                    signature
+                )
+            }
+            Finding::InvalidValidpgpkeys { .. } => {
+                "Use the full 40 character fingerprint of the key".to_string()
+            }
+            Finding::UnusableValidpgpkeys { .. } => {
+                "Update the key or replace it with a key that's still in use upstream".to_string()
+            }
+            Finding::ValidpgpkeysNotInWkd { .. } => return None,
+            Finding::SignatureWithoutValidpgpkeys { .. }
+            | Finding::GitSignedWithoutValidpgpkeys(_) => {
+                "Add the fingerprint of the upstream signing key to validpgpkeys".to_string()
+            }
+            Finding::SourceUnavailable { .. } => {
+                "Find the new location of the source, or a mirror that serves the same file"
+                    .to_string()
+            }
+            Finding::SourceSizeMismatch { .. } | Finding::ChecksumMismatch { .. } => {
+                "Investigate why upstream serves a different file before updating the checksums"
+                    .to_string()
+            }
+            Finding::IpLiteralHost { .. } | Finding::ConfusableHost { .. } => {
+                "Download the source from the canonical hostname of the project".to_string()
+            }
+            Finding::NetworkFetchInBuild { .. } => {
+                "Add the download to source= and pin it with a checksum".to_string()
+            }
+            Finding::EvalDownloadedContent { .. } | Finding::PipeDownloadToShell { .. } => {
+                "Add the script to source=, pin it with a checksum and run the local copy"
+                    .to_string()
+            }
+            Finding::SudoInPkgbuild { .. } => {
+                "Remove sudo, makepkg runs the build as an unprivileged user".to_string()
+            }
+            Finding::WriteOutsideBuildDir { .. } => "Only write to $srcdir and $pkgdir".to_string(),
+            Finding::DangerousInstallScriptlet { .. } => return None,
+            Finding::DlagentsOverride { .. } => {
+                "Remove the DLAGENTS override and use the download agents of makepkg".to_string()
+            }
+            Finding::GitPinNotUpstream { .. } => {
+                "Verify the pinned commit with upstream and pin a published commit".to_string()
+            }
+            Finding::GitTagSignatureInvalid { .. } => {
+                "Verify the tag with upstream and update validpgpkeys".to_string()
+            }
+            Finding::PkgverMismatch { version, .. } => {
+                format!("Set pkgver to {:?} or pin the matching release", version)
+            }
+            Finding::UnversionedUrl { .. } => {
+                "Download an archive of a specific release".to_string()
+            }
+            Finding::UnpinnedSourceVariable { variable, .. } => {
+                format!("Set {} to a full commit hash", variable)
+            }
+            Finding::GitTagHashNotTagObject { .. } => {
+                "Pin the hash with #commit= instead".to_string()
+            }
+            Finding::SignatureSchemeMismatch { .. } => {
+                "Download the artifact and its signature over https".to_string()
+            }
+            Finding::OrphanedSignature { signature } => {
+                format!(
+                    "Remove {:?} or add the artifact it signs to source=",
+                    signature
+                )
+            }
+            Finding::CredentialsInUrl { .. } => {
+                "Remove the credentials from the url and revoke them".to_string()
+            }
+            Finding::MalformedUrl { .. } => "Fix the quoting of the source url".to_string(),
+            Finding::DuplicateSource { .. } => {
+                "Remove the duplicate or download it to a different filename with `name::url`"
+                    .to_string()
+            }
+            Finding::MissingLocalSource { file } => {
+                format!(
+                    "Add {:?} to the package directory or remove it from the PKGBUILD",
+                    file
+                )
+            }
+            Finding::PkgverNotInSources { .. } => return None,
+            Finding::GithubGeneratedArchive { .. } => {
+                "Use a release asset uploaded by upstream or a git source pinned to a commit"
+                    .to_string()
+            }
+            Finding::GitSshTransport { .. } => "Clone the repository over https".to_string(),
+            Finding::SrcinfoDrift { .. } => {
+                "Regenerate the .SRCINFO with `makepkg --printsrcinfo > .SRCINFO`".to_string()
+            }
+            Finding::CheckSkipped { .. } => return None,
+            Finding::SecurityAdvisory { .. } => {
+                "Update the affected dependencies, or the source to a release that does".to_string()
+            }
+        })
+    }
+
+    /// The finding together with the fields that are derived from it, for structured output
+    pub fn record(&self) -> FindingRecord<'_> {
+        FindingRecord {
+            severity: self.severity(),
+            description: self.description(),
+            source_url: self.url(),
+            fix: self.fix(),
+            finding: self,
+        }
+    }
+
+    pub fn is_selected(&self, filters: &HashSet<String>, min_severity: Option<Severity>) -> bool {
+        if let Some(min_severity) = min_severity {
+            if self.severity() < min_severity {
+                return false;
             }
         }
 
-        has_findings
+        let key: &'static str = self.into();
+        filters.is_empty() || filters.contains(key)
     }
 }
 
 impl fmt::Display for Finding {
     fn fmt(&self, w: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Finding::InsecureScheme { scheme, source } => {
-                write!(w, "Using insecure {}:// scheme: {:?}", scheme, source)
+            Finding::InsecureScheme {
+                scheme,
+                source,
+                suggestion,
+            } => {
+                write!(w, "Using insecure {}:// scheme: {:?}", scheme, source)?;
+                if let Some(suggestion) = suggestion {
+                    write!(w, " (also available at {:?})", suggestion)?;
+                }
+                Ok(())
             }
-            Finding::UnknownScheme((scheme, source)) => {
+            Finding::UnknownScheme { scheme, source } => {
                 write!(w, "Unknown scheme {:?}: {:?}", scheme, source)
             }
             Finding::WrongNumberOfChecksums { sources, alg, sums } => {
@@ -168,9 +884,25 @@ impl fmt::Display for Finding {
                     sources, alg, sums,
                 )
             }
+            Finding::UnknownChecksumAlgorithm { alg } => {
+                write!(
+                    w,
+                    "Unknown checksum algorithm, checksums are ignored: {:?}",
+                    alg
+                )
+            }
             Finding::GitCommitInsecurePin(source) => {
                 write!(w, "Git commit is not securely pinned: {:?}", source)
             }
+            Finding::GitTagInsecurePin(source) => {
+                write!(w, "Git source is pinned by tag name: {:?}", source)
+            }
+            Finding::GitBranchInsecurePin(source) => {
+                write!(w, "Git source follows a branch: {:?}", source)
+            }
+            Finding::GitHeadInsecurePin(source) => {
+                write!(w, "Git source builds HEAD: {:?}", source)
+            }
             Finding::SvnInsecurePin(source) => {
                 write!(
                     w,
@@ -188,6 +920,23 @@ impl fmt::Display for Finding {
                     source
                 )
             }
+            Finding::FossilCommitInsecurePin(source) => {
+                write!(w, "Fossil commit is not securely pinned: {:?}", source)
+            }
+            Finding::DarcsInsecurePin(source) => {
+                write!(
+                    w,
+                    "darcs is never a cryptographically secure pin: {:?}",
+                    source
+                )
+            }
+            Finding::VcsPackageInsecurePin { vcs, url } => {
+                write!(
+                    w,
+                    "VCS package follows {} source without pinning it: {:?}",
+                    vcs, url
+                )
+            }
             Finding::UrlArtifactInsecurePin(source) => {
                 write!(
                     w,
@@ -195,6 +944,316 @@ impl fmt::Display for Finding {
                     source
                 )
             }
+            Finding::PypiSourceNotVersionPinned { url } => {
+                write!(w, "Pypi source is not pinned to a release: {:?}", url)
+            }
+            Finding::PypiChecksumMismatch {
+                url,
+                pypi,
+                pkgbuild,
+            } => {
+                write!(
+                    w,
+                    "Checksum doesn't match pypi (pypi={:?}, pkgbuild={:?}): {:?}",
+                    pypi, pkgbuild, url
+                )
+            }
+            Finding::GoSumdbMismatch {
+                url,
+                module,
+                version,
+                sumdb,
+            } => {
+                write!(
+                    w,
+                    "Go module {}@{} doesn't match the go checksum database ({}): {:?}",
+                    module, version, sumdb, url
+                )
+            }
+            Finding::NpmChecksumMismatch { url, npm, pkgbuild } => {
+                write!(
+                    w,
+                    "Checksum doesn't match npm (npm={:?}, pkgbuild={:?}): {:?}",
+                    npm, pkgbuild, url
+                )
+            }
+            Finding::NpmChecksumUncorroborated { url } => {
+                write!(
+                    w,
+                    "npm only publishes sha512 and sha1 checksums, none of them are configured: {:?}",
+                    url
+                )
+            }
+            Finding::UnusedUpstreamSignature { url, signature } => {
+                write!(
+                    w,
+                    "Upstream signature is available but not used ({:?}): {:?}",
+                    signature, url
+                )
+            }
+            Finding::InvalidValidpgpkeys { key, issue } => {
+                write!(w, "Invalid validpgpkeys entry ({}): {:?}", issue, key)
+            }
+            Finding::UnusableValidpgpkeys { key, status } => {
+                write!(w, "Unusable validpgpkeys entry ({}): {:?}", status, key)
+            }
+            Finding::ValidpgpkeysNotInWkd { key, emails } => {
+                write!(
+                    w,
+                    "validpgpkeys entry is not published in the Web Key Directory of {:?}: {:?}",
+                    emails, key
+                )
+            }
+            Finding::SignatureWithoutValidpgpkeys { signatures } => {
+                write!(
+                    w,
+                    "Signatures are verified with any key in the keyring, validpgpkeys is empty: {:?}",
+                    signatures
+                )
+            }
+            Finding::GitSignedWithoutValidpgpkeys(source) => {
+                write!(
+                    w,
+                    "Git signature is verified with any key in the keyring, validpgpkeys is empty: {:?}",
+                    source
+                )
+            }
+            Finding::UrlArtifactChecksumSkipped(source) => {
+                write!(
+                    w,
+                    "Url artifact is not verified, all checksums are SKIP: {:?}",
+                    source
+                )
+            }
+            Finding::SourceUnavailable { url, reason } => {
+                write!(w, "Source is not available ({}): {:?}", reason, url)
+            }
+            Finding::SourceSizeMismatch {
+                url,
+                expected,
+                actual,
+            } => {
+                write!(
+                    w,
+                    "Source is served with a different size (expected={}, actual={}): {:?}",
+                    expected, actual, url
+                )
+            }
+            Finding::IpLiteralHost { host, source } => {
+                write!(w, "Source is hosted on ip address {}: {:?}", host, source)
+            }
+            Finding::ConfusableHost {
+                host,
+                issue,
+                source,
+            } => {
+                write!(
+                    w,
+                    "Source hostname {:?} might be a lookalike domain ({}): {:?}",
+                    host, issue, source
+                )
+            }
+            Finding::NetworkFetchInBuild { function, command } => {
+                write!(
+                    w,
+                    "Network access in {}(), the download isn't pinned: {:?}",
+                    function, command
+                )
+            }
+            Finding::EvalDownloadedContent { line, code } => {
+                write!(
+                    w,
+                    "Downloaded content is evaluated on line {}: {:?}",
+                    line, code
+                )
+            }
+            Finding::SudoInPkgbuild { line, code } => {
+                write!(w, "sudo is used on line {}: {:?}", line, code)
+            }
+            Finding::WriteOutsideBuildDir { line, code } => {
+                write!(
+                    w,
+                    "Writing outside of $srcdir and $pkgdir on line {}: {:?}",
+                    line, code
+                )
+            }
+            Finding::PipeDownloadToShell { line, code } => {
+                write!(
+                    w,
+                    "Download is piped into a shell on line {}: {:?}",
+                    line, code
+                )
+            }
+            Finding::DangerousInstallScriptlet {
+                file,
+                issue,
+                line,
+                code,
+            } => {
+                write!(
+                    w,
+                    "Install scriptlet {:?} is dangerous ({}) on line {}: {:?}",
+                    file, issue, line, code
+                )
+            }
+            Finding::DlagentsOverride {
+                agents,
+                disables_tls,
+            } => {
+                if *disables_tls {
+                    write!(
+                        w,
+                        "DLAGENTS is overridden and disables certificate verification: {:?}",
+                        agents
+                    )
+                } else {
+                    write!(w, "DLAGENTS is overridden: {:?}", agents)
+                }
+            }
+            Finding::ChecksumMismatch {
+                url,
+                expected,
+                actual,
+            } => {
+                write!(
+                    w,
+                    "Downloaded file doesn't match checksum (expected={:?}, actual={:?}): {:?}",
+                    expected, actual, url
+                )
+            }
+            Finding::GitPinNotUpstream { source, issue } => {
+                write!(w, "Pinned git commit is {}: {:?}", issue, source)
+            }
+            Finding::GitTagSignatureInvalid { source, issue, key } => {
+                if let Some(key) = key {
+                    write!(
+                        w,
+                        "Git tag signature is {} (key={}): {:?}",
+                        issue, key, source
+                    )
+                } else {
+                    write!(w, "Git tag signature is {}: {:?}", issue, source)
+                }
+            }
+            Finding::PkgverMismatch {
+                pkgver,
+                version,
+                url,
+            } => {
+                write!(
+                    w,
+                    "Source is for version {:?} but pkgver is {:?}: {:?}",
+                    version, pkgver, url
+                )
+            }
+            Finding::UnversionedUrl { url, keyword } => {
+                if let Some(keyword) = keyword {
+                    write!(
+                        w,
+                        "Url refers to {:?} instead of a version: {:?}",
+                        keyword, url
+                    )
+                } else {
+                    write!(w, "Url has no version component: {:?}", url)
+                }
+            }
+            Finding::UnpinnedSourceVariable {
+                variable,
+                value,
+                line,
+            } => {
+                write!(
+                    w,
+                    "Variable ${} used in source on line {} is not a commit hash: {:?}",
+                    variable, line, value
+                )
+            }
+            Finding::GitTagHashNotTagObject { source, name } => {
+                if let Some(name) = name {
+                    write!(
+                        w,
+                        "Hash in #tag= is the commit of tag {:?}, not the tag object: {:?}",
+                        name, source
+                    )
+                } else {
+                    write!(w, "Hash in #tag= is not a tag upstream: {:?}", source)
+                }
+            }
+            Finding::SignatureSchemeMismatch {
+                artifact,
+                signature,
+            } => {
+                write!(
+                    w,
+                    "Artifact and signature use different schemes: {:?}, {:?}",
+                    artifact, signature
+                )
+            }
+            Finding::OrphanedSignature { signature } => {
+                write!(w, "Signature has no matching artifact: {:?}", signature)
+            }
+            Finding::CredentialsInUrl { url, user } => {
+                write!(w, "Url contains credentials for user {:?}: {:?}", user, url)
+            }
+            Finding::MalformedUrl { issue, source } => {
+                write!(w, "Url is malformed ({}): {:?}", issue, source)
+            }
+            Finding::DuplicateSource {
+                var,
+                filename,
+                source,
+            } => {
+                write!(
+                    w,
+                    "Source is listed more than once in {} (filename={:?}): {:?}",
+                    var, filename, source
+                )
+            }
+            Finding::MissingLocalSource { file } => {
+                write!(w, "Local source file is missing: {:?}", file)
+            }
+            Finding::PkgverNotInSources { pkgver } => {
+                write!(w, "pkgver {:?} doesn't show up in any source", pkgver)
+            }
+            Finding::GithubGeneratedArchive { url } => {
+                write!(
+                    w,
+                    "Auto-generated archive, consider a release asset or a git source pinned by commit: {:?}",
+                    url
+                )
+            }
+            Finding::VerbatimArtifactInsecurePin { source, noextract } => {
+                if *noextract {
+                    write!(
+                        w,
+                        "Artifact in noextract is not securely pinned by checksums: {:?}",
+                        source
+                    )
+                } else {
+                    write!(
+                        w,
+                        "Executable artifact is not securely pinned by checksums: {:?}",
+                        source
+                    )
+                }
+            }
+            Finding::GitSshTransport { source } => {
+                write!(w, "Git source uses ssh: {:?}", source)
+            }
+            Finding::SrcinfoDrift {
+                key,
+                pkgbuild,
+                srcinfo,
+            } => {
+                write!(
+                    w,
+                    "{} differs between PKGBUILD and .SRCINFO: {:?} != {:?}",
+                    key, pkgbuild, srcinfo
+                )
+            }
+            Finding::CheckSkipped { check } => {
+                write!(w, "Skipped --{} because of --offline", check)
+            }
             Finding::SecurityAdvisory { source, packages } => {
                 write!(
                     w,
@@ -204,7 +1263,7 @@ impl fmt::Display for Finding {
                     source,
                 )?;
                 let mut first = true;
-                for groups in &packages.groups {
+                if let Some(groups) = &packages.groups {
                     for group in groups {
                         for id in &group.ids {
                             if first {
@@ -222,211 +1281,2234 @@ impl fmt::Display for Finding {
     }
 }
 
-pub async fn check_pkg(target: &Target, discover_sigs: bool) -> Result<Vec<Finding>> {
-    let client = reqwest::Client::builder()
-        .user_agent(concat!(
-            env!("CARGO_PKG_NAME"),
-            "/",
-            env!("CARGO_PKG_VERSION"),
-        ))
-        .build()?;
+/// Options for getting the PKGBUILD of a target
+#[derive(Debug, Default, Clone)]
+pub struct CheckoutOptions {
+    /// How to fetch the PKGBUILD of official packages
+    pub backend: asp::Backend,
+    /// The version of official packages to checkout instead of the latest
+    pub package_version: Option<String>,
+    /// Check directories in a git repository at this revision instead of the working tree
+    pub rev: Option<String>,
+    /// Fail for targets that need to be downloaded
+    pub offline: bool,
+}
+
+/// Get a local directory with the PKGBUILD of a target, the temporary directory
+/// (if any) needs to be kept around for as long as the path is in use
+pub async fn checkout(
+    client: &reqwest::Client,
+    target: &Target,
+    opts: &CheckoutOptions,
+) -> Result<(Option<TempDir>, PathBuf)> {
+    if opts.offline
+        && matches!(
+            target,
+            Target::ArchBuildSystem(_)
+                | Target::ArchBuildSystemRelease { .. }
+                | Target::Aur(_)
+                | Target::Url(_)
+        )
+    {
+        bail!("Can't checkout package, network access is disabled");
+    }
 
-    let (_temp_dir, path) = match &target {
+    let (temp_dir, path) = match &target {
         Target::ArchBuildSystem(pkg) => {
             let tmp = tempfile::Builder::new()
                 .prefix("archlinux-inputs-fsck")
                 .tempdir()?;
-            let path = asp::checkout_package(tmp.path(), pkg).await?;
+            let path = asp::checkout(
+                client,
+                opts.backend,
+                opts.package_version.as_deref(),
+                tmp.path(),
+                pkg,
+            )
+            .await?;
+            (Some(tmp), path)
+        }
+        Target::ArchBuildSystemRelease { pkgbase, version } => {
+            let tmp = tempfile::Builder::new()
+                .prefix("archlinux-inputs-fsck")
+                .tempdir()?;
+            let path =
+                asp::checkout(client, opts.backend, Some(version), tmp.path(), pkgbase).await?;
+            (Some(tmp), path)
+        }
+        Target::Aur(pkg) => {
+            let tmp = tempfile::Builder::new()
+                .prefix("archlinux-inputs-fsck")
+                .tempdir()?;
+            let path = aur::checkout_package(tmp.path(), pkg).await?;
+            (Some(tmp), path)
+        }
+        Target::BuildPath(path) => {
+            if let Some(rev) = &opts.rev {
+                let tmp = tempfile::Builder::new()
+                    .prefix("archlinux-inputs-fsck")
+                    .tempdir()?;
+                git::export_rev(path, rev, tmp.path()).await?;
+                let path = tmp.path().to_path_buf();
+                (Some(tmp), path)
+            } else {
+                (None, PathBuf::from(path))
+            }
+        }
+        Target::SourcePackage(tarball) => {
+            let tmp = tempfile::Builder::new()
+                .prefix("archlinux-inputs-fsck")
+                .tempdir()?;
+            let path = srcpkg::extract(tarball, tmp.path())?;
+            (Some(tmp), path)
+        }
+        Target::Stdin(pkgbuild) => {
+            let tmp = tempfile::Builder::new()
+                .prefix("archlinux-inputs-fsck")
+                .tempdir()?;
+            fs::write(tmp.path().join("PKGBUILD"), pkgbuild)?;
+            let path = tmp.path().to_path_buf();
+            (Some(tmp), path)
+        }
+        Target::Url(url) => {
+            let tmp = tempfile::Builder::new()
+                .prefix("archlinux-inputs-fsck")
+                .tempdir()?;
+            remote::fetch_pkgbuild(client, url, tmp.path()).await?;
+            let path = tmp.path().to_path_buf();
             (Some(tmp), path)
         }
-        Target::BuildPath(path) => (None, PathBuf::from(path)),
     };
 
     let pkgbuild_path = path.join("PKGBUILD");
-    if !pkgbuild_path.exists() {
+    if !pkgbuild_path.exists() && !path.join(".SRCINFO").exists() {
         bail!("Missing PKGBUILD: {:?}", pkgbuild_path);
     }
 
-    let sources = makepkg::list_sources(&path).await?;
-    debug!("Found sources: {:?}", sources);
+    Ok((temp_dir, path))
+}
 
-    let mut findings = Vec::new();
+/// Classify a source by its scheme, reporting schemes that are insecure or unknown
+fn classify_source(source: Source, findings: &mut Vec<Finding>) -> Result<AuthedSource> {
+    if let Some(host) = stats::host(source.url()) {
+        if host.parse::<IpAddr>().is_ok() {
+            findings.push(Finding::IpLiteralHost {
+                host: host.to_string(),
+                source: source.clone(),
+            });
+        }
+        if let Some(issue) = homograph::check_host(host) {
+            findings.push(Finding::ConfusableHost {
+                host: host.to_string(),
+                issue,
+                source: source.clone(),
+            });
+        }
+    }
 
-    let mut sources = sources
-        .into_iter()
-        .map(|source| {
-            let scheme = source.scheme();
-            Ok(match &scheme {
-                Some("https") => AuthedSource::url(source),
-                Some("http") => AuthedSource::url(source),
-                Some("ftp") => AuthedSource::url(source),
-                Some(scheme) if scheme.starts_with("git") => {
-                    if let "git" | "git+http" | "git+git" = *scheme {
-                        // Mark all insecure ones
-                        findings.push(Finding::InsecureScheme {
-                            scheme: scheme.to_string(),
-                            source: source.clone(),
-                        });
-                    } else if !matches!(*scheme, "git+https") {
-                        // Mark all that aren't known as secure as `unknown`
-                        findings.push(Finding::UnknownScheme((scheme.to_string(), source.clone())));
-                    }
+    if let Some(issue) = lint::check_url(source.url()) {
+        findings.push(Finding::MalformedUrl {
+            issue,
+            source: source.clone(),
+        });
+    }
 
-                    AuthedSource::Git(source.url().parse()?)
-                }
-                Some(scheme) if scheme.starts_with("svn") => {
-                    if let "svn" | "svn+http" = *scheme {
-                        // Mark all insecure ones
-                        findings.push(Finding::InsecureScheme {
-                            scheme: scheme.to_string(),
-                            source: source.clone(),
-                        });
-                    } else if !matches!(*scheme, "svn+https") {
-                        // Mark all that aren't known as secure as `unknown`
-                        findings.push(Finding::UnknownScheme((scheme.to_string(), source.clone())));
-                    }
+    if let Some(userinfo) = stats::userinfo(source.url()) {
+        let (user, password) = match userinfo.split_once(':') {
+            Some((user, password)) => (user, Some(password)),
+            None => (userinfo, None),
+        };
+        // a username without password is normal for ssh, eg. `git+ssh://git@github.com/...`
+        let is_ssh = source
+            .scheme()
+            .is_some_and(|scheme| scheme.ends_with("ssh"));
+        if password.is_some() || !(is_ssh || user == "anonymous") {
+            findings.push(Finding::CredentialsInUrl {
+                url: stats::redact_password(source.url()),
+                user: user.to_string(),
+            });
+        }
+    }
 
-                    AuthedSource::Svn(source.url().parse()?)
-                }
-                Some(scheme) if scheme.starts_with("hg") => {
-                    if *scheme == "hg+http" {
-                        // Mark all insecure ones
-                        findings.push(Finding::InsecureScheme {
-                            scheme: scheme.to_string(),
-                            source: source.clone(),
-                        });
-                    } else if !matches!(*scheme, "hg+https") {
-                        // Mark all that aren't known as secure as `unknown`
-                        findings.push(Finding::UnknownScheme((scheme.to_string(), source.clone())));
-                    }
+    let scheme = source.scheme();
+    Ok(match &scheme {
+        Some("https") => AuthedSource::url(source),
+        Some("http") => AuthedSource::url(source),
+        Some("ftp") => AuthedSource::url(source),
+        Some(scheme) if scheme.starts_with("git") => {
+            if let "git" | "git+http" | "git+git" = *scheme {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if *scheme == "git+ssh" {
+                findings.push(Finding::GitSshTransport {
+                    source: source.clone(),
+                });
+            } else if !matches!(*scheme, "git+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
 
-                    AuthedSource::Hg(source.url().parse()?)
-                }
-                Some(scheme) if scheme.starts_with("bzr") => {
-                    if *scheme == "bzr+http" {
-                        // Mark all insecure ones
-                        findings.push(Finding::InsecureScheme {
-                            scheme: scheme.to_string(),
-                            source: source.clone(),
-                        });
-                    } else if !matches!(*scheme, "bzr+https") {
-                        // Mark all that aren't known as secure as `unknown`
-                        findings.push(Finding::UnknownScheme((scheme.to_string(), source.clone())));
-                    }
+            AuthedSource::Git(source.url().parse()?)
+        }
+        Some(scheme) if scheme.starts_with("svn") => {
+            if let "svn" | "svn+http" = *scheme {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if !matches!(*scheme, "svn+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
 
-                    AuthedSource::Bzr(source.url().parse()?)
-                }
-                Some(scheme) => {
-                    findings.push(Finding::UnknownScheme((scheme.to_string(), source.clone())));
-                    AuthedSource::url(source)
-                }
-                None => AuthedSource::File(source.url().to_string()),
-            })
-        })
+            AuthedSource::Svn(source.url().parse()?)
+        }
+        Some(scheme) if scheme.starts_with("hg") => {
+            if *scheme == "hg+http" {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if !matches!(*scheme, "hg+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
+
+            AuthedSource::Hg(source.url().parse()?)
+        }
+        Some(scheme) if scheme.starts_with("bzr") => {
+            if *scheme == "bzr+http" {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if !matches!(*scheme, "bzr+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
+
+            AuthedSource::Bzr(source.url().parse()?)
+        }
+        Some(scheme) if scheme.starts_with("fossil") => {
+            if *scheme == "fossil+http" {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if !matches!(*scheme, "fossil+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
+
+            AuthedSource::Fossil(source.url().parse()?)
+        }
+        Some(scheme) if scheme.starts_with("darcs") => {
+            if *scheme == "darcs+http" {
+                // Mark all insecure ones
+                findings.push(Finding::InsecureScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                    suggestion: None,
+                });
+            } else if !matches!(*scheme, "darcs+https") {
+                // Mark all that aren't known as secure as `unknown`
+                findings.push(Finding::UnknownScheme {
+                    scheme: scheme.to_string(),
+                    source: source.clone(),
+                });
+            }
+
+            AuthedSource::Darcs(source.url().parse()?)
+        }
+        Some(scheme) => {
+            findings.push(Finding::UnknownScheme {
+                scheme: scheme.to_string(),
+                source: source.clone(),
+            });
+            AuthedSource::url(source)
+        }
+        None if git::is_scp_url(source.url()) => {
+            findings.push(Finding::GitSshTransport {
+                source: source.clone(),
+            });
+            AuthedSource::Git(source.url().parse()?)
+        }
+        None => AuthedSource::File(source.url().to_string()),
+    })
+}
+
+/// Attach the checksums of a checksum array to the sources by position. If the number of
+/// checksums doesn't match the number of sources the positions can't be trusted, so nothing
+/// is attached and a finding is returned instead.
+fn attach_checksums(
+    sources: &mut [AuthedSource],
+    var: &str,
+    alg: &str,
+    sums: Vec<String>,
+) -> Option<Finding> {
+    if sources.len() != sums.len() {
+        return Some(Finding::WrongNumberOfChecksums {
+            sources: sources.len(),
+            alg: var.to_string(),
+            sums: sums.len(),
+        });
+    }
+
+    for (i, (source, sum)) in sources.iter_mut().zip(sums).enumerate() {
+        let AuthedSource::Url(source) = source else {
+            continue;
+        };
+
+        if sum == "SKIP" {
+            source.skipped.push(var.to_string());
+        } else if let Some(cm) = Checksum::new(alg, sum) {
+            debug!("Found checksum for #{}: {:?}", i, cm);
+            source.checksums.push(cm);
+        }
+    }
+
+    None
+}
+
+/// Parse a `source=` array (or `source_$arch=` with a suffix) and attach the checksums to each source
+async fn parse_source_array(
+    pkgbuild: &Pkgbuild,
+    suffix: &str,
+    sources: Vec<Source>,
+    checksum_arrays: &[String],
+    findings: &mut Vec<Finding>,
+) -> Result<Vec<AuthedSource>> {
+    let mut sources = sources
+        .into_iter()
+        .map(|source| classify_source(source, findings))
         .collect::<Result<Vec<_>>>()?;
 
-    for alg in makepkg::SUPPORTED_ALGS {
-        let sums = makepkg::list_variable(&path, alg).await?;
+    for var in checksum_arrays {
+        let alg = if suffix.is_empty() {
+            var.as_str()
+        } else if let Some(alg) = var.strip_suffix(suffix) {
+            alg
+        } else {
+            continue;
+        };
+        if alg.contains('_') {
+            continue;
+        }
+
+        let sums = pkgbuild.list_variable(var).await?;
         if sums.is_empty() {
             continue;
         }
 
-        debug!("Found checksums ({}): {:?}", alg, sums);
+        debug!("Found checksums ({}): {:?}", var, sums);
+
+        if !makepkg::SUPPORTED_ALGS.contains(&alg) {
+            findings.push(Finding::UnknownChecksumAlgorithm { alg: var.clone() });
+            continue;
+        }
+
+        if let Some(finding) = attach_checksums(&mut sources, var, alg, sums) {
+            findings.push(finding);
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Parse the `source=` arrays of a PKGBUILD, including the architecture specific
+/// ones, and attach the checksums to each source
+pub async fn parse_sources(
+    pkgbuild: &Pkgbuild,
+    findings: &mut Vec<Finding>,
+) -> Result<Vec<AuthedSource>> {
+    let mut suffixes = vec![String::new()];
+    for arch in pkgbuild.list_variable("arch").await? {
+        if arch != "any" {
+            suffixes.push(format!("_{}", arch));
+        }
+    }
+
+    let checksum_arrays = pkgbuild.list_checksum_arrays().await?;
+    debug!("Found checksum arrays: {:?}", checksum_arrays);
+
+    let common = pkgbuild.list_sources("source").await?;
+    let mut sources = Vec::new();
+    for suffix in suffixes {
+        let var = format!("source{}", suffix);
+        let array = if suffix.is_empty() {
+            common.clone()
+        } else {
+            pkgbuild.list_sources(&var).await?
+        };
+        debug!("Found sources ({}): {:?}", var, array);
+
+        // arch specific sources are appended to the common ones by makepkg
+        let previous = if suffix.is_empty() { &[][..] } else { &common };
+        findings.extend(find_duplicate_sources(&var, previous, &array));
+
+        sources.extend(
+            parse_source_array(pkgbuild, &suffix, array, &checksum_arrays, findings).await?,
+        );
+    }
+
+    Ok(sources)
+}
 
-        if sources.len() != sums.len() {
-            findings.push(Finding::WrongNumberOfChecksums {
-                sources: sources.len(),
-                alg,
-                sums: sums.len(),
+/// Find sources that are listed more than once, by url or by the filename they're downloaded to
+fn find_duplicate_sources(var: &str, previous: &[Source], sources: &[Source]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (i, source) in sources.iter().enumerate() {
+        let filename = source.destination();
+        let is_duplicate = previous
+            .iter()
+            .chain(&sources[..i])
+            .any(|other| other.url() == source.url() || other.destination() == filename);
+        if is_duplicate {
+            findings.push(Finding::DuplicateSource {
+                var: var.to_string(),
+                filename,
+                source: source.clone(),
             });
         }
+    }
+    findings
+}
 
-        for (i, sum) in sums.into_iter().enumerate() {
-            if sum == "SKIP" {
-                continue;
-            }
+/// Pick the finding for an insecurely pinned git source by the kind of fragment it uses
+fn git_insecure_pin(source: GitSource) -> Finding {
+    if source.commit().is_some() {
+        Finding::GitCommitInsecurePin(source)
+    } else if source.tag().is_some() {
+        Finding::GitTagInsecurePin(source)
+    } else if source.branch().is_some() {
+        Finding::GitBranchInsecurePin(source)
+    } else {
+        Finding::GitHeadInsecurePin(source)
+    }
+}
 
-            let cm = Checksum::new(alg, sum)?;
-            debug!("Found checksum for #{}: {:?}", i, cm);
-            if let AuthedSource::Url(source) = &mut sources[i] {
-                source.checksums.push(cm);
-            }
+/// Packages with a `pkgver()` function (like `foo-git`) intentionally follow the latest
+/// upstream revision, report their unpinned VCS sources with a distinct finding
+fn vcs_insecure_pin(is_vcs_package: bool, vcs: &str, finding: Finding) -> Finding {
+    if is_vcs_package {
+        Finding::VcsPackageInsecurePin {
+            vcs: vcs.to_string(),
+            url: finding.url().unwrap_or_default().to_string(),
         }
+    } else {
+        finding
     }
+}
 
-    // if an upstream project has submodules it's normal for them to be listed
-    // in source= without pinning them by commit. As long as the primary repo
-    // is securely pinned it's fine, but there's no reliable way to determine which
-    // one is the primary one. So we just assume if any is pinned it's a-okay.
-    let has_any_secure_git_sources = sources.iter().any(|source| match source {
-        AuthedSource::Git(source) => source.is_commit_securely_pinned(),
-        _ => false,
-    });
+/// Options for checks that go beyond the PKGBUILD itself
+#[derive(Debug, Default, Clone)]
+pub struct CheckOptions {
+    /// Look for signed tags of github archive sources
+    pub discover_sigs: bool,
+    /// Authenticate GitHub api requests of `discover_sigs` for a higher rate limit
+    pub github_token: Option<github::Token>,
+    /// Compare sources with the metadata published by package registries (eg. pypi)
+    pub check_registries: bool,
+    /// Lookup the validpgpkeys on keyservers
+    pub check_keys: bool,
+    pub keyservers: Vec<String>,
+    /// Check if sources with an unauthenticated scheme are also served over https
+    pub probe_https: bool,
+    /// Send a request for every url source to check if it's still available
+    pub check_availability: bool,
+    /// Download every url source and verify it with the declared checksums
+    pub verify_sums: bool,
+    /// Confirm pinned git commits exist upstream and are reachable from the named tag
+    pub verify_git_pins: bool,
+    /// Verify signed git tags with the keys listed in validpgpkeys
+    pub verify_signed_tags: bool,
+    /// Only accept unpinned git sources if they're a submodule of a pinned git source
+    pub check_submodules: bool,
+    /// The checksum algorithms that count as securely pinned
+    pub checksum_policy: ChecksumPolicy,
+    /// How to get the PKGBUILD of a target
+    pub checkout: CheckoutOptions,
+    /// Checks that were requested but need network access, while it's disabled
+    pub skipped_checks: Vec<String>,
+    /// How the PKGBUILD is read
+    pub evaluator: Evaluator,
+    /// How the bash evaluator is isolated from the system
+    pub sandbox: Sandbox,
+    /// Sources that are accepted even though they'd be reported otherwise
+    pub source_policy: SourcePolicy,
+}
 
-    for source in sources {
-        debug!("source={:?}", source);
-        match source {
-            AuthedSource::File(_) => (),
-            AuthedSource::Url(source) => {
-                if source.is_signature_file() {
-                    debug!("Skipping signature file: {:?}", source);
-                    continue;
-                }
+/// Check packages for inputs that aren't cryptographically pinned, this is the entrypoint
+/// for using archlinux-inputs-fsck as a library
+///
+/// ```no_run
+/// use archlinux_inputs_fsck::fsck::{Fsck, Target};
+/// use archlinux_inputs_fsck::makepkg::Evaluator;
+///
+/// # async fn run() -> anyhow::Result<()> {
+/// let pkg = Fsck::builder()
+///     .discover_sigs(true)
+///     .evaluator(Evaluator::Srcinfo)
+///     .check(&Target::ArchBuildSystem("zstd".to_string()))
+///     .await?;
+/// for finding in pkg.findings {
+///     println!("{}: {}", finding.severity(), finding);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Fsck {
+    opts: CheckOptions,
+    registry: Registry,
+    cache: Option<Cache>,
+    client: Option<reqwest::Client>,
+}
 
-                if !source
-                    .checksums
-                    .iter()
-                    .any(|x| x.is_checksum_securely_pinned())
-                {
-                    findings.push(Finding::UrlArtifactInsecurePin(source.clone()));
-                }
+impl Fsck {
+    pub fn builder() -> FsckBuilder {
+        FsckBuilder::default()
+    }
 
-                /*
-                let re =
-                    Regex::new(r"^https://gitlab.com/[^/]+/([^/]+)/-/archive/(.+)/[^/]+.tar.gz$")?;
-                */
-
-                if discover_sigs {
-                    if let Some(upstream) = github::detect_signed_tag_from_url(&source.url)? {
-                        let tag = github::fetch_tag(
-                            &client,
-                            &upstream.owner,
-                            &upstream.name,
-                            &upstream.tag,
-                        )
-                        .await?;
-                        if tag.object.r#type == "tag" {
-                            info!(
-                                "✨ There's likely a signed tag here we could use: {:?}",
-                                tag
-                            );
-                        }
-                    }
+    pub fn options(&self) -> &CheckOptions {
+        &self.opts
+    }
+
+    /// Checkout a target and check its PKGBUILD
+    pub async fn check(&self, target: &Target) -> Result<PackageFindings> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => http_client()?,
+        };
+        check_pkg(
+            target,
+            &self.opts,
+            &self.registry,
+            self.cache.as_ref(),
+            &client,
+        )
+        .await
+    }
+}
+
+/// Configure a [`Fsck`], all checks that need network access beyond getting the PKGBUILD are disabled by default
+#[derive(Debug, Clone)]
+pub struct FsckBuilder {
+    opts: CheckOptions,
+    registry: Registry,
+    cache: Option<Cache>,
+    client: Option<reqwest::Client>,
+}
+
+impl Default for FsckBuilder {
+    fn default() -> Self {
+        FsckBuilder {
+            opts: CheckOptions {
+                keyservers: vec![pgp::DEFAULT_KEYSERVER.to_string()],
+                ..Default::default()
+            },
+            registry: Registry::default(),
+            cache: None,
+            client: None,
+        }
+    }
+}
+
+impl FsckBuilder {
+    pub fn discover_sigs(mut self, enabled: bool) -> Self {
+        self.opts.discover_sigs = enabled;
+        self
+    }
+
+    pub fn github_token(mut self, token: Option<github::Token>) -> Self {
+        self.opts.github_token = token;
+        self
+    }
+
+    pub fn check_registries(mut self, enabled: bool) -> Self {
+        self.opts.check_registries = enabled;
+        self
+    }
+
+    pub fn check_keys(mut self, enabled: bool) -> Self {
+        self.opts.check_keys = enabled;
+        self
+    }
+
+    /// The keyservers to lookup validpgpkeys on, defaults to keys.openpgp.org
+    pub fn keyservers(mut self, keyservers: Vec<String>) -> Self {
+        self.opts.keyservers = keyservers;
+        self
+    }
+
+    pub fn probe_https(mut self, enabled: bool) -> Self {
+        self.opts.probe_https = enabled;
+        self
+    }
+
+    pub fn check_availability(mut self, enabled: bool) -> Self {
+        self.opts.check_availability = enabled;
+        self
+    }
+
+    pub fn verify_sums(mut self, enabled: bool) -> Self {
+        self.opts.verify_sums = enabled;
+        self
+    }
+
+    pub fn verify_git_pins(mut self, enabled: bool) -> Self {
+        self.opts.verify_git_pins = enabled;
+        self
+    }
+
+    pub fn verify_signed_tags(mut self, enabled: bool) -> Self {
+        self.opts.verify_signed_tags = enabled;
+        self
+    }
+
+    pub fn check_submodules(mut self, enabled: bool) -> Self {
+        self.opts.check_submodules = enabled;
+        self
+    }
+
+    pub fn checksum_policy(mut self, policy: ChecksumPolicy) -> Self {
+        self.opts.checksum_policy = policy;
+        self
+    }
+
+    pub fn backend(mut self, backend: asp::Backend) -> Self {
+        self.opts.checkout.backend = backend;
+        self
+    }
+
+    pub fn package_version(mut self, version: Option<String>) -> Self {
+        self.opts.checkout.package_version = version;
+        self
+    }
+
+    pub fn rev(mut self, rev: Option<String>) -> Self {
+        self.opts.checkout.rev = rev;
+        self
+    }
+
+    /// Don't access the network, the checks that need it are reported with a `check-skipped` finding
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.opts.checkout.offline = offline;
+        self
+    }
+
+    pub fn evaluator(mut self, evaluator: Evaluator) -> Self {
+        self.opts.evaluator = evaluator;
+        self
+    }
+
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.opts.sandbox = sandbox;
+        self
+    }
+
+    pub fn source_policy(mut self, policy: SourcePolicy) -> Self {
+        self.opts.source_policy = policy;
+        self
+    }
+
+    /// Replace the lints that are run, eg. with [`Registry::empty`] to only run custom lints
+    pub fn registry(mut self, registry: Registry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Register an additional lint, it runs after the lints that are already registered
+    pub fn lint<L: Lint + 'static>(mut self, lint: L) -> Self {
+        self.registry.register(lint);
+        self
+    }
+
+    /// Reuse the results of packages that didn't change since they were checked with the same options
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Share an http client (and its connection pool) between checks, a new client is created for every check otherwise
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn build(self) -> Fsck {
+        let mut opts = self.opts;
+        if opts.checkout.offline {
+            for (name, enabled) in [
+                ("discover-sigs", &mut opts.discover_sigs),
+                ("check-registries", &mut opts.check_registries),
+                ("check-keys", &mut opts.check_keys),
+                ("probe-https", &mut opts.probe_https),
+                ("check-availability", &mut opts.check_availability),
+                ("verify-sums", &mut opts.verify_sums),
+                ("verify-git-pins", &mut opts.verify_git_pins),
+                ("verify-signed-tags", &mut opts.verify_signed_tags),
+                ("check-submodules", &mut opts.check_submodules),
+            ] {
+                if *enabled {
+                    opts.skipped_checks.push(name.to_string());
+                    *enabled = false;
                 }
             }
-            AuthedSource::Git(source) => {
-                if !has_any_secure_git_sources && !source.is_commit_securely_pinned() {
-                    findings.push(Finding::GitCommitInsecurePin(source));
+        }
+        Fsck {
+            opts,
+            registry: self.registry,
+            cache: self.cache,
+            client: self.client,
+        }
+    }
+
+    /// Build the [`Fsck`] and check a single target
+    pub async fn check(self, target: &Target) -> Result<PackageFindings> {
+        self.build().check(target).await
+    }
+}
+
+/// Check if a url source is still available, and compare the size with the file makepkg downloaded before (if any)
+async fn check_availability(
+    client: &reqwest::Client,
+    path: &Path,
+    source: &UrlSource,
+    findings: &mut Vec<Finding>,
+) {
+    if !source.url.starts_with("https://") && !source.url.starts_with("http://") {
+        return;
+    }
+
+    match probe::check_availability(client, &source.url).await {
+        probe::Availability::Available { size: Some(actual) } => {
+            let filename = source.destination();
+            if filename.is_empty() {
+                return;
+            }
+            if let Ok(metadata) = fs::metadata(path.join(&filename)) {
+                let expected = metadata.len();
+                if expected != actual {
+                    findings.push(Finding::SourceSizeMismatch {
+                        url: source.url.clone(),
+                        expected,
+                        actual,
+                    });
                 }
             }
-            AuthedSource::Svn(source) => {
-                findings.push(Finding::SvnInsecurePin(source));
+        }
+        probe::Availability::Available { size: None } => (),
+        probe::Availability::Status(status) => findings.push(Finding::SourceUnavailable {
+            url: source.url.clone(),
+            reason: format!("http status {}", status),
+        }),
+        probe::Availability::Error(err) => findings.push(Finding::SourceUnavailable {
+            url: source.url.clone(),
+            reason: err,
+        }),
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    url.split_once("://").map(|(_, url)| url).unwrap_or(url)
+}
+
+/// Pair every signature file with the artifact it signs, by url or by filename
+///
+/// A signature may also be for the uncompressed artifact, eg. `foo.tar.sign` for `foo.tar.xz`.
+fn check_signature_pairs(sources: &[AuthedSource]) -> Vec<Finding> {
+    let urls = sources
+        .iter()
+        .filter_map(|source| match source {
+            AuthedSource::Url(source) => Some(source),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let signs = |signed: Option<&str>, name: Option<&str>| match (signed, name) {
+        (Some(signed), Some(name)) => {
+            name == signed
+                || name
+                    .strip_prefix(signed)
+                    .is_some_and(|ext| ext.starts_with('.'))
+        }
+        _ => false,
+    };
+    let strip_ext = |name: &str| -> Option<String> {
+        let (name, _) = name.rsplit_once('.')?;
+        Some(name.to_string())
+    };
+
+    let mut findings = Vec::new();
+    for signature in urls.iter().filter(|source| source.is_signature_file()) {
+        let signed_url = strip_ext(strip_scheme(&signature.url));
+        let signed_filename = signature.filename.as_deref().and_then(strip_ext);
+        let artifact = urls.iter().find(|source| {
+            !source.is_signature_file()
+                && (signs(signed_url.as_deref(), Some(strip_scheme(&source.url)))
+                    || signs(signed_filename.as_deref(), source.filename.as_deref()))
+        });
+
+        if let Some(artifact) = artifact {
+            let scheme = |url: &str| url.split_once("://").map(|(scheme, _)| scheme.to_string());
+            if scheme(&artifact.url) != scheme(&signature.url) {
+                findings.push(Finding::SignatureSchemeMismatch {
+                    artifact: artifact.url.clone(),
+                    signature: signature.url.clone(),
+                });
             }
-            AuthedSource::Hg(source) => {
-                if !source.is_revision_securely_pinned() {
-                    findings.push(Finding::HgRevisionInsecurePin(source));
+        } else {
+            findings.push(Finding::OrphanedSignature {
+                signature: signature.url.clone(),
+            });
+        }
+    }
+    findings
+}
+
+/// Confirm a hash in `#tag=` refers to an annotated tag object upstream, and not to a commit
+async fn verify_tag_hash(source: &GitSource, hash: &str, findings: &mut Vec<Finding>) {
+    let tags = match git::ls_remote_tags(source.clone_url()).await {
+        Ok(tags) => tags,
+        Err(err) => {
+            findings.push(Finding::SourceUnavailable {
+                url: source.url().to_string(),
+                reason: format!("{:#}", err),
+            });
+            return;
+        }
+    };
+
+    let name = if let Some(tag) = tags.iter().find(|tag| tag.object == hash) {
+        if tag.is_annotated() {
+            info!("Tag object {} is tag {:?}", hash, tag.name);
+            return;
+        }
+        Some(tag.name.clone())
+    } else {
+        tags.iter()
+            .find(|tag| tag.commit == hash)
+            .map(|tag| tag.name.clone())
+    };
+
+    findings.push(Finding::GitTagHashNotTagObject {
+        source: source.clone(),
+        name,
+    });
+}
+
+/// Collect the submodule urls of all securely pinned git sources
+///
+/// Returns `None` if any `.gitmodules` couldn't be fetched.
+async fn list_submodules(
+    sources: &[AuthedSource],
+    findings: &mut Vec<Finding>,
+) -> Option<HashSet<String>> {
+    let mut submodules = HashSet::new();
+    for source in sources {
+        let AuthedSource::Git(source) = source else {
+            continue;
+        };
+        let Some((commit, _)) = source.pinned_commit() else {
+            continue;
+        };
+
+        match git::fetch_file(source.clone_url(), commit, ".gitmodules").await {
+            Ok(Some(gitmodules)) => {
+                for url in git::submodule_urls(&gitmodules, source.clone_url()) {
+                    debug!("Found submodule in {:?}: {:?}", source.url(), url);
+                    submodules.insert(git::normalize_url(&url));
                 }
             }
-            AuthedSource::Bzr(source) => {
-                findings.push(Finding::BzrInsecurePin(source));
+            Ok(None) => (),
+            Err(err) => {
+                findings.push(Finding::SourceUnavailable {
+                    url: source.url().to_string(),
+                    reason: format!("{:#}", err),
+                });
+                return None;
             }
         }
     }
+    Some(submodules)
+}
+
+/// Compare the inputs declared in `.SRCINFO` with the evaluated PKGBUILD, the AUR only shows the `.SRCINFO` metadata
+async fn check_srcinfo(pkgbuild: &Pkgbuild, findings: &mut Vec<Finding>) -> Result<()> {
+    let srcinfo_path = pkgbuild.folder().join(".SRCINFO");
+    if !srcinfo_path.exists() {
+        return Ok(());
+    }
+    let text = fs::read_to_string(&srcinfo_path).context("Failed to read .SRCINFO")?;
+    let srcinfo = SrcInfo::parse(&text);
 
-    let validpgpkeys = makepkg::list_variable(&path, "validpgpkeys").await?;
-    if !validpgpkeys.is_empty() {
-        debug!("Found validpgpkeys={:?}", validpgpkeys);
+    let mut keys = srcinfo
+        .input_keys()
+        .map(String::from)
+        .collect::<BTreeSet<_>>();
+    keys.insert("source".to_string());
+    keys.insert("validpgpkeys".to_string());
+    for arch in pkgbuild.list_variable("arch").await? {
+        keys.insert(format!("source_{}", arch));
     }
+    keys.extend(pkgbuild.list_checksum_arrays().await?);
 
-    Ok(findings)
+    for key in keys {
+        let values = pkgbuild.list_variable(&key).await?;
+        let srcinfo = srcinfo.get(&key);
+        if values != srcinfo {
+            findings.push(Finding::SrcinfoDrift {
+                key,
+                pkgbuild: values,
+                srcinfo: srcinfo.to_vec(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the pkgver shows up in any remote source, otherwise the sources might be hardcoded to an old release
+fn check_pkgver_in_sources(sources: &[AuthedSource], pkgver: &str) -> Option<Finding> {
+    let mut has_remote_sources = false;
+    for source in sources {
+        let parts = match source {
+            AuthedSource::File(_) => continue,
+            AuthedSource::Url(source) => vec![Some(source.url()), source.filename()],
+            AuthedSource::Git(source) => vec![Some(source.url()), source.tag(), source.branch()],
+            AuthedSource::Svn(source) => vec![Some(source.url())],
+            AuthedSource::Hg(source) => vec![Some(source.url())],
+            AuthedSource::Bzr(source) => vec![Some(source.url())],
+            AuthedSource::Fossil(source) => vec![Some(source.url())],
+            AuthedSource::Darcs(source) => vec![Some(source.url())],
+        };
+        has_remote_sources = true;
+        if parts
+            .into_iter()
+            .flatten()
+            .any(|part| version::contains_pkgver(part, pkgver))
+        {
+            return None;
+        }
+    }
+
+    if has_remote_sources {
+        Some(Finding::PkgverNotInSources {
+            pkgver: pkgver.to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Compare the version of tags and release archives with the declared pkgver
+fn check_pkgver(
+    sources: &[AuthedSource],
+    pkgver: &str,
+    pkgnames: &[String],
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    for source in sources {
+        let (url, version) = match source {
+            AuthedSource::Git(source) => {
+                let Some(version) = source.tag().and_then(version::tag_version) else {
+                    continue;
+                };
+                (source.url(), version.to_string())
+            }
+            AuthedSource::Url(source) => {
+                let Some(version) =
+                    version::url_version(&source.url, source.filename.as_deref(), pkgnames)?
+                else {
+                    continue;
+                };
+                (source.url.as_str(), version)
+            }
+            _ => continue,
+        };
+
+        if !version::matches_pkgver(&version, pkgver) {
+            findings.push(Finding::PkgverMismatch {
+                pkgver: pkgver.to_string(),
+                version,
+                url: url.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Verify the signature of `?signed#tag=` sources with the keys listed in `validpgpkeys=`
+async fn verify_signed_tags(
+    client: &reqwest::Client,
+    keyservers: &[String],
+    sources: &[GitSource],
+    validpgpkeys: &[String],
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let sources = sources
+        .iter()
+        .filter_map(|source| Some((source, source.tag()?)))
+        .collect::<Vec<_>>();
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    let keyring = pgp::Keyring::new()?;
+    for key in validpgpkeys {
+        if pgp::validate_fingerprint(key).is_err() {
+            continue;
+        }
+        if let Some(key) = pgp::find_key(client, keyservers, key).await? {
+            keyring.import(&key).await?;
+        }
+    }
+
+    for (source, tag) in sources {
+        match git::verify_tag(source.clone_url(), tag, keyring.path()).await {
+            Ok(status) => {
+                if let Some((issue, key)) = pgp::check_signature_status(&status, validpgpkeys) {
+                    findings.push(Finding::GitTagSignatureInvalid {
+                        source: source.clone(),
+                        issue,
+                        key,
+                    });
+                }
+            }
+            Err(err) => findings.push(Finding::SourceUnavailable {
+                url: source.url().to_string(),
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+
+    Ok(())
+}
+
+/// Download a url source and compare it with all declared checksums
+async fn verify_sums(client: &reqwest::Client, source: &UrlSource, findings: &mut Vec<Finding>) {
+    if source.checksums.is_empty()
+        || (!source.url.starts_with("https://") && !source.url.starts_with("http://"))
+    {
+        return;
+    }
+
+    match verify::download_checksums(client, &source.url, &source.checksums).await {
+        Ok(checksums) => {
+            for (expected, actual) in checksums {
+                if !expected.value().eq_ignore_ascii_case(actual.value()) {
+                    findings.push(Finding::ChecksumMismatch {
+                        url: source.url.clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Err(err) => findings.push(Finding::SourceUnavailable {
+            url: source.url.clone(),
+            reason: format!("{:#}", err),
+        }),
+    }
+}
+
+/// Compare the checksums of a pypi source with the digests published on pypi
+async fn check_pypi(
+    client: &reqwest::Client,
+    source: &UrlSource,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let Some(sdist) = pypi::detect_sdist_from_url(&source.url)? else {
+        return Ok(());
+    };
+    let Some(version) = &sdist.version else {
+        findings.push(Finding::PypiSourceNotVersionPinned {
+            url: source.url.clone(),
+        });
+        return Ok(());
+    };
+
+    let release = match pypi::fetch_release(client, &sdist.project, version).await {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            warn!(
+                "Release not found on pypi: {:?} {:?}",
+                sdist.project, version
+            );
+            return Ok(());
+        }
+        Err(err) => {
+            findings.push(Finding::SourceUnavailable {
+                url: source.url.clone(),
+                reason: format!("{:#}", err),
+            });
+            return Ok(());
+        }
+    };
+    let Some(file) = release
+        .urls
+        .into_iter()
+        .find(|file| file.filename == sdist.filename)
+    else {
+        warn!("File not found in pypi release: {:?}", sdist.filename);
+        return Ok(());
+    };
+
+    for checksum in &source.checksums {
+        if let Some(digest) = file.digests.get(checksum) {
+            if !digest.eq_ignore_ascii_case(checksum.value()) {
+                let pypi = Checksum::new(checksum.alg(), digest.to_string())
+                    .context("Failed to construct checksum")?;
+                findings.push(Finding::PypiChecksumMismatch {
+                    url: source.url.clone(),
+                    pypi,
+                    pkgbuild: checksum.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if the upstream of an archive url publishes annotated tags that could be pinned instead
+async fn discover_signed_tag(
+    client: &reqwest::Client,
+    github_token: Option<&github::Token>,
+    url: &str,
+) -> Result<()> {
+    // the debug output of the annotated tag, the forges all have their own types
+    let tag = if let Some(upstream) = github::detect_signed_tag_from_url(url)? {
+        github::fetch_tag(
+            client,
+            github_token,
+            &upstream.owner,
+            &upstream.name,
+            &upstream.tag,
+        )
+        .await?
+        .filter(|tag| tag.object.r#type == "tag")
+        .map(|tag| format!("{:?}", tag))
+    } else if let Some(upstream) = gitlab::detect_signed_tag_from_url(url)? {
+        gitlab::fetch_tag(client, &upstream.project, &upstream.tag)
+            .await?
+            .filter(|tag| tag.is_annotated())
+            .map(|tag| format!("{:?}", tag))
+    } else if let Some(upstream) = codeberg::detect_signed_tag_from_url(url)? {
+        codeberg::fetch_tag(client, &upstream.owner, &upstream.name, &upstream.tag)
+            .await?
+            .filter(|tag| tag.is_annotated())
+            .map(|tag| format!("{:?}", tag))
+    } else if let Some(upstream) = sourcehut::detect_signed_tag_from_url(url)? {
+        git::ls_remote_tag(&upstream.clone_url(), &upstream.tag)
+            .await?
+            .filter(|tag| tag.is_annotated())
+            .map(|tag| format!("{:?}", tag))
+    } else if let Some(upstream) = bitbucket::detect_signed_tag_from_url(url)? {
+        git::ls_remote_tag(&upstream.clone_url(), &upstream.tag)
+            .await?
+            .filter(|tag| tag.is_annotated())
+            .map(|tag| format!("{:?}", tag))
+    } else {
+        None
+    };
+
+    if let Some(tag) = tag {
+        info!("✨ There's likely a signed tag here we could use: {}", tag);
+    }
+    Ok(())
+}
+
+/// Compare the checksums of an npm tarball with the integrity published by the npm registry
+async fn check_npm(
+    client: &reqwest::Client,
+    source: &UrlSource,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let Some(tarball) = npm::detect_tarball_from_url(&source.url)? else {
+        return Ok(());
+    };
+    let version = match npm::fetch_version(client, &tarball.name, &tarball.version).await {
+        Ok(Some(version)) => version,
+        Ok(None) => {
+            warn!(
+                "Version not found on npm: {:?} {:?}",
+                tarball.name, tarball.version
+            );
+            return Ok(());
+        }
+        Err(err) => {
+            findings.push(Finding::SourceUnavailable {
+                url: source.url.clone(),
+                reason: format!("{:#}", err),
+            });
+            return Ok(());
+        }
+    };
+
+    let mut corroborated = false;
+    for checksum in &source.checksums {
+        if let Some(digest) = version.dist.get(checksum) {
+            if digest.eq_ignore_ascii_case(checksum.value()) {
+                corroborated = true;
+            } else {
+                let npm = Checksum::new(checksum.alg(), digest)
+                    .context("Failed to construct checksum")?;
+                findings.push(Finding::NpmChecksumMismatch {
+                    url: source.url.clone(),
+                    npm,
+                    pkgbuild: checksum.clone(),
+                });
+                return Ok(());
+            }
+        }
+    }
+
+    if !corroborated {
+        findings.push(Finding::NpmChecksumUncorroborated {
+            url: source.url.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Download a go module zip from the proxy and compare it with the PKGBUILD checksums and sum.golang.org
+async fn check_go_module(
+    client: &reqwest::Client,
+    source: &UrlSource,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let Some(module) = golang::detect_module_from_url(&source.url)? else {
+        return Ok(());
+    };
+    let sumdb = match golang::fetch_sumdb_hash(client, &module).await {
+        Ok(Some(sumdb)) => sumdb,
+        Ok(None) => {
+            warn!(
+                "Module not found in go checksum database: {}@{}",
+                module.module, module.version
+            );
+            return Ok(());
+        }
+        Err(err) => {
+            findings.push(Finding::SourceUnavailable {
+                url: source.url.clone(),
+                reason: format!("{:#}", err),
+            });
+            return Ok(());
+        }
+    };
+
+    info!("Url={}", source.url);
+    let zip = match download(client, &source.url).await {
+        Ok(zip) => zip,
+        Err(err) => {
+            findings.push(Finding::SourceUnavailable {
+                url: source.url.clone(),
+                reason: format!("{:#}", err),
+            });
+            return Ok(());
+        }
+    };
+
+    // the PKGBUILD disagreeing with the zip is a regular checksum mismatch
+    for expected in &source.checksums {
+        let actual = match expected {
+            Checksum::Sha256(_) => Checksum::Sha256(golang::hex(&Sha256::digest(&zip))),
+            Checksum::Sha512(_) => Checksum::Sha512(golang::hex(&Sha512::digest(&zip))),
+            _ => continue,
+        };
+        if !expected.value().eq_ignore_ascii_case(actual.value()) {
+            findings.push(Finding::ChecksumMismatch {
+                url: source.url.clone(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+
+    if golang::hash_zip(&zip)? != sumdb {
+        findings.push(Finding::GoSumdbMismatch {
+            url: source.url.clone(),
+            module: module.module,
+            version: module.version,
+            sumdb,
+        });
+    }
+
+    Ok(())
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+/// The http client for all requests of a scan, it's cheap to clone and clones share the connection pool
+pub fn http_client() -> Result<reqwest::Client> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!(
+            env!("CARGO_PKG_NAME"),
+            "/",
+            env!("CARGO_PKG_VERSION"),
+        ))
+        .build()?;
+    Ok(client)
+}
+
+/// The findings of a checked package
+#[derive(Default, Clone)]
+pub struct PackageFindings {
+    pub findings: Vec<Finding>,
+    /// Findings that are hidden by a `# fsck: ignore=` comment in the PKGBUILD
+    pub suppressed: Vec<Suppressed>,
+}
+
+impl From<Vec<Finding>> for PackageFindings {
+    fn from(findings: Vec<Finding>) -> Self {
+        PackageFindings {
+            findings,
+            suppressed: Vec::new(),
+        }
+    }
+}
+
+async fn check_pkg(
+    target: &Target,
+    opts: &CheckOptions,
+    registry: &Registry,
+    cache: Option<&Cache>,
+    client: &reqwest::Client,
+) -> Result<PackageFindings> {
+    let (_temp_dir, path) = checkout(client, target, &opts.checkout).await?;
+
+    let key = if let Some(cache) = cache {
+        let settings = format!("{}\n{:?}\n{:?}", target.display(), opts, registry);
+        let key = cache::key(&path, &settings)?;
+        if let Some(pkg) = cache.get(&key)? {
+            debug!("Using cached results for {:?}", target.display());
+            return Ok(pkg);
+        }
+        Some(key)
+    } else {
+        None
+    };
+
+    let pkgbuild = Pkgbuild::load(&path, opts.evaluator, opts.sandbox).await?;
+
+    let mut findings = opts
+        .skipped_checks
+        .iter()
+        .map(|check| Finding::CheckSkipped {
+            check: check.clone(),
+        })
+        .collect::<Vec<_>>();
+    let sources = parse_sources(&pkgbuild, &mut findings).await?;
+
+    let pkg = Package::new(target, opts, client, path, pkgbuild, sources).await?;
+    registry.check(&pkg, &mut findings).await?;
+    findings.retain(|finding| !opts.source_policy.accepts(finding));
+
+    let suppressions = pkg.text.as_deref().map(suppress::parse).unwrap_or_default();
+    let (findings, suppressed) = suppress::apply(&suppressions, findings);
+    let pkg = PackageFindings {
+        findings,
+        suppressed,
+    };
+
+    if let (Some(cache), Some(key)) = (cache, key) {
+        cache.put(&key, &pkg)?;
+    }
+    Ok(pkg)
+}
+
+/// The url sources that are downloaded as artifacts, signature files are left out
+fn url_artifacts(sources: &[AuthedSource]) -> impl Iterator<Item = &UrlSource> {
+    sources.iter().filter_map(|source| match source {
+        AuthedSource::Url(source) if !source.is_signature_file() => Some(source),
+        _ => None,
+    })
+}
+
+/// The lints that are registered by default, in the order they run
+pub(crate) fn builtin_lints() -> Vec<Arc<dyn Lint>> {
+    vec![
+        Arc::new(ProbeHttps),
+        Arc::new(SignaturePairs),
+        Arc::new(SrcinfoDrift),
+        Arc::new(PkgverLint),
+        Arc::new(MissingLocalSources),
+        Arc::new(UrlArtifactPins),
+        Arc::new(UrlArtifactNames),
+        Arc::new(Availability),
+        Arc::new(VerifySums),
+        Arc::new(Registries),
+        Arc::new(DiscoverSigs),
+        Arc::new(GitPins),
+        Arc::new(VcsPins),
+        Arc::new(Validpgpkeys),
+        Arc::new(SignedTags),
+        Arc::new(Keys),
+        Arc::new(PkgbuildPatterns),
+        Arc::new(PinVariables),
+        Arc::new(Dlagents),
+        Arc::new(InstallScriptlets),
+        Arc::new(NetworkFetchInBuild),
+    ]
+}
+
+/// Check if sources with an unauthenticated scheme are also served over https
+struct ProbeHttps;
+
+#[async_trait]
+impl Lint for ProbeHttps {
+    fn name(&self) -> &'static str {
+        "probe-https"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.probe_https
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for finding in findings.iter_mut() {
+            if let Finding::InsecureScheme {
+                source, suggestion, ..
+            } = finding
+            {
+                *suggestion = probe::probe_https(pkg.client, source.url()).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct SignaturePairs;
+
+#[async_trait]
+impl Lint for SignaturePairs {
+    fn name(&self) -> &'static str {
+        "signature-pairs"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        findings.extend(check_signature_pairs(&pkg.sources));
+        Ok(())
+    }
+}
+
+struct SrcinfoDrift;
+
+#[async_trait]
+impl Lint for SrcinfoDrift {
+    fn name(&self) -> &'static str {
+        "srcinfo-drift"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if pkg.pkgbuild.evaluator() == Evaluator::Srcinfo {
+            return Ok(());
+        }
+        check_srcinfo(&pkg.pkgbuild, findings).await
+    }
+}
+
+/// Compare the pkgver with the versions of the sources, VCS packages compute their pkgver
+struct PkgverLint;
+
+#[async_trait]
+impl Lint for PkgverLint {
+    fn name(&self) -> &'static str {
+        "pkgver"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if !pkg.pkgbuild.has_functions() {
+            debug!("Functions of the PKGBUILD are unknown, skipping pkgver checks");
+        } else if pkg.is_vcs_package {
+            debug!("Found pkgver() function, this is a VCS package");
+        } else if let Some(pkgver) = pkg.pkgbuild.list_variable("pkgver").await?.first() {
+            let pkgnames = pkg.pkgbuild.list_variable("pkgname").await?;
+            check_pkgver(&pkg.sources, pkgver, &pkgnames, findings)?;
+            findings.extend(check_pkgver_in_sources(&pkg.sources, pkgver));
+        }
+        Ok(())
+    }
+}
+
+struct MissingLocalSources;
+
+#[async_trait]
+impl Lint for MissingLocalSources {
+    fn name(&self) -> &'static str {
+        "missing-local-sources"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if !pkg.has_files {
+            return Ok(());
+        }
+        for source in &pkg.sources {
+            if let AuthedSource::File(file) = source {
+                if !pkg.path.join(file).exists() {
+                    findings.push(Finding::MissingLocalSource { file: file.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if url artifacts are pinned with a checksum that's considered secure
+struct UrlArtifactPins;
+
+#[async_trait]
+impl Lint for UrlArtifactPins {
+    fn name(&self) -> &'static str {
+        "url-artifact-pins"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        let noextract = pkg.pkgbuild.list_variable("noextract").await?;
+        for source in url_artifacts(&pkg.sources) {
+            if source
+                .checksums
+                .iter()
+                .any(|x| pkg.opts.checksum_policy.is_secure(x))
+            {
+                continue;
+            }
+
+            let destination = source.destination();
+            let noextract = noextract.contains(&destination);
+            if noextract || lint::is_verbatim_artifact(&destination) {
+                findings.push(Finding::VerbatimArtifactInsecurePin {
+                    source: source.clone(),
+                    noextract,
+                });
+            } else if source.checksums.is_empty() && !source.skipped.is_empty() {
+                findings.push(Finding::UrlArtifactChecksumSkipped(source.clone()));
+            } else {
+                findings.push(Finding::UrlArtifactInsecurePin(source.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if url artifacts are generated on the fly or don't name a specific version
+struct UrlArtifactNames;
+
+#[async_trait]
+impl Lint for UrlArtifactNames {
+    fn name(&self) -> &'static str {
+        "url-artifact-names"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in url_artifacts(&pkg.sources) {
+            if github::is_generated_archive(&source.url)? {
+                findings.push(Finding::GithubGeneratedArchive {
+                    url: source.url.clone(),
+                });
+            }
+
+            let keyword = version::moving_keyword(&source.url);
+            if keyword.is_some() || !version::has_version(&source.url) {
+                findings.push(Finding::UnversionedUrl {
+                    url: source.url.clone(),
+                    keyword: keyword.map(String::from),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Availability;
+
+#[async_trait]
+impl Lint for Availability {
+    fn name(&self) -> &'static str {
+        "check-availability"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.check_availability
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in url_artifacts(&pkg.sources) {
+            check_availability(pkg.client, &pkg.path, source, findings).await;
+        }
+        Ok(())
+    }
+}
+
+struct VerifySums;
+
+#[async_trait]
+impl Lint for VerifySums {
+    fn name(&self) -> &'static str {
+        "verify-sums"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.verify_sums
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in url_artifacts(&pkg.sources) {
+            verify_sums(pkg.client, source, findings).await;
+        }
+        Ok(())
+    }
+}
+
+struct Registries;
+
+#[async_trait]
+impl Lint for Registries {
+    fn name(&self) -> &'static str {
+        "check-registries"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.check_registries
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in url_artifacts(&pkg.sources) {
+            check_pypi(pkg.client, source, findings).await?;
+            check_go_module(pkg.client, source, findings).await?;
+            check_npm(pkg.client, source, findings).await?;
+        }
+        Ok(())
+    }
+}
+
+struct DiscoverSigs;
+
+#[async_trait]
+impl Lint for DiscoverSigs {
+    fn name(&self) -> &'static str {
+        "discover-sigs"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.discover_sigs
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in url_artifacts(&pkg.sources) {
+            if let Err(err) =
+                discover_signed_tag(pkg.client, pkg.opts.github_token.as_ref(), &source.url).await
+            {
+                warn!(
+                    "Failed to discover signed tag for {:?}: {:#}",
+                    source.url, err
+                );
+            }
+
+            if gnu::is_release_url(&source.url)
+                && !pkg.signatures.contains(&gnu::signature_url(&source.url))
+            {
+                match gnu::probe_signature(pkg.client, &source.url).await {
+                    Ok(Some(signature)) => findings.push(Finding::UnusedUpstreamSignature {
+                        url: source.url.clone(),
+                        signature,
+                    }),
+                    Ok(None) => (),
+                    Err(err) => warn!("Failed to probe signature for {:?}: {:#}", source.url, err),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Confirm pinned git commits exist upstream and tag hashes are tag objects
+struct GitPins;
+
+#[async_trait]
+impl Lint for GitPins {
+    fn name(&self) -> &'static str {
+        "verify-git-pins"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.verify_git_pins
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for source in &pkg.sources {
+            let AuthedSource::Git(source) = source else {
+                continue;
+            };
+
+            if let Some((commit, tag)) = source.pinned_commit() {
+                match git::verify_pin(source.clone_url(), commit, tag).await {
+                    Ok(Some(issue)) => findings.push(Finding::GitPinNotUpstream {
+                        source: source.clone(),
+                        issue,
+                    }),
+                    Ok(None) => (),
+                    Err(err) => findings.push(Finding::SourceUnavailable {
+                        url: source.url().to_string(),
+                        reason: format!("{:#}", err),
+                    }),
+                }
+            }
+
+            if let Some(hash) = source
+                .tag()
+                .filter(|tag| source.commit().is_none() && git::is_git_object_hash(tag))
+            {
+                verify_tag_hash(source, hash, findings).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if vcs sources are cryptographically pinned
+struct VcsPins;
+
+#[async_trait]
+impl Lint for VcsPins {
+    fn name(&self) -> &'static str {
+        "vcs-pins"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        let is_vcs_package = pkg.is_vcs_package;
+
+        // if an upstream project has submodules it's normal for them to be listed
+        // in source= without pinning them by commit. As long as the primary repo
+        // is securely pinned it's fine, but there's no reliable way to determine which
+        // one is the primary one. So we just assume if any is pinned it's a-okay,
+        // unless we're allowed to look up the declared submodules.
+        let has_any_secure_git_sources = pkg.sources.iter().any(|source| match source {
+            AuthedSource::Git(source) => source.is_commit_securely_pinned(),
+            _ => false,
+        });
+        let submodules = if pkg.opts.check_submodules && has_any_secure_git_sources {
+            list_submodules(&pkg.sources, findings).await
+        } else {
+            None
+        };
+
+        for source in &pkg.sources {
+            match source {
+                AuthedSource::File(_) | AuthedSource::Url(_) => (),
+                AuthedSource::Git(source) => {
+                    let is_pinned_by_parent = if let Some(submodules) = &submodules {
+                        submodules.contains(&git::normalize_url(source.clone_url()))
+                    } else {
+                        has_any_secure_git_sources
+                    };
+                    if !is_pinned_by_parent && !source.is_commit_securely_pinned() {
+                        findings.push(vcs_insecure_pin(
+                            is_vcs_package,
+                            "git",
+                            git_insecure_pin(source.clone()),
+                        ));
+                    }
+                }
+                AuthedSource::Svn(source) => {
+                    findings.push(vcs_insecure_pin(
+                        is_vcs_package,
+                        "svn",
+                        Finding::SvnInsecurePin(source.clone()),
+                    ));
+                }
+                AuthedSource::Hg(source) => {
+                    if !source.is_revision_securely_pinned() {
+                        findings.push(vcs_insecure_pin(
+                            is_vcs_package,
+                            "hg",
+                            Finding::HgRevisionInsecurePin(source.clone()),
+                        ));
+                    }
+                }
+                AuthedSource::Bzr(source) => {
+                    findings.push(vcs_insecure_pin(
+                        is_vcs_package,
+                        "bzr",
+                        Finding::BzrInsecurePin(source.clone()),
+                    ));
+                }
+                AuthedSource::Fossil(source) => {
+                    if !source.is_commit_securely_pinned() {
+                        findings.push(vcs_insecure_pin(
+                            is_vcs_package,
+                            "fossil",
+                            Finding::FossilCommitInsecurePin(source.clone()),
+                        ));
+                    }
+                }
+                AuthedSource::Darcs(source) => {
+                    findings.push(vcs_insecure_pin(
+                        is_vcs_package,
+                        "darcs",
+                        Finding::DarcsInsecurePin(source.clone()),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn signed_git_sources(sources: &[AuthedSource]) -> Vec<GitSource> {
+    sources
+        .iter()
+        .filter_map(|source| match source {
+            AuthedSource::Git(source) if source.is_signed() => Some(source.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Check if signatures can be verified with the configured validpgpkeys
+struct Validpgpkeys;
+
+#[async_trait]
+impl Lint for Validpgpkeys {
+    fn name(&self) -> &'static str {
+        "validpgpkeys"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if !pkg.validpgpkeys.is_empty() {
+            debug!("Found validpgpkeys={:?}", pkg.validpgpkeys);
+        } else {
+            if !pkg.signatures.is_empty() {
+                let mut signatures = pkg.signatures.iter().cloned().collect::<Vec<_>>();
+                signatures.sort();
+                findings.push(Finding::SignatureWithoutValidpgpkeys { signatures });
+            }
+            for source in signed_git_sources(&pkg.sources) {
+                findings.push(Finding::GitSignedWithoutValidpgpkeys(source));
+            }
+        }
+
+        for key in &pkg.validpgpkeys {
+            if let Err(issue) = pgp::validate_fingerprint(key) {
+                findings.push(Finding::InvalidValidpgpkeys {
+                    key: key.clone(),
+                    issue,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+struct SignedTags;
+
+#[async_trait]
+impl Lint for SignedTags {
+    fn name(&self) -> &'static str {
+        "verify-signed-tags"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.verify_signed_tags
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if pkg.validpgpkeys.is_empty() {
+            return Ok(());
+        }
+        verify_signed_tags(
+            pkg.client,
+            &pkg.opts.keyservers,
+            &signed_git_sources(&pkg.sources),
+            &pkg.validpgpkeys,
+            findings,
+        )
+        .await
+    }
+}
+
+/// Lookup the validpgpkeys on keyservers and in the Web Key Directory
+struct Keys;
+
+#[async_trait]
+impl Lint for Keys {
+    fn name(&self) -> &'static str {
+        "check-keys"
+    }
+
+    fn is_enabled(&self, opts: &CheckOptions) -> bool {
+        opts.check_keys
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        // the keys are used to verify signature files or `?signed` git sources
+        let has_signatures =
+            !pkg.signatures.is_empty() || !signed_git_sources(&pkg.sources).is_empty();
+
+        for key in &pkg.validpgpkeys {
+            if pgp::validate_fingerprint(key).is_err() {
+                continue;
+            }
+
+            let Some(info) = pgp::lookup_key(pkg.client, &pkg.opts.keyservers, key).await? else {
+                findings.push(Finding::UnusableValidpgpkeys {
+                    key: key.clone(),
+                    status: KeyStatus::NotFound,
+                });
+                continue;
+            };
+
+            if info.status != KeyStatus::Valid {
+                findings.push(Finding::UnusableValidpgpkeys {
+                    key: key.clone(),
+                    status: info.status,
+                });
+            } else if has_signatures && !info.emails.is_empty() {
+                let mut in_wkd = false;
+                for email in &info.emails {
+                    if pgp::wkd_has_key(pkg.client, email, key).await? {
+                        in_wkd = true;
+                        break;
+                    }
+                }
+                if !in_wkd {
+                    findings.push(Finding::ValidpgpkeysNotInWkd {
+                        key: key.clone(),
+                        emails: info.emails,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Search the PKGBUILD for dangerous patterns like `sudo` or `curl | sh`
+struct PkgbuildPatterns;
+
+#[async_trait]
+impl Lint for PkgbuildPatterns {
+    fn name(&self) -> &'static str {
+        "pkgbuild-patterns"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        let Some(text) = &pkg.text else {
+            return Ok(());
+        };
+        for m in lint::scan_pkgbuild(text) {
+            let (line, code) = (m.line, m.code);
+            findings.push(match m.lint {
+                lint::Lint::EvalDownload => Finding::EvalDownloadedContent { line, code },
+                lint::Lint::Sudo => Finding::SudoInPkgbuild { line, code },
+                lint::Lint::WriteOutsideBuildDir => Finding::WriteOutsideBuildDir { line, code },
+                lint::Lint::PipeToShell => Finding::PipeDownloadToShell { line, code },
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Check variables that are used to pin sources contain a full commit hash
+struct PinVariables;
+
+#[async_trait]
+impl Lint for PinVariables {
+    fn name(&self) -> &'static str {
+        "pin-variables"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        let Some(text) = &pkg.text else {
+            return Ok(());
+        };
+        // the values of custom variables are only known if the PKGBUILD is evaluated
+        if !pkg.pkgbuild.has_functions() {
+            return Ok(());
+        }
+        for (variable, line, is_fragment) in lint::pin_variables(text) {
+            let value = pkg
+                .pkgbuild
+                .list_variable(&variable)
+                .await?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            // a numeric revision in an url is fine, it's pinned by checksum
+            let is_number = !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+            if !lint::is_full_hash(&value) && (is_fragment || !is_number) {
+                findings.push(Finding::UnpinnedSourceVariable {
+                    variable,
+                    value,
+                    line,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Dlagents;
+
+#[async_trait]
+impl Lint for Dlagents {
+    fn name(&self) -> &'static str {
+        "dlagents"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        let agents = pkg.pkgbuild.list_variable("DLAGENTS").await?;
+        if !agents.is_empty() {
+            let disables_tls = agents.iter().any(|agent| lint::dlagent_disables_tls(agent));
+            findings.push(Finding::DlagentsOverride {
+                agents,
+                disables_tls,
+            });
+        }
+        Ok(())
+    }
+}
+
+struct InstallScriptlets;
+
+#[async_trait]
+impl Lint for InstallScriptlets {
+    fn name(&self) -> &'static str {
+        "install-scriptlets"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        if !pkg.has_files {
+            return Ok(());
+        }
+        let mut scriptlets = pkg.pkgbuild.list_variable("install").await?;
+        scriptlets.sort();
+        scriptlets.dedup();
+        for file in scriptlets {
+            let text = match fs::read_to_string(pkg.path.join(&file)) {
+                Ok(text) => text,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    findings.push(Finding::MissingLocalSource { file });
+                    continue;
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| anyhow!("Failed to read install scriptlet: {:?}", file))
+                }
+            };
+            for (issue, line, code) in lint::scan_scriptlet(&text) {
+                findings.push(Finding::DangerousInstallScriptlet {
+                    file: file.clone(),
+                    issue,
+                    line,
+                    code,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+struct NetworkFetchInBuild;
+
+#[async_trait]
+impl Lint for NetworkFetchInBuild {
+    fn name(&self) -> &'static str {
+        "network-fetch-in-build"
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for function in pkg.pkgbuild.list_functions().await? {
+            if !lint::is_build_function(&function) {
+                continue;
+            }
+            let body = pkg.pkgbuild.function_body(&function).await?;
+            for command in lint::network_commands(&body) {
+                findings.push(Finding::NetworkFetchInBuild {
+                    function: function.clone(),
+                    command,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> AuthedSource {
+        AuthedSource::url(Source::Url(url.to_string()))
+    }
+
+    #[test]
+    fn test_attach_checksums() {
+        let mut sources = vec![
+            url("https://example.com/foo.tar.gz"),
+            AuthedSource::File("foo.patch".to_string()),
+            url("https://example.com/foo.tar.gz.sig"),
+        ];
+        let sums = vec!["aaaa".to_string(), "bbbb".to_string(), "SKIP".to_string()];
+        let finding = attach_checksums(&mut sources, "sha256sums", "sha256sums", sums);
+        assert!(finding.is_none());
+
+        let AuthedSource::Url(source) = &sources[0] else {
+            panic!("expected url source");
+        };
+        assert_eq!(source.checksums(), &[Checksum::Sha256("aaaa".to_string())]);
+        let AuthedSource::Url(source) = &sources[2] else {
+            panic!("expected url source");
+        };
+        assert!(source.checksums().is_empty());
+        assert_eq!(source.skipped, &["sha256sums"]);
+    }
+
+    #[test]
+    fn test_check_signature_pairs() {
+        let sources = vec![
+            url("https://example.com/foo-1.0.tar.gz"),
+            url("http://example.com/foo-1.0.tar.gz.sig"),
+            url("https://cdn.kernel.org/linux-6.0.tar.xz"),
+            url("https://cdn.kernel.org/linux-6.0.tar.sign"),
+            url("https://example.com/bar-1.0.tar.gz.asc"),
+        ];
+        let findings = check_signature_pairs(&sources);
+        assert_eq!(findings.len(), 2);
+        assert!(matches!(
+            &findings[0],
+            Finding::SignatureSchemeMismatch { signature, .. } if signature == "http://example.com/foo-1.0.tar.gz.sig"
+        ));
+        assert!(matches!(
+            &findings[1],
+            Finding::OrphanedSignature { signature } if signature == "https://example.com/bar-1.0.tar.gz.asc"
+        ));
+    }
+
+    #[test]
+    fn test_find_duplicate_sources() {
+        let common = vec![Source::Url(
+            "https://example.com/foo-1.0.tar.gz".to_string(),
+        )];
+        let sources = vec![
+            Source::Url("https://example.com/bar-1.0.tar.gz".to_string()),
+            Source::Url("https://mirror.example.com/foo-1.0.tar.gz".to_string()),
+            Source::UrlWithFilename((
+                "https://example.com/download?id=2".to_string(),
+                "bar-1.0.tar.gz".to_string(),
+            )),
+        ];
+        assert!(find_duplicate_sources("source", &[], &common).is_empty());
+        let findings = find_duplicate_sources("source_x86_64", &common, &sources);
+        let filenames = findings
+            .iter()
+            .map(|finding| match finding {
+                Finding::DuplicateSource { filename, .. } => filename.as_str(),
+                _ => panic!("unexpected finding"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(filenames, &["foo-1.0.tar.gz", "bar-1.0.tar.gz"]);
+    }
+
+    #[test]
+    fn test_attach_checksums_wrong_number() {
+        let mut sources = vec![url("https://example.com/foo.tar.gz")];
+        let sums = vec!["aaaa".to_string(), "bbbb".to_string()];
+        let finding = attach_checksums(&mut sources, "b2sums_x86_64", "b2sums", sums);
+        assert!(matches!(
+            finding,
+            Some(Finding::WrongNumberOfChecksums {
+                sources: 1,
+                sums: 2,
+                ..
+            })
+        ));
+
+        let AuthedSource::Url(source) = &sources[0] else {
+            panic!("expected url source");
+        };
+        assert!(source.checksums().is_empty());
+    }
+
+    #[test]
+    fn test_serialize_finding_record() -> Result<()> {
+        let finding = Finding::InsecureScheme {
+            scheme: "http".to_string(),
+            source: Source::Url("http://example.com/foo-1.0.tar.gz".to_string()),
+            suggestion: Some("https://example.com/foo-1.0.tar.gz".to_string()),
+        };
+        let value = serde_json::to_value(finding.record())?;
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "kind": "insecure-scheme",
+                "severity": "high",
+                "description": "A source uses a complex protocol over an unauthenticated connection",
+                "source_url": "http://example.com/foo-1.0.tar.gz",
+                "fix": "Download the source from \"https://example.com/foo-1.0.tar.gz\"",
+                "scheme": "http",
+                "source": {
+                    "url": "http://example.com/foo-1.0.tar.gz",
+                    "filename": null,
+                },
+                "suggestion": "https://example.com/foo-1.0.tar.gz",
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_file_name() {
+        for (path, expected) in [
+            ("svntogit-packages/linux/trunk", "linux"),
+            (
+                "svntogit-packages/linux/repos/core-x86_64",
+                "linux-core-x86_64",
+            ),
+            ("packages/zstd", "zstd"),
+        ] {
+            let target = Target::BuildPath(PathBuf::from(path));
+            assert_eq!(target.file_name(), expected, "{}", path);
+        }
+    }
 }
@@ -1,15 +1,56 @@
 use crate::errors::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
 use std::str::FromStr;
+use strum::{Display, IntoStaticStr};
+use tokio::process::Command;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct GitSource {
     url: String,
     commit: Option<String>,
     tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
     signed: bool,
 }
 
 impl GitSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn commit(&self) -> Option<&str> {
+        self.commit.as_deref()
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// The url that can be passed to git, without the `git+` prefix used by makepkg
+    pub fn clone_url(&self) -> &str {
+        self.url.strip_prefix("git+").unwrap_or(&self.url)
+    }
+
+    /// The commit this source is pinned to, and the tag that is named next to it
+    pub fn pinned_commit(&self) -> Option<(&str, Option<&str>)> {
+        match (self.commit(), self.tag()) {
+            (Some(commit), tag) if is_git_object_hash(commit) => Some((commit, tag)),
+            (None, Some(tag)) if is_git_object_hash(tag) => Some((tag, None)),
+            _ => None,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signed
+    }
+
     pub fn is_commit_securely_pinned(&self) -> bool {
         if let Some(commit) = &self.commit {
             is_git_object_hash(commit)
@@ -21,7 +62,476 @@ impl GitSource {
     }
 }
 
-fn is_git_object_hash(name: &str) -> bool {
+/// A tag as advertised by a remote git server
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct RemoteTag {
+    pub name: String,
+    pub object: String,
+    pub commit: String,
+}
+
+impl RemoteTag {
+    /// Lightweight tags point to the commit directly, only annotated tags can be signed
+    pub fn is_annotated(&self) -> bool {
+        self.object != self.commit
+    }
+}
+
+async fn ls_remote(url: &str, patterns: &[&str]) -> Result<Vec<RemoteTag>> {
+    info!("Url={}", url);
+    let out = Command::new("git")
+        .args(["ls-remote", "--tags", "--", url])
+        .args(patterns)
+        .kill_on_drop(true)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdout(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!("git ls-remote exited with error: {:?}", out.status);
+    }
+    let out = String::from_utf8(out.stdout).context("git output contains invalid utf8")?;
+    Ok(parse_ls_remote(&out))
+}
+
+fn parse_ls_remote(out: &str) -> Vec<RemoteTag> {
+    let mut tags = Vec::<RemoteTag>::new();
+    for line in out.lines() {
+        let Some((hash, r)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(name) = r.strip_prefix("refs/tags/") else {
+            continue;
+        };
+
+        if let Some(name) = name.strip_suffix("^{}") {
+            if let Some(tag) = tags.iter_mut().find(|tag| tag.name == name) {
+                tag.commit = hash.to_string();
+            }
+        } else {
+            tags.push(RemoteTag {
+                name: name.to_string(),
+                object: hash.to_string(),
+                commit: hash.to_string(),
+            });
+        }
+    }
+    tags
+}
+
+/// Resolve a tag with `git ls-remote` without cloning the repository
+pub async fn ls_remote_tag(url: &str, tag: &str) -> Result<Option<RemoteTag>> {
+    let name = format!("refs/tags/{}", tag);
+    let peeled = format!("{}^{{}}", name);
+    let tags = ls_remote(url, &[&name, &peeled]).await?;
+    Ok(tags.into_iter().find(|remote| remote.name == tag))
+}
+
+/// List all tags of a remote with `git ls-remote`
+pub async fn ls_remote_tags(url: &str) -> Result<Vec<RemoteTag>> {
+    ls_remote(url, &[]).await
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum PinIssue {
+    /// The commit can't be fetched from upstream
+    Dangling,
+    /// The named tag doesn't exist upstream
+    MissingTag,
+    /// The commit exists but isn't reachable from the named tag
+    Divergent,
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<bool> {
+    debug!("Running git {:?}", args);
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .kill_on_drop(true)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .context("Failed to run git")?;
+    Ok(status.success())
+}
+
+/// Fetch the pinned commit (and tag) into a temporary repository to confirm it's published upstream
+///
+/// Returns an error if the pin can't be verified, eg. because the remote couldn't be reached.
+pub async fn verify_pin(url: &str, commit: &str, tag: Option<&str>) -> Result<Option<PinIssue>> {
+    let dir = tempfile::tempdir()?;
+    let dir = dir.path();
+    if !git(dir, &["init", "--bare", "-q"]).await? {
+        bail!("Failed to initialize temporary git repository");
+    }
+
+    info!("Url={}", url);
+    if !git(dir, &["ls-remote", "--heads", "--", url]).await? {
+        bail!("Failed to reach git remote: {:?}", url);
+    }
+
+    let object = format!("{}^{{commit}}", commit);
+    if let Some(tag) = tag {
+        if ls_remote_tag(url, tag).await?.is_none() {
+            return Ok(Some(PinIssue::MissingTag));
+        }
+
+        // Fetch the history of the tag without any trees or blobs
+        let refspec = format!("+refs/tags/{0}:refs/tags/{0}", tag);
+        if !git(
+            dir,
+            &[
+                "fetch",
+                "-q",
+                "--no-tags",
+                "--filter=tree:0",
+                "--",
+                url,
+                &refspec,
+            ],
+        )
+        .await?
+        {
+            bail!("Failed to fetch tag {:?} from git remote: {:?}", tag, url);
+        }
+
+        let tag = format!("refs/tags/{}", tag);
+        if git(dir, &["merge-base", "--is-ancestor", commit, &tag]).await? {
+            return Ok(None);
+        }
+        if git(dir, &["cat-file", "-e", &object]).await? {
+            return Ok(Some(PinIssue::Divergent));
+        }
+    }
+
+    let fetched = git(
+        dir,
+        &[
+            "fetch",
+            "-q",
+            "--no-tags",
+            "--depth=1",
+            "--filter=tree:0",
+            "--",
+            url,
+            commit,
+        ],
+    )
+    .await?;
+
+    if !fetched || !git(dir, &["cat-file", "-e", &object]).await? {
+        // Some servers refuse to fetch a commit by its hash, fall back to the history of all advertised refs
+        if !git(
+            dir,
+            &[
+                "fetch",
+                "-q",
+                "--filter=tree:0",
+                "--",
+                url,
+                "+refs/heads/*:refs/heads/*",
+                "+refs/tags/*:refs/tags/*",
+            ],
+        )
+        .await?
+        {
+            bail!(
+                "Failed to fetch commit {:?} from git remote: {:?}",
+                commit,
+                url
+            );
+        }
+        if !git(dir, &["cat-file", "-e", &object]).await? {
+            return Ok(Some(PinIssue::Dangling));
+        }
+    }
+
+    if tag.is_some() {
+        Ok(Some(PinIssue::Divergent))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Fetch a tag into a temporary repository and run `git verify-tag` with the given gpg home directory
+///
+/// Returns the raw `[GNUPG:]` status lines, these are empty if the tag isn't signed.
+pub async fn verify_tag(url: &str, tag: &str, gnupghome: &Path) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    let dir = dir.path();
+    if !git(dir, &["init", "--bare", "-q"]).await? {
+        bail!("Failed to initialize temporary git repository");
+    }
+
+    info!("Url={}", url);
+    let refspec = format!("+refs/tags/{0}:refs/tags/{0}", tag);
+    if !git(
+        dir,
+        &[
+            "fetch",
+            "-q",
+            "--no-tags",
+            "--depth=1",
+            "--filter=tree:0",
+            "--",
+            url,
+            &refspec,
+        ],
+    )
+    .await?
+    {
+        bail!("Failed to fetch tag {:?} from {:?}", tag, url);
+    }
+
+    let tag = format!("refs/tags/{}", tag);
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["verify-tag", "--raw", &tag])
+        .env("GNUPGHOME", gnupghome)
+        .kill_on_drop(true)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    let status = String::from_utf8_lossy(&out.stderr);
+    Ok(status
+        .lines()
+        .filter(|line| line.starts_with("[GNUPG:] "))
+        .map(|line| format!("{}\n", line))
+        .collect())
+}
+
+/// Read a file from a commit without cloning the full repository, blobs are only fetched on demand
+pub async fn fetch_file(url: &str, commit: &str, path: &str) -> Result<Option<String>> {
+    let dir = tempfile::tempdir()?;
+    let dir = dir.path();
+    if !git(dir, &["init", "--bare", "-q"]).await? {
+        bail!("Failed to initialize temporary git repository");
+    }
+    if !git(dir, &["remote", "add", "origin", url]).await? {
+        bail!("Failed to configure git remote");
+    }
+
+    info!("Url={}", url);
+    if !git(
+        dir,
+        &[
+            "fetch",
+            "-q",
+            "--no-tags",
+            "--depth=1",
+            "--filter=blob:none",
+            "origin",
+            commit,
+        ],
+    )
+    .await?
+    {
+        bail!("Failed to fetch commit {:?} from {:?}", commit, url);
+    }
+
+    let object = format!("{}:{}", commit, path);
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["show", &object])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .kill_on_drop(true)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        return Ok(None);
+    }
+    let content = String::from_utf8(out.stdout).context("File contains invalid utf8")?;
+    Ok(Some(content))
+}
+
+/// Export a directory of a local git repository as it was at `rev`, the working tree is not touched
+pub async fn export_rev(path: &Path, rev: &str, dest: &Path) -> Result<()> {
+    if rev.starts_with('-') {
+        bail!("Invalid git revision: {:?}", rev);
+    }
+
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--show-toplevel", "--show-prefix"])
+        .kill_on_drop(true)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!("Directory is not part of a git repository: {:?}", path);
+    }
+    let out = String::from_utf8(out.stdout).context("Path contains invalid utf8")?;
+    let mut lines = out.lines();
+    let toplevel = lines
+        .next()
+        .context("Failed to find top of git repository")?;
+    let prefix = lines.next().unwrap_or_default();
+
+    // git archive refuses to run in an untracked directory, run it from the top
+    let treeish = format!("{}:{}", rev, prefix);
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(toplevel)
+        .args(["archive", "--format=tar", &treeish])
+        .kill_on_drop(true)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!(
+            "Failed to export {:?} from git repository: {:?}",
+            treeish,
+            path
+        );
+    }
+
+    tar::Archive::new(&out.stdout[..])
+        .unpack(dest)
+        .context("Failed to extract git archive")?;
+    Ok(())
+}
+
+/// Resolve a revision of a local git repository to its commit hash
+pub async fn resolve_commit(path: &Path, rev: &str) -> Result<String> {
+    if rev.starts_with('-') {
+        bail!("Invalid git revision: {:?}", rev);
+    }
+
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", rev))
+        .kill_on_drop(true)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!("Failed to resolve {:?} in git repository: {:?}", rev, path);
+    }
+    let commit = String::from_utf8(out.stdout).context("Commit contains invalid utf8")?;
+    Ok(commit.trim().to_string())
+}
+
+/// List the files below `path` that changed since a commit, relative to `path`. Without `rev`
+/// the commit is compared with the working tree, so uncommitted changes are included
+pub async fn changed_files(path: &Path, since: &str, rev: Option<&str>) -> Result<Vec<String>> {
+    for rev in [Some(since), rev].into_iter().flatten() {
+        if rev.starts_with('-') {
+            bail!("Invalid git revision: {:?}", rev);
+        }
+    }
+
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args([
+            "diff",
+            "-z",
+            "--name-only",
+            "--relative",
+            "--no-renames",
+            since,
+        ])
+        .args(rev)
+        .args(["--", "."])
+        .kill_on_drop(true)
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!(
+            "Failed to list files changed since {:?} in git repository: {:?}",
+            since,
+            path
+        );
+    }
+    let out = String::from_utf8(out.stdout).context("Path contains invalid utf8")?;
+    Ok(out
+        .split('\0')
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Detect the scp-like syntax of ssh urls, eg. `git@github.com:foo/bar.git`
+pub fn is_scp_url(url: &str) -> bool {
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let Some((authority, path)) = url.split_once(':') else {
+        return false;
+    };
+    let Some((user, host)) = authority.split_once('@') else {
+        return false;
+    };
+    !user.is_empty()
+        && !host.is_empty()
+        && !path.is_empty()
+        && !path.starts_with("//")
+        && authority
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '-' | '_'))
+}
+
+/// Normalize a git url so the same repository compares equal, regardless of scheme or `.git` suffix
+pub fn normalize_url(url: &str) -> String {
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let url = url.split_once("://").map(|(_, url)| url).unwrap_or(url);
+    // scp-like syntax, eg. git@github.com:foo/bar.git
+    let url = url.rsplit_once('@').map(|(_, url)| url).unwrap_or(url);
+    let url = url.replacen(':', "/", 1);
+    let url = url.trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    url.to_lowercase()
+}
+
+/// List the urls of all submodules in a `.gitmodules` file, relative urls are resolved against the repository url
+pub fn submodule_urls(gitmodules: &str, base: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    for line in gitmodules.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "url" {
+            continue;
+        }
+
+        let mut url = value.trim();
+        if url.starts_with("./") || url.starts_with("../") {
+            let mut resolved = base.trim_end_matches('/').to_string();
+            while let Some(remaining) = url.strip_prefix("../") {
+                if let Some((parent, _)) = resolved.rsplit_once('/') {
+                    resolved.truncate(parent.len());
+                }
+                url = remaining;
+            }
+            let url = url.strip_prefix("./").unwrap_or(url);
+            urls.push(format!("{}/{}", resolved, url));
+        } else {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+pub fn is_git_object_hash(name: &str) -> bool {
     name.len() == 40 && name.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
 }
 
@@ -32,6 +542,7 @@ impl FromStr for GitSource {
         let mut signed = false;
         let mut commit = None;
         let mut tag = None;
+        let mut branch = None;
 
         if let Some(remaining) = s.strip_suffix("?signed") {
             signed = true;
@@ -48,6 +559,11 @@ impl FromStr for GitSource {
             s = remaining;
         }
 
+        if let Some((remaining, value)) = s.rsplit_once("#branch=") {
+            branch = Some(value.to_string());
+            s = remaining;
+        }
+
         if let Some(remaining) = s.strip_suffix("?signed") {
             signed = true;
             s = remaining;
@@ -57,7 +573,87 @@ impl FromStr for GitSource {
             url: s.to_string(),
             commit,
             tag,
+            branch,
             signed,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_branch() {
+        let source = "git+https://github.com/foo/bar.git#branch=main?signed"
+            .parse::<GitSource>()
+            .unwrap();
+        assert_eq!(source.url(), "git+https://github.com/foo/bar.git");
+        assert_eq!(source.branch(), Some("main"));
+        assert!(source.is_signed());
+        assert!(!source.is_commit_securely_pinned());
+    }
+
+    #[test]
+    fn test_parse_ls_remote() {
+        let out = "2d5e4ec6eee1d2a7e4c8a5b1a9b4b37c1b0c7b2a\trefs/tags/v1.0.0
+8f14e45fceea167a5a36dedd4bea2543b1b3c2d1\trefs/tags/v1.0.0^{}
+c9f0f895fb98ab9159f51fd0297e236d0a4b5e2f\trefs/tags/v1.1.0
+";
+        assert_eq!(
+            parse_ls_remote(out),
+            &[
+                RemoteTag {
+                    name: "v1.0.0".to_string(),
+                    object: "2d5e4ec6eee1d2a7e4c8a5b1a9b4b37c1b0c7b2a".to_string(),
+                    commit: "8f14e45fceea167a5a36dedd4bea2543b1b3c2d1".to_string(),
+                },
+                RemoteTag {
+                    name: "v1.1.0".to_string(),
+                    object: "c9f0f895fb98ab9159f51fd0297e236d0a4b5e2f".to_string(),
+                    commit: "c9f0f895fb98ab9159f51fd0297e236d0a4b5e2f".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_submodule_urls() {
+        let gitmodules = r#"[submodule "vendor/foo"]
+	path = vendor/foo
+	url = https://github.com/foo/foo.git
+[submodule "bar"]
+	path = bar
+	url = ../bar.git
+"#;
+        let urls = submodule_urls(gitmodules, "https://github.com/example/project.git");
+        assert_eq!(
+            urls,
+            [
+                "https://github.com/foo/foo.git".to_string(),
+                "https://github.com/example/bar.git".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_scp_url() {
+        assert!(is_scp_url("git@github.com:foo/bar.git#tag=v1.0"));
+        assert!(is_scp_url("git+git@github.com:foo/bar.git"));
+        assert!(!is_scp_url("foo.patch"));
+        assert!(!is_scp_url("https://github.com/foo/bar.git"));
+        assert!(!is_scp_url("user@example.com.patch"));
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        let expected = "github.com/foo/bar";
+        assert_eq!(
+            normalize_url("git+https://github.com/foo/bar.git"),
+            expected
+        );
+        assert_eq!(normalize_url("https://github.com/Foo/bar/"), expected);
+        assert_eq!(normalize_url("git@github.com:foo/bar.git"), expected);
+        assert_eq!(normalize_url("git://github.com/foo/bar"), expected);
+    }
+}
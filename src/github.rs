@@ -1,7 +1,159 @@
 use crate::errors::*;
+use crate::metrics::METRICS;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time;
+
+/// How often a request is retried after it was rejected by a secondary rate limit
+const MAX_RETRIES: u32 = 5;
+/// The wait before the first retry, it's doubled for every further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Never wait longer than this for a single retry, even if the api asks for it
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+static RATELIMIT: RateLimit = RateLimit::new();
+
+/// An api token, it's only sent to api.github.com and never shown in logs
+#[derive(Clone, PartialEq, Eq)]
+pub struct Token(String);
+
+impl From<String> for Token {
+    fn from(token: String) -> Self {
+        Token(token)
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Token(<redacted>)")
+    }
+}
+
+impl Token {
+    fn header(&self) -> Result<HeaderValue> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", self.0))
+            .map_err(|_| anyhow!("GitHub token contains invalid characters"))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// The api rate limit of this process, as reported by the most recent response
+struct RateLimit {
+    remaining: AtomicI64,
+    /// When the rate limit is reset, in seconds since the unix epoch
+    reset: AtomicU64,
+    warned: AtomicBool,
+}
+
+impl RateLimit {
+    const fn new() -> RateLimit {
+        RateLimit {
+            remaining: AtomicI64::new(-1),
+            reset: AtomicU64::new(0),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    fn update(&self, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        if let Some(remaining) = header("x-ratelimit-remaining").and_then(|v| v.parse().ok()) {
+            self.remaining.store(remaining, Ordering::Relaxed);
+            METRICS.set_github_ratelimit_remaining(remaining);
+            if remaining > 0 {
+                self.warned.store(false, Ordering::Relaxed);
+            }
+        }
+        if let Some(reset) = header("x-ratelimit-reset").and_then(|v| v.parse().ok()) {
+            self.reset.store(reset, Ordering::Relaxed);
+        }
+    }
+
+    /// If no requests are left, the number of seconds until the rate limit is reset
+    fn exhausted(&self, now: u64) -> Option<u64> {
+        let reset = self.reset.load(Ordering::Relaxed);
+        if self.remaining.load(Ordering::Relaxed) == 0 && reset > now {
+            Some(reset - now)
+        } else {
+            None
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// How long to wait before retrying a request that was rate limited, `retry-after` is used if the api sent one
+fn backoff(attempt: u32, retry_after: Option<u64>) -> Duration {
+    let wait = match retry_after {
+        Some(secs) => Duration::from_secs(secs),
+        None => INITIAL_BACKOFF * 2u32.pow(attempt),
+    };
+    wait.min(MAX_BACKOFF)
+}
+
+/// Send an api request, requests are retried with exponential backoff if they hit a secondary
+/// rate limit. Once the primary rate limit is used up, no requests are sent until it's reset
+async fn get(client: &Client, url: &str, token: Option<&Token>) -> Result<Option<Response>> {
+    let mut attempt = 0;
+    loop {
+        if let Some(secs) = RATELIMIT.exhausted(now()) {
+            if !RATELIMIT.warned.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "GitHub api rate limit is exhausted, skipping GitHub lookups for the next {}s",
+                    secs
+                );
+            }
+            return Ok(None);
+        }
+
+        info!("Url={}", url);
+        let mut request = client
+            .get(url)
+            .header("Accept", "application/vnd.github.v3+json");
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, token.header()?);
+        }
+        let response = request.send().await?;
+        RATELIMIT.update(response.headers());
+
+        let status = response.status();
+        if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(Some(response));
+        }
+        if RATELIMIT.exhausted(now()).is_some() {
+            continue;
+        }
+
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        // a 403 without any hint of a rate limit is a regular error
+        let is_rate_limited = status == StatusCode::TOO_MANY_REQUESTS || retry_after.is_some();
+        if !is_rate_limited || attempt >= MAX_RETRIES {
+            return Ok(Some(response));
+        }
+
+        let wait = backoff(attempt, retry_after);
+        warn!(
+            "Rate limited by the GitHub api (http status {}), retrying in {:?}",
+            status, wait
+        );
+        time::sleep(wait).await;
+        attempt += 1;
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Tag {
@@ -67,29 +219,84 @@ pub fn detect_signed_tag_from_url(url: &str) -> Result<Option<TagUrl>> {
     Ok(None)
 }
 
-pub async fn fetch_tag(client: &Client, owner: &str, name: &str, tag: &str) -> Result<Tag> {
+/// Archives of a tag or commit that are generated on the fly, as opposed to uploaded release assets
+pub fn is_generated_archive(url: &str) -> Result<bool> {
+    let re = Regex::new(
+        r"^https?://(?:github\.com/[^/]+/[^/]+/(?:archive|tarball|zipball)/|codeload\.github\.com/)",
+    )?;
+    Ok(re.is_match(url))
+}
+
+/// Lookup a tag, this returns `None` if the api rate limit is exhausted
+pub async fn fetch_tag(
+    client: &Client,
+    token: Option<&Token>,
+    owner: &str,
+    name: &str,
+    tag: &str,
+) -> Result<Option<Tag>> {
     let url = format!(
         "https://api.github.com/repos/{}/{}/git/ref/tags/{}",
         owner, name, tag
     );
 
-    info!("Url={}", url);
-    let json = client
-        .get(url)
-        .header("Accept", "application/vnd.github.v3+json")
-        .send()
-        .await?
-        .error_for_status()?
-        .json()
-        .await?;
-
-    Ok(json)
+    let Some(response) = get(client, &url, token).await? else {
+        return Ok(None);
+    };
+    let json = response.error_for_status()?.json().await?;
+
+    Ok(Some(json))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_generated_archive() -> Result<()> {
+        assert!(is_generated_archive(
+            "https://github.com/kpcyrd/acme-redirect/archive/v0.5.3/acme-redirect-0.5.3.tar.gz"
+        )?);
+        assert!(is_generated_archive(
+            "https://codeload.github.com/kpcyrd/acme-redirect/tar.gz/refs/tags/v0.5.3"
+        )?);
+        assert!(!is_generated_archive(
+            "https://github.com/kpcyrd/acme-redirect/releases/download/v0.5.3/acme-redirect-0.5.3.tar.gz"
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_token_redacted() -> Result<()> {
+        let token = Token::from("github_pat_secret".to_string());
+        assert_eq!(format!("{:?}", Some(&token)), "Some(Token(<redacted>))");
+        assert!(token.header()?.is_sensitive());
+        assert!(Token::from("secret\n".to_string()).header().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratelimit() {
+        let ratelimit = RateLimit::new();
+        assert_eq!(ratelimit.exhausted(1000), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "1060".parse().unwrap());
+        ratelimit.update(&headers);
+        assert_eq!(ratelimit.exhausted(1000), Some(60));
+        assert_eq!(ratelimit.exhausted(1060), None);
+
+        headers.insert("x-ratelimit-remaining", "59".parse().unwrap());
+        ratelimit.update(&headers);
+        assert_eq!(ratelimit.exhausted(1000), None);
+
+        assert_eq!(backoff(0, None), Duration::from_secs(1));
+        assert_eq!(backoff(3, None), Duration::from_secs(8));
+        assert_eq!(backoff(3, Some(30)), Duration::from_secs(30));
+        assert_eq!(backoff(0, Some(3600)), MAX_BACKOFF);
+    }
+
     #[test]
     fn test_github_url_matching() -> Result<()> {
         let x = detect_signed_tag_from_url(
@@ -0,0 +1,94 @@
+use crate::errors::*;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub message: Option<String>,
+    pub target: String,
+}
+
+impl Tag {
+    /// Lightweight tags have no message, only annotated tags can be signed
+    pub fn is_annotated(&self) -> bool {
+        self.message.as_deref().is_some_and(|m| !m.is_empty())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagUrl {
+    pub project: String,
+    pub tag: String,
+}
+
+pub fn detect_signed_tag_from_url(url: &str) -> Result<Option<TagUrl>> {
+    let re = Regex::new(
+        r"^https://gitlab.com/(.+?)/-/archive/([^/]+)/[^/]+\.(tar\.gz|tar\.bz2|tar|zip)$",
+    )?;
+    if let Some(caps) = re.captures(url) {
+        let project = &caps[1];
+        let tag = &caps[2];
+
+        return Ok(Some(TagUrl {
+            project: project.to_string(),
+            tag: tag.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+fn encode_path(s: &str) -> String {
+    s.replace('%', "%25").replace('/', "%2F")
+}
+
+pub async fn fetch_tag(client: &Client, project: &str, tag: &str) -> Result<Option<Tag>> {
+    let url = format!(
+        "https://gitlab.com/api/v4/projects/{}/repository/tags/{}",
+        encode_path(project),
+        encode_path(tag)
+    );
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let json = response.error_for_status()?.json().await?;
+
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitlab_url_matching() -> Result<()> {
+        let x = detect_signed_tag_from_url(
+            "https://gitlab.com/inkscape/inkscape/-/archive/INKSCAPE_1_2_2/inkscape-INKSCAPE_1_2_2.tar.gz",
+        )?;
+        assert_eq!(
+            x,
+            Some(TagUrl {
+                project: "inkscape/inkscape".to_string(),
+                tag: "INKSCAPE_1_2_2".to_string(),
+            })
+        );
+
+        let x = detect_signed_tag_from_url(
+            "https://gitlab.com/gitlab-org/cli/-/archive/v1.24.1/cli-v1.24.1.tar.bz2",
+        )?;
+        assert_eq!(
+            x,
+            Some(TagUrl {
+                project: "gitlab-org/cli".to_string(),
+                tag: "v1.24.1".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use crate::errors::*;
+use reqwest::Client;
+
+const RELEASE_HOSTS: &[&str] = &[
+    "https://ftp.gnu.org/gnu/",
+    "https://ftpmirror.gnu.org/",
+    "https://mirrors.kernel.org/gnu/",
+    "https://download.savannah.gnu.org/releases/",
+    "https://download.savannah.nongnu.org/releases/",
+];
+
+/// GNU and Savannah publish detached `.sig` signatures next to their release tarballs
+pub fn is_release_url(url: &str) -> bool {
+    let url = url.replacen("http://", "https://", 1);
+    RELEASE_HOSTS.iter().any(|prefix| url.starts_with(prefix))
+}
+
+pub fn signature_url(url: &str) -> String {
+    format!("{}.sig", url)
+}
+
+/// Check if a signature has been published for this release url
+pub async fn probe_signature(client: &Client, url: &str) -> Result<Option<String>> {
+    let url = signature_url(url);
+
+    info!("Url={}", url);
+    let response = client.head(&url).send().await?;
+    if response.status().is_success() {
+        Ok(Some(url))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gnu_url_matching() {
+        assert!(is_release_url(
+            "https://ftp.gnu.org/gnu/coreutils/coreutils-9.1.tar.xz"
+        ));
+        assert!(is_release_url(
+            "https://download.savannah.nongnu.org/releases/acl/acl-2.3.1.tar.xz"
+        ));
+        assert!(!is_release_url(
+            "https://github.com/kpcyrd/acme-redirect/archive/v0.5.3.tar.gz"
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::errors::*;
+use base64::Engine;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, Read};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleUrl {
+    /// The module path in its escaped form, eg. `github.com/!burnt!sushi/toml`
+    pub module: String,
+    pub version: String,
+}
+
+pub fn detect_module_from_url(url: &str) -> Result<Option<ModuleUrl>> {
+    let re = Regex::new(r"^https://proxy.golang.org/(.+)/@v/([^/]+)\.zip$")?;
+    if let Some(caps) = re.captures(url) {
+        let module = &caps[1];
+        let version = &caps[2];
+
+        return Ok(Some(ModuleUrl {
+            module: module.to_string(),
+            version: version.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Lookup the `h1:` hash of a module zip in the go checksum database
+pub async fn fetch_sumdb_hash(client: &Client, module: &ModuleUrl) -> Result<Option<String>> {
+    let url = format!(
+        "https://sum.golang.org/lookup/{}@{}",
+        module.module, module.version
+    );
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND || response.status() == StatusCode::GONE {
+        return Ok(None);
+    }
+    let text = response.error_for_status()?.text().await?;
+
+    for line in text.lines() {
+        let mut parts = line.split(' ');
+        if let (Some(_module), Some(version), Some(hash), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        {
+            if version == module.version {
+                return Ok(Some(hash.to_string()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Calculate the `h1:` hash of a module zip, like `golang.org/x/mod/sumdb/dirhash.Hash1`
+pub fn hash_zip(bytes: &[u8]) -> Result<String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open zip")?;
+
+    let mut files = Vec::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        if name.contains('\n') {
+            bail!("Filename in module zip contains newline: {:?}", name);
+        }
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        files.push((name, hex(&Sha256::digest(&buf))));
+    }
+    files.sort();
+
+    let mut summary = Sha256::new();
+    for (name, hash) in files {
+        summary.update(format!("{}  {}\n", hash, name));
+    }
+    let summary = base64::engine::general_purpose::STANDARD.encode(summary.finalize());
+    Ok(format!("h1:{}", summary))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_go_proxy_url_matching() -> Result<()> {
+        let x = detect_module_from_url(
+            "https://proxy.golang.org/github.com/!burnt!sushi/toml/@v/v1.2.1.zip",
+        )?;
+        assert_eq!(
+            x,
+            Some(ModuleUrl {
+                module: "github.com/!burnt!sushi/toml".to_string(),
+                version: "v1.2.1".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
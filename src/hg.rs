@@ -1,13 +1,22 @@
 use crate::errors::*;
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct HgSource {
     url: String,
     revision: Option<String>,
 }
 
 impl HgSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+
     pub fn is_revision_securely_pinned(&self) -> bool {
         if let Some(revision) = &self.revision {
             is_hg_object_hash(revision)
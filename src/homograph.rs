@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use strum::{Display, IntoStaticStr};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum HostIssue {
+    /// A label is punycode encoded (`xn--`), the hostname is displayed differently in a browser
+    Punycode,
+    /// A label mixes characters of different scripts, eg. latin and cyrillic
+    MixedScript,
+    /// The hostname contains non-ascii characters
+    NonAscii,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Other,
+}
+
+fn script(c: char) -> Option<Script> {
+    match c {
+        '0'..='9' | '-' | '_' => None,
+        'a'..='z' | 'A'..='Z' | '\u{00c0}'..='\u{024f}' | '\u{1e00}'..='\u{1eff}' => {
+            Some(Script::Latin)
+        }
+        '\u{0370}'..='\u{03ff}' | '\u{1f00}'..='\u{1fff}' => Some(Script::Greek),
+        '\u{0400}'..='\u{052f}' => Some(Script::Cyrillic),
+        '\u{0530}'..='\u{058f}' => Some(Script::Armenian),
+        _ => Some(Script::Other),
+    }
+}
+
+/// Check a hostname for lookalike characters that could be used to impersonate another domain
+pub fn check_host(host: &str) -> Option<HostIssue> {
+    let mut non_ascii = false;
+    for label in host.split('.') {
+        if label.to_ascii_lowercase().starts_with("xn--") {
+            return Some(HostIssue::Punycode);
+        }
+
+        let mut scripts = label.chars().filter_map(script);
+        if let Some(first) = scripts.next() {
+            if scripts.any(|s| s != first) {
+                return Some(HostIssue::MixedScript);
+            }
+        }
+
+        non_ascii |= !label.is_ascii();
+    }
+
+    if non_ascii {
+        Some(HostIssue::NonAscii)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_host() {
+        assert_eq!(check_host("github.com"), None);
+        assert_eq!(check_host("xn--gthub-n4a.com"), Some(HostIssue::Punycode));
+        // cyrillic `і` in github
+        assert_eq!(
+            check_host("g\u{0456}thub.com"),
+            Some(HostIssue::MixedScript)
+        );
+        assert_eq!(check_host("пример.рф"), Some(HostIssue::NonAscii));
+    }
+}
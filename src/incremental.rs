@@ -0,0 +1,123 @@
+use crate::errors::*;
+use crate::fsck::Target;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The last scanned commit of each packaging repository, so the next scan only needs to check what changed since then
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+    /// The commit of each scan directory, by its canonical path
+    #[serde(default)]
+    repos: BTreeMap<PathBuf, String>,
+}
+
+impl History {
+    /// Load a history file, a file that doesn't exist yet means nothing was scanned before
+    pub fn load(path: &Path) -> Result<History> {
+        let buf = match fs::read(path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("History file {:?} doesn't exist yet", path);
+                return Ok(History::default());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| anyhow!("Failed to read history: {:?}", path))
+            }
+        };
+        serde_json::from_slice(&buf).with_context(|| anyhow!("Failed to parse history: {:?}", path))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut buf = serde_json::to_string_pretty(self)?;
+        buf.push('\n');
+        fs::write(path, buf).with_context(|| anyhow!("Failed to write history: {:?}", path))
+    }
+
+    fn key(dir: &Path) -> Result<PathBuf> {
+        fs::canonicalize(dir).with_context(|| anyhow!("Failed to resolve path: {:?}", dir))
+    }
+
+    /// The commit a scan directory was at when it was scanned the last time
+    pub fn last_commit(&self, dir: &Path) -> Result<Option<&str>> {
+        let key = Self::key(dir)?;
+        Ok(self.repos.get(&key).map(String::as_str))
+    }
+
+    pub fn record(&mut self, dir: &Path, commit: String) -> Result<()> {
+        let key = Self::key(dir)?;
+        self.repos.insert(key, commit);
+        Ok(())
+    }
+}
+
+/// The top-level directories that contain a changed file, with the svntogit and gitlab
+/// monorepo layout these are the pkgbases
+pub fn changed_pkgbases(files: &[String]) -> BTreeSet<String> {
+    files
+        .iter()
+        .filter_map(|file| {
+            let (pkgbase, _) = file.split_once('/')?;
+            Some(pkgbase.to_string())
+        })
+        .collect()
+}
+
+/// If a target found in a scan directory belongs to one of the changed pkgbases
+pub fn is_changed(dir: &Path, changed: &BTreeSet<String>, target: &Target) -> bool {
+    let Target::BuildPath(path) = target else {
+        return true;
+    };
+    let Ok(path) = path.strip_prefix(dir) else {
+        return true;
+    };
+    match path.components().next() {
+        Some(Component::Normal(pkgbase)) => changed.contains(pkgbase.to_string_lossy().as_ref()),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_pkgbases() -> Result<()> {
+        let changed = changed_pkgbases(&[
+            "linux/trunk/PKGBUILD".to_string(),
+            "linux/repos/core-x86_64/PKGBUILD".to_string(),
+            "zstd/zstd.install".to_string(),
+            "README.md".to_string(),
+        ]);
+        assert_eq!(
+            changed.iter().map(String::as_str).collect::<Vec<_>>(),
+            &["linux", "zstd"]
+        );
+
+        let dir = Path::new("./svntogit-packages");
+        for (path, expected) in [
+            ("./svntogit-packages/linux/trunk", true),
+            ("./svntogit-packages/zstd", true),
+            ("./svntogit-packages/python/trunk", false),
+        ] {
+            let target = Target::BuildPath(PathBuf::from(path));
+            assert_eq!(is_changed(dir, &changed, &target), expected, "{}", path);
+        }
+
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("history.json");
+        let mut history = History::load(&path)?;
+        assert_eq!(history.last_commit(tmp.path())?, None);
+        history.record(tmp.path(), "a".repeat(40))?;
+        history.save(&path)?;
+
+        let history = History::load(&path)?;
+        assert_eq!(
+            history.last_commit(tmp.path())?,
+            Some("a".repeat(40).as_str())
+        );
+        Ok(())
+    }
+}
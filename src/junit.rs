@@ -0,0 +1,121 @@
+use crate::errors::*;
+use crate::report::{ErrorKind, Report};
+use std::fmt::Write as _;
+use std::io::Write;
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // control characters are not allowed in xml 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {
+                write!(out, "\\u{{{:x}}}", c as u32).ok();
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn write<W: Write>(mut w: W, report: &Report) -> Result<()> {
+    let failures = report
+        .packages
+        .iter()
+        .map(|pkg| pkg.findings.len())
+        .sum::<usize>();
+    let tests = report
+        .packages
+        .iter()
+        .map(|pkg| pkg.findings.len().max(1))
+        .sum::<usize>()
+        + report.errors.len();
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<testsuites name="{}" tests="{}" failures="{}" errors="{}">"#,
+        env!("CARGO_PKG_NAME"),
+        tests,
+        failures,
+        report.errors.len(),
+    )?;
+
+    for pkg in &report.packages {
+        let target = escape(&pkg.target);
+        writeln!(
+            w,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0">"#,
+            target,
+            pkg.findings.len().max(1),
+            pkg.findings.len(),
+        )?;
+
+        if pkg.findings.is_empty() {
+            writeln!(w, r#"    <testcase classname="{}" name="fsck"/>"#, target)?;
+        }
+
+        for finding in &pkg.findings {
+            let kind: &'static str = finding.into();
+            writeln!(
+                w,
+                r#"    <testcase classname="{}" name="{}">"#,
+                target, kind
+            )?;
+            writeln!(
+                w,
+                r#"      <failure type="{}" message="[{}] {}">{}</failure>"#,
+                kind,
+                finding.severity(),
+                escape(finding.description()),
+                escape(&finding.to_string()),
+            )?;
+            writeln!(w, "    </testcase>")?;
+        }
+
+        writeln!(w, "  </testsuite>")?;
+    }
+
+    for err in &report.errors {
+        let target = escape(&err.target);
+        writeln!(
+            w,
+            r#"  <testsuite name="{}" tests="1" failures="0" errors="1">"#,
+            target
+        )?;
+        writeln!(w, r#"    <testcase classname="{}" name="fsck">"#, target)?;
+        let message = match err.kind {
+            ErrorKind::Failed => "Failed to check package",
+            ErrorKind::TimedOut => "Checking the package timed out",
+        };
+        writeln!(
+            w,
+            r#"      <error message="{}">{}</error>"#,
+            message,
+            escape(&err.error)
+        )?;
+        writeln!(w, "    </testcase>")?;
+        writeln!(w, "  </testsuite>")?;
+    }
+
+    writeln!(w, "</testsuites>")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"Unknown scheme "git+foo": <a & b>"#),
+            "Unknown scheme &quot;git+foo&quot;: &lt;a &amp; b&gt;"
+        );
+        assert_eq!(escape("a\x1bb\nc"), "a\\u{1b}b\nc");
+    }
+}
@@ -1,11 +1,58 @@
+pub mod archweb;
 pub mod args;
 pub mod asp;
+pub mod aur;
+pub mod baseline;
+pub mod bitbucket;
 pub mod bzr;
+pub mod cache;
+pub mod checkpoint;
+pub mod codeberg;
+pub mod config;
+pub mod darcs;
+pub mod db;
+pub mod deps;
+pub mod disk;
 pub mod errors;
+pub mod fossil;
 pub mod fsck;
 pub mod git;
 pub mod github;
+pub mod gitlab;
+pub mod gnu;
+pub mod golang;
 pub mod hg;
+pub mod homograph;
+pub mod incremental;
+pub mod junit;
+pub mod lint;
 pub mod makepkg;
+pub mod metrics;
+pub mod native;
+pub mod npm;
 pub mod osv;
+pub mod packaging;
+pub mod pacman;
+pub mod pgp;
+pub mod pkgctl;
+pub mod probe;
+pub mod progress;
+pub mod pypi;
+pub mod registry;
+pub mod remote;
+pub mod report;
+pub mod sandbox;
+pub mod sarif;
+pub mod sbom;
+pub mod sourcehut;
+pub mod srcinfo;
+pub mod srcpkg;
+pub mod state;
+pub mod stats;
+pub mod suppress;
 pub mod svn;
+pub mod template;
+pub mod term;
+pub mod verify;
+pub mod version;
+pub mod watch;
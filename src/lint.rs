@@ -0,0 +1,466 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use strum::{Display, IntoStaticStr};
+
+/// The functions of a PKGBUILD that makepkg runs after the sources have been downloaded
+pub fn is_build_function(name: &str) -> bool {
+    matches!(name, "prepare" | "pkgver" | "build" | "check" | "package")
+        || name.starts_with("package_")
+}
+
+const NETWORK_COMMANDS: &[&str] = &[
+    r"curl",
+    r"wget",
+    r"aria2c",
+    r"git\s+(clone|fetch|pull|submodule\s+update)",
+    r"svn\s+(checkout|co|export|update|up)",
+    r"hg\s+(clone|pull)",
+    r"pip3?\s+install",
+    r"python3?\s+-m\s+pip\s+install",
+    r"npm\s+(install|i|ci)",
+    r"yarn",
+    r"go\s+get",
+];
+
+static NETWORK_COMMAND: LazyLock<Regex> = LazyLock::new(|| {
+    let commands = NETWORK_COMMANDS.join("|");
+    Regex::new(&format!(
+        r"(?:^|[\s;&|(`])((?:{})(?:\s[^;&|\n]*)?)(?:$|[;&|\n)`])",
+        commands
+    ))
+    .expect("network command regex is valid")
+});
+
+/// `yarn` without a subcommand installs the dependencies, other subcommands like `yarn build` are offline
+fn is_yarn_fetch(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    if words.next() != Some("yarn") {
+        return true;
+    }
+    match words.find(|word| !word.starts_with('-')) {
+        Some(subcommand) => matches!(subcommand, "install" | "add"),
+        None => true,
+    }
+}
+
+/// Find commands in a function body that download something from the network
+pub fn network_commands(body: &str) -> Vec<String> {
+    body.lines()
+        .filter(|line| !line.contains("--no-index") && !line.contains("--offline"))
+        .flat_map(|line| {
+            NETWORK_COMMAND
+                .captures_iter(line)
+                .map(|caps| caps[1].trim().to_string())
+                .filter(|command| is_yarn_fetch(command))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lint {
+    /// `eval` on the output of a download
+    EvalDownload,
+    /// `sudo` anywhere in the PKGBUILD
+    Sudo,
+    /// Writing to an absolute path that isn't inside `$srcdir` or `$pkgdir`
+    WriteOutsideBuildDir,
+    /// A download piped into a shell
+    PipeToShell,
+}
+
+/// A line of a PKGBUILD that matched a lint, with its 1-based line number
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Match {
+    pub lint: Lint,
+    pub line: usize,
+    pub code: String,
+}
+
+fn strip_comment(line: &str) -> &str {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('#') {
+        return "";
+    }
+    match line.find(" #") {
+        Some(idx) => &line[..idx],
+        None => line,
+    }
+}
+
+fn is_allowed_path(path: &str) -> bool {
+    let path = path.trim_matches(|c| c == '"' || c == '\'');
+    !path.starts_with('/') || path.starts_with("/dev/")
+}
+
+static REDIRECT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r">>?\s*(\S+)").expect("redirect regex is valid"));
+
+fn writes_outside_build_dir(line: &str) -> bool {
+    if REDIRECT
+        .captures_iter(line)
+        .any(|caps| !is_allowed_path(&caps[1]))
+    {
+        return true;
+    }
+
+    for command in line.split(['&', ';', '|']) {
+        let mut words = command.split_whitespace();
+        let Some(cmd) = words.next() else {
+            continue;
+        };
+        let args = words.filter(|w| !w.starts_with('-')).collect::<Vec<_>>();
+        let targets = match cmd {
+            "cp" | "mv" | "install" | "ln" => args.last().into_iter().collect::<Vec<_>>(),
+            "mkdir" | "touch" | "rm" | "tee" | "chmod" | "chown" => args.iter().collect(),
+            _ => continue,
+        };
+        if targets.into_iter().any(|path| !is_allowed_path(path)) {
+            return true;
+        }
+    }
+
+    false
+}
+
+static EVAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(^|[\s;&|(])eval\s.*(\$\(|`)\s*(curl|wget)\b").expect("eval regex is valid")
+});
+static SUDO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(^|[\s;&|(`])sudo(\s|$)").expect("sudo regex is valid"));
+static PIPE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(curl|wget)\b[^|]*\|\s*(sudo\s+)?(ba|z|da|k)?sh\b").expect("pipe regex is valid")
+});
+
+/// Scan the text of a PKGBUILD for dangerous constructs
+pub fn scan_pkgbuild(text: &str) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let code = strip_comment(line);
+        let mut push = |lint| {
+            matches.push(Match {
+                lint,
+                line: i + 1,
+                code: line.trim().to_string(),
+            })
+        };
+
+        if EVAL.is_match(code) {
+            push(Lint::EvalDownload);
+        }
+        if SUDO.is_match(code) {
+            push(Lint::Sudo);
+        }
+        if PIPE.is_match(code) {
+            push(Lint::PipeToShell);
+        }
+        if writes_outside_build_dir(code) {
+            push(Lint::WriteOutsideBuildDir);
+        }
+    }
+    matches
+}
+
+static PIN_FRAGMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"#(?:commit|revision)=\$\{?([A-Za-z_][A-Za-z0-9_]*)\}?")
+        .expect("fragment regex is valid")
+});
+static PIN_VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$\{?([A-Za-z_][A-Za-z0-9_]*(?:commit|rev|hash)[A-Za-z0-9_]*)\}?")
+        .expect("variable regex is valid")
+});
+
+/// Find variables that are supposed to pin a commit in source urls, eg. `#commit=$_commit`
+///
+/// Returns the variable name, the first line it's used on and if it's used in a `#commit=` or
+/// `#revision=` fragment.
+pub fn pin_variables(text: &str) -> Vec<(String, usize, bool)> {
+    let mut variables = Vec::<(String, usize, bool)>::new();
+    for (i, line) in text.lines().enumerate() {
+        let code = strip_comment(line);
+        if !code.contains("://") {
+            continue;
+        }
+
+        let fragments = PIN_FRAGMENT
+            .captures_iter(code)
+            .map(|caps| (caps[1].to_string(), true));
+        let names = PIN_VARIABLE
+            .captures_iter(code)
+            .map(|caps| (caps[1].to_string(), false));
+        for (name, is_fragment) in fragments.chain(names) {
+            if let Some(existing) = variables.iter_mut().find(|(x, _, _)| *x == name) {
+                existing.2 |= is_fragment;
+            } else {
+                variables.push((name, i + 1, is_fragment));
+            }
+        }
+    }
+    variables
+}
+
+/// Check if the value of a pin variable is a full commit hash (sha1 or sha256)
+pub fn is_full_hash(value: &str) -> bool {
+    matches!(value.len(), 40 | 64) && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlIssue {
+    /// Whitespace, usually from a quoting bug in an array
+    Whitespace,
+    /// Control characters like newlines or escape sequences
+    ControlCharacter,
+    /// Backslashes, usually from an escape sequence that wasn't interpreted
+    Backslash,
+    /// A scheme directly followed by another one, eg. `https://https://`
+    DoubleScheme,
+}
+
+static DOUBLE_SCHEME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z][A-Za-z0-9+-]*://[A-Za-z][A-Za-z0-9+-]*:/")
+        .expect("double scheme regex is valid")
+});
+
+/// Check a source url for characters that make the fetched input unpredictable
+pub fn check_url(url: &str) -> Option<UrlIssue> {
+    if url.chars().any(|c| c.is_control()) {
+        Some(UrlIssue::ControlCharacter)
+    } else if url.chars().any(|c| c.is_whitespace()) {
+        Some(UrlIssue::Whitespace)
+    } else if url.contains('\\') {
+        Some(UrlIssue::Backslash)
+    } else if DOUBLE_SCHEME.is_match(url) {
+        Some(UrlIssue::DoubleScheme)
+    } else {
+        None
+    }
+}
+
+const VERBATIM_EXTENSIONS: &[&str] = &[
+    ".jar",
+    ".appimage",
+    ".run",
+    ".bin",
+    ".exe",
+    ".whl",
+    ".deb",
+    ".rpm",
+];
+
+/// Check if a file is usually executed or installed as-is instead of being extracted
+pub fn is_verbatim_artifact(filename: &str) -> bool {
+    let filename = filename.to_lowercase();
+    VERBATIM_EXTENSIONS
+        .iter()
+        .any(|ext| filename.ends_with(ext))
+}
+
+/// Check if a `DLAGENTS=` entry disables certificate verification
+pub fn dlagent_disables_tls(agent: &str) -> bool {
+    let command = agent.split_once("::").map(|x| x.1).unwrap_or(agent);
+    let is_curl = command
+        .split_whitespace()
+        .next()
+        .is_some_and(|cmd| cmd.ends_with("curl"));
+    command.split_whitespace().any(|arg| match arg {
+        "--insecure" | "--no-check-certificate" | "--check-certificate=false" => true,
+        // curl allows combining short options, eg. `-fLk`
+        _ if is_curl && arg.starts_with('-') && !arg.starts_with("--") => arg.contains('k'),
+        _ => false,
+    })
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum ScriptletIssue {
+    /// The scriptlet accesses the network
+    NetworkAccess,
+    /// The scriptlet downloads and executes code
+    RemoteCode,
+    /// The scriptlet modifies the pacman configuration or keyring
+    PacmanConfig,
+}
+
+static PACMAN_CONFIG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(pacman-key\s|/etc/pacman\.conf|/etc/pacman\.d/|/usr/share/pacman/keyrings|/etc/makepkg\.conf)",
+    )
+    .expect("pacman regex is valid")
+});
+
+/// Scan an `.install` scriptlet, these run as root on the system of everybody installing the package
+pub fn scan_scriptlet(text: &str) -> Vec<(ScriptletIssue, usize, String)> {
+    let mut matches = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let code = strip_comment(line);
+        let mut push = |issue| matches.push((issue, i + 1, line.trim().to_string()));
+
+        let lints = scan_pkgbuild(code);
+        if lints
+            .iter()
+            .any(|m| matches!(m.lint, Lint::EvalDownload | Lint::PipeToShell))
+        {
+            push(ScriptletIssue::RemoteCode);
+        } else if !network_commands(code).is_empty() {
+            push(ScriptletIssue::NetworkAccess);
+        }
+        if PACMAN_CONFIG.is_match(code) {
+            push(ScriptletIssue::PacmanConfig);
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_variables() {
+        let text = r#"_commit=main
+_rev=1234
+source=("git+https://example.com/foo.git#commit=$_commit"
+        "https://example.com/foo/archive/${_commit}.tar.gz"
+        "https://example.com/bar-r${_rev}.tar.gz"
+        "hg+https://example.com/baz#revision=$_baz")
+"#;
+        assert_eq!(
+            pin_variables(text),
+            &[
+                ("_commit".to_string(), 3, true),
+                ("_rev".to_string(), 5, false),
+                ("_baz".to_string(), 6, true),
+            ]
+        );
+        assert!(is_full_hash("a20e5bde5cb5c8dae3ecb01c3b2e6a3b0a0a4d5b"));
+        assert!(!is_full_hash("a20e5bd"));
+        assert!(!is_full_hash("main"));
+    }
+
+    #[test]
+    fn test_is_verbatim_artifact() {
+        assert!(is_verbatim_artifact("foo-1.0.jar"));
+        assert!(is_verbatim_artifact("Foo-1.0-x86_64.AppImage"));
+        assert!(!is_verbatim_artifact("foo-1.0.tar.gz"));
+    }
+
+    #[test]
+    fn test_check_url() {
+        assert_eq!(check_url("https://example.com/foo-1.0.tar.gz"), None);
+        assert_eq!(
+            check_url("https://web.archive.org/web/2020/https://example.com/foo.tar.gz"),
+            None
+        );
+        assert_eq!(
+            check_url("https://example.com/foo 1.0.tar.gz"),
+            Some(UrlIssue::Whitespace)
+        );
+        assert_eq!(
+            check_url("https://example.com/foo\n"),
+            Some(UrlIssue::ControlCharacter)
+        );
+        assert_eq!(
+            check_url("https://example.com\\foo.tar.gz"),
+            Some(UrlIssue::Backslash)
+        );
+        assert_eq!(
+            check_url("https://https://example.com/foo.tar.gz"),
+            Some(UrlIssue::DoubleScheme)
+        );
+        assert_eq!(
+            check_url("git+https://git+https://example.com/foo.git"),
+            Some(UrlIssue::DoubleScheme)
+        );
+    }
+
+    #[test]
+    fn test_scan_pkgbuild() {
+        let text = r#"pkgname=foo
+# sudo in a comment is fine
+build() {
+  eval "$(curl -sSf https://example.com/env)"
+  curl https://example.com/install.sh | sh
+  make > /dev/null
+  echo foo > "$srcdir/foo"
+}
+package() {
+  sudo make install
+  install -Dm644 foo.conf /etc/foo.conf
+  install -Dm644 foo "$pkgdir/usr/bin/foo"
+  echo bar >> /etc/profile
+}
+"#;
+        let lints = scan_pkgbuild(text)
+            .into_iter()
+            .map(|m| (m.lint, m.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lints,
+            &[
+                (Lint::EvalDownload, 4),
+                (Lint::PipeToShell, 5),
+                (Lint::Sudo, 10),
+                (Lint::WriteOutsideBuildDir, 11),
+                (Lint::WriteOutsideBuildDir, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_network_commands() {
+        let body = "build () \n{ \n    cd \"$srcdir/foo\";\n    git clone https://example.com/foo.git vendor;\n    ./configure --with-curl;\n    curl -sSf https://example.com/install.sh | sh;\n    pip install --no-index --no-deps .;\n    make\n}\n";
+        assert_eq!(
+            network_commands(body),
+            &[
+                "git clone https://example.com/foo.git vendor",
+                "curl -sSf https://example.com/install.sh",
+            ]
+        );
+
+        let body = "build() {\n    yarn --frozen-lockfile\n    yarn build\n    yarn run build\n    yarn add left-pad\n}\n";
+        assert_eq!(
+            network_commands(body),
+            &["yarn --frozen-lockfile", "yarn add left-pad"]
+        );
+    }
+
+    #[test]
+    fn test_scan_scriptlet() {
+        let text = r#"post_install() {
+  # pacman-key in a comment is fine
+  curl -sSf https://example.com/setup.sh | bash
+  wget -q https://example.com/data -O /var/lib/foo/data
+  pacman-key --recv-keys 0123456789ABCDEF
+  echo '[foo]' >> /etc/pacman.conf
+}
+"#;
+        let issues = scan_scriptlet(text)
+            .into_iter()
+            .map(|(issue, line, _)| (issue, line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            &[
+                (ScriptletIssue::RemoteCode, 3),
+                (ScriptletIssue::NetworkAccess, 4),
+                (ScriptletIssue::PacmanConfig, 5),
+                (ScriptletIssue::PacmanConfig, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dlagent_disables_tls() {
+        assert!(!dlagent_disables_tls(
+            "https::/usr/bin/curl -qgb \"\" -fLC - --retry 3 --retry-delay 3 -o %o %u"
+        ));
+        assert!(dlagent_disables_tls("https::/usr/bin/curl -fLk -o %o %u"));
+        assert!(dlagent_disables_tls(
+            "https::/usr/bin/wget --no-check-certificate -O %o %u"
+        ));
+    }
+}
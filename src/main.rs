@@ -1,6 +1,7 @@
-use archlinux_inputs_fsck::args::{Args, Scan, SubCommand};
+use archlinux_inputs_fsck::args::{Args, Outcome, Scan, SubCommand};
 use archlinux_inputs_fsck::errors::*;
 use archlinux_inputs_fsck::fsck::Finding;
+use archlinux_inputs_fsck::watch;
 use clap::Parser;
 use env_logger::Env;
 use strum::VariantNames;
@@ -17,14 +18,33 @@ async fn main() -> Result<()> {
     };
     env_logger::init_from_env(Env::default().default_filter_or(log_level));
 
-    match args.subcommand {
+    let mut subcommand = args.subcommand;
+    match &mut subcommand {
+        SubCommand::Check(check) => check.apply_config()?,
+        SubCommand::Vulns(vulns) => vulns.check.apply_config()?,
+        _ => (),
+    }
+
+    let outcome = match subcommand {
+        SubCommand::Check(check) if check.watch => watch::run(&check, &check).await?,
         SubCommand::Check(check) => check.run(&check).await?,
+        SubCommand::Vulns(vulns) if vulns.check.watch => watch::run(&vulns, &vulns.check).await?,
         SubCommand::Vulns(vulns) => vulns.run(&vulns.check).await?,
+        SubCommand::Sbom(sbom) => {
+            sbom.run().await?;
+            Outcome::default()
+        }
         SubCommand::SupportedIssues => {
             for issue in Finding::VARIANTS {
                 println!("{}", issue);
             }
+            Outcome::default()
         }
+    };
+
+    let code = outcome.exit_code();
+    if code != 0 {
+        std::process::exit(code);
     }
 
     Ok(())
@@ -1,7 +1,16 @@
 use crate::errors::*;
-use std::path::Path;
+use crate::native::Script;
+use crate::sandbox::Sandbox;
+use crate::srcinfo::SrcInfo;
+use clap::ValueEnum;
+use serde::de::Deserializer;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
-use tokio::process::Command;
+use std::time::Duration;
+use tokio::time;
 
 pub const SUPPORTED_ALGS: &[&str] = &[
     "sha256sums",
@@ -11,6 +20,7 @@ pub const SUPPORTED_ALGS: &[&str] = &[
     "b2sums",
     "md5sums",
     "sha1sums",
+    "cksums",
 ];
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -37,18 +47,76 @@ impl Source {
     pub fn scheme(&self) -> Option<&str> {
         self.url().split_once("://").map(|x| x.0)
     }
+
+    /// The filename makepkg downloads this source to, like `get_filename` in makepkg
+    pub fn destination(&self) -> String {
+        if let Some(filename) = self.filename() {
+            return filename.to_string();
+        }
+
+        let url = self.url();
+        let url = url.split('#').next().unwrap_or(url);
+        let url = url.strip_suffix("?signed").unwrap_or(url);
+        let url = url.trim_end_matches('/');
+        let filename = url.rsplit('/').next().unwrap_or(url);
+
+        let is_vcs = self
+            .scheme()
+            .is_some_and(|scheme| scheme.contains('+') || scheme == "git");
+        if is_vcs {
+            for ext in [".git", ".hg", ".bzr", ".svn", ".fossil"] {
+                if let Some(filename) = filename.strip_suffix(ext) {
+                    return filename.to_string();
+                }
+            }
+        }
+        filename.to_string()
+    }
+}
+
+impl Serialize for Source {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Source", 2)?;
+        s.serialize_field("url", self.url())?;
+        s.serialize_field("filename", &self.filename())?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Source {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Fields {
+            url: String,
+            filename: Option<String>,
+        }
+
+        let Fields { url, filename } = Fields::deserialize(deserializer)?;
+        Ok(match filename {
+            Some(filename) => Source::UrlWithFilename((url, filename)),
+            None => Source::Url(url),
+        })
+    }
 }
 
-async fn exec_sh(folder: &Path, cmd: &str) -> Result<Vec<String>> {
-    let child = Command::new("bash")
-        .arg("-c")
-        .arg(format!("source ./PKGBUILD;{}", cmd))
+/// How long sourcing the PKGBUILD may take before bash is killed
+const EVAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+async fn exec_sh(folder: &Path, sandbox: Sandbox, cmd: &str) -> Result<Vec<String>> {
+    let buf = run_bash(folder, sandbox, &format!("source ./PKGBUILD;{}", cmd)).await?;
+    Ok(buf.lines().map(String::from).collect())
+}
+
+async fn run_bash(folder: &Path, sandbox: Sandbox, cmd: &str) -> Result<String> {
+    let child = sandbox
+        .bash_command(folder, cmd)
         .stdout(Stdio::piped())
-        .current_dir(folder)
         .spawn()
-        .context("Failed to run bash")?;
+        .with_context(|| anyhow!("Failed to run bash (sandbox: {:?})", sandbox.resolve()))?;
 
-    let out = child.wait_with_output().await?;
+    let out = time::timeout(EVAL_TIMEOUT, child.wait_with_output())
+        .await
+        .with_context(|| anyhow!("Evaluating the PKGBUILD timed out: {:?}", cmd))??;
     if !out.status.success() {
         bail!(
             "Process (bash, {:?}) exited with error: {:?}",
@@ -58,28 +126,276 @@ async fn exec_sh(folder: &Path, cmd: &str) -> Result<Vec<String>> {
     }
 
     let buf = String::from_utf8(out.stdout).context("Shell output contains invalid utf8")?;
-    Ok(buf.lines().map(String::from).collect())
+    Ok(buf)
 }
 
-pub async fn list_variable(folder: &Path, var: &str) -> Result<Vec<String>> {
+/// Evaluate the PKGBUILD with `makepkg --printsrcinfo`, this reads all metadata in one process
+pub async fn print_srcinfo(folder: &Path, sandbox: Sandbox) -> Result<SrcInfo> {
+    let buf = run_bash(folder, sandbox, "makepkg --printsrcinfo").await?;
+    Ok(SrcInfo::parse(&buf))
+}
+
+pub async fn list_variable(folder: &Path, sandbox: Sandbox, var: &str) -> Result<Vec<String>> {
     exec_sh(
         folder,
-        &format!("for x in ${{{}[@]}}; do echo \"$x\"; done", var),
+        sandbox,
+        &format!("for x in \"${{{}[@]}}\"; do echo \"$x\"; done", var),
     )
     .await
 }
 
-pub async fn list_sources(folder: &Path) -> Result<Vec<Source>> {
-    let sources = list_variable(folder, "source").await?;
-    let sources = sources
+/// Check if the PKGBUILD defines a function with this name, eg. `pkgver`
+pub async fn has_function(folder: &Path, sandbox: Sandbox, name: &str) -> Result<bool> {
+    let out = exec_sh(
+        folder,
+        sandbox,
+        &format!("if declare -F {} >/dev/null; then echo 1; fi", name),
+    )
+    .await?;
+    Ok(!out.is_empty())
+}
+
+/// List the names of all functions defined by the PKGBUILD
+pub async fn list_functions(folder: &Path, sandbox: Sandbox) -> Result<Vec<String>> {
+    let out = exec_sh(folder, sandbox, "declare -F").await?;
+    Ok(out
         .into_iter()
-        .map(|line| {
-            if let Some((file, url)) = line.split_once("::") {
-                Source::UrlWithFilename((url.to_string(), file.to_string()))
-            } else {
-                Source::Url(line)
+        .filter_map(|line| line.strip_prefix("declare -f ").map(String::from))
+        .collect())
+}
+
+/// The body of a function as normalized by bash, comments are already removed
+pub async fn function_body(folder: &Path, sandbox: Sandbox, name: &str) -> Result<String> {
+    let out = exec_sh(folder, sandbox, &format!("declare -f {}", name)).await?;
+    Ok(out.join("\n"))
+}
+
+fn is_checksum_array(var: &str) -> bool {
+    let alg = var.split_once('_').map(|x| x.0).unwrap_or(var);
+    alg.len() > "sums".len()
+        && alg.ends_with("sums")
+        && alg
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+}
+
+/// List all variables that look like a checksum array, eg. `sha256sums` or `b2sums_x86_64`
+pub async fn list_checksum_arrays(folder: &Path, sandbox: Sandbox) -> Result<Vec<String>> {
+    let vars = exec_sh(folder, sandbox, "compgen -A variable").await?;
+    let vars = vars
+        .into_iter()
+        .filter(|var| is_checksum_array(var))
+        .collect();
+    Ok(vars)
+}
+
+fn parse_source(line: String) -> Source {
+    if let Some((file, url)) = line.split_once("::") {
+        Source::UrlWithFilename((url.to_string(), file.to_string()))
+    } else {
+        Source::Url(line)
+    }
+}
+
+pub async fn list_sources(folder: &Path, sandbox: Sandbox, var: &str) -> Result<Vec<Source>> {
+    let sources = list_variable(folder, sandbox, var).await?;
+    Ok(sources.into_iter().map(parse_source).collect())
+}
+
+/// How the variables and functions of a PKGBUILD are read
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Evaluator {
+    /// Source the PKGBUILD with bash, this executes the code in the PKGBUILD
+    #[default]
+    Bash,
+    /// Only read the metadata in `.SRCINFO`, nothing is executed but checks that need the PKGBUILD functions are skipped
+    Srcinfo,
+    /// Parse the PKGBUILD without executing it, only top-level assignments without command substitution are understood
+    Native,
+    /// Read the metadata with `makepkg --printsrcinfo` in a single process, functions and custom variables are read with bash
+    Makepkg,
+}
+
+/// The result of reading the PKGBUILD with an evaluator
+#[derive(Debug)]
+enum Parsed {
+    Bash(Sandbox),
+    Srcinfo(SrcInfo),
+    Native(Script),
+    Makepkg(SrcInfo, Sandbox),
+}
+
+/// A package directory, with the PKGBUILD read by one of the evaluators
+#[derive(Debug)]
+pub struct Pkgbuild {
+    folder: PathBuf,
+    parsed: Parsed,
+}
+
+impl Pkgbuild {
+    pub async fn load(folder: &Path, evaluator: Evaluator, sandbox: Sandbox) -> Result<Self> {
+        let parsed = match evaluator {
+            Evaluator::Bash => Parsed::Bash(sandbox),
+            Evaluator::Srcinfo => {
+                let path = folder.join(".SRCINFO");
+                let text = fs::read_to_string(&path)
+                    .with_context(|| anyhow!("Failed to read .SRCINFO: {:?}", path))?;
+                Parsed::Srcinfo(SrcInfo::parse(&text))
+            }
+            Evaluator::Native => {
+                let path = folder.join("PKGBUILD");
+                let text = fs::read_to_string(&path)
+                    .with_context(|| anyhow!("Failed to read PKGBUILD: {:?}", path))?;
+                let script = Script::parse(&text)
+                    .with_context(|| anyhow!("Failed to parse PKGBUILD: {:?}", path))?;
+                Parsed::Native(script)
             }
+            Evaluator::Makepkg => {
+                let srcinfo = print_srcinfo(folder, sandbox)
+                    .await
+                    .context("Failed to evaluate PKGBUILD with makepkg")?;
+                Parsed::Makepkg(srcinfo, sandbox)
+            }
+        };
+        Ok(Pkgbuild {
+            folder: folder.to_path_buf(),
+            parsed,
         })
-        .collect();
-    Ok(sources)
+    }
+
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    pub fn evaluator(&self) -> Evaluator {
+        match self.parsed {
+            Parsed::Bash(_) => Evaluator::Bash,
+            Parsed::Srcinfo(_) => Evaluator::Srcinfo,
+            Parsed::Native(_) => Evaluator::Native,
+            Parsed::Makepkg(..) => Evaluator::Makepkg,
+        }
+    }
+
+    /// If the functions of the PKGBUILD are known, `.SRCINFO` only has the variables
+    pub fn has_functions(&self) -> bool {
+        !matches!(self.parsed, Parsed::Srcinfo(_))
+    }
+
+    pub async fn list_variable(&self, var: &str) -> Result<Vec<String>> {
+        match &self.parsed {
+            Parsed::Bash(sandbox) => list_variable(&self.folder, *sandbox, var).await,
+            Parsed::Srcinfo(srcinfo) if var == "pkgname" => Ok(srcinfo.pkgnames.clone()),
+            Parsed::Srcinfo(srcinfo) => Ok(srcinfo.get(var).to_vec()),
+            Parsed::Native(script) => script.variable(var),
+            Parsed::Makepkg(srcinfo, _) if var == "pkgname" => Ok(srcinfo.pkgnames.clone()),
+            Parsed::Makepkg(srcinfo, _) if srcinfo.covers(var) => Ok(srcinfo.get(var).to_vec()),
+            Parsed::Makepkg(_, sandbox) => list_variable(&self.folder, *sandbox, var).await,
+        }
+    }
+
+    pub async fn has_function(&self, name: &str) -> Result<bool> {
+        match &self.parsed {
+            Parsed::Bash(sandbox) => has_function(&self.folder, *sandbox, name).await,
+            Parsed::Srcinfo(_) => Ok(false),
+            Parsed::Native(script) => Ok(script.function(name).is_some()),
+            Parsed::Makepkg(_, sandbox) => has_function(&self.folder, *sandbox, name).await,
+        }
+    }
+
+    pub async fn list_functions(&self) -> Result<Vec<String>> {
+        match &self.parsed {
+            Parsed::Bash(sandbox) => list_functions(&self.folder, *sandbox).await,
+            Parsed::Srcinfo(_) => Ok(Vec::new()),
+            Parsed::Native(script) => Ok(script.functions().map(String::from).collect()),
+            Parsed::Makepkg(_, sandbox) => list_functions(&self.folder, *sandbox).await,
+        }
+    }
+
+    pub async fn function_body(&self, name: &str) -> Result<String> {
+        match &self.parsed {
+            Parsed::Bash(sandbox) => function_body(&self.folder, *sandbox, name).await,
+            Parsed::Srcinfo(_) => bail!("Function bodies are not available in .SRCINFO"),
+            Parsed::Native(script) => script
+                .function(name)
+                .map(String::from)
+                .with_context(|| anyhow!("Function is not defined: {:?}", name)),
+            Parsed::Makepkg(_, sandbox) => function_body(&self.folder, *sandbox, name).await,
+        }
+    }
+
+    pub async fn list_checksum_arrays(&self) -> Result<Vec<String>> {
+        match &self.parsed {
+            Parsed::Bash(sandbox) => list_checksum_arrays(&self.folder, *sandbox).await,
+            Parsed::Srcinfo(srcinfo) | Parsed::Makepkg(srcinfo, _) => Ok(srcinfo
+                .values
+                .keys()
+                .filter(|var| is_checksum_array(var))
+                .cloned()
+                .collect()),
+            Parsed::Native(script) => Ok(script
+                .variables()
+                .filter(|var| is_checksum_array(var))
+                .map(String::from)
+                .collect()),
+        }
+    }
+
+    pub async fn list_sources(&self, var: &str) -> Result<Vec<Source>> {
+        let sources = self.list_variable(var).await?;
+        Ok(sources.into_iter().map(parse_source).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destination() {
+        let source = Source::Url("https://example.com/foo-1.0.tar.gz".to_string());
+        assert_eq!(source.destination(), "foo-1.0.tar.gz");
+        let source = Source::Url("git+https://example.com/foo.git#tag=v1.0?signed".to_string());
+        assert_eq!(source.destination(), "foo");
+        let source = Source::UrlWithFilename((
+            "https://example.com/download?id=1".to_string(),
+            "foo.tar.gz".to_string(),
+        ));
+        assert_eq!(source.destination(), "foo.tar.gz");
+        let source = Source::Url("foo.patch".to_string());
+        assert_eq!(source.destination(), "foo.patch");
+    }
+
+    #[tokio::test]
+    async fn test_srcinfo_evaluator() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join(".SRCINFO"),
+            "pkgbase = foo
+	pkgver = 1.0
+	arch = x86_64
+	source = foo.patch::https://example.com/0001.patch
+	sha256sums = aaaa
+	b2sums_x86_64 = bbbb
+
+pkgname = foo
+",
+        )?;
+        let pkgbuild = Pkgbuild::load(dir.path(), Evaluator::Srcinfo, Sandbox::None).await?;
+        assert_eq!(pkgbuild.list_variable("pkgname").await?, &["foo"]);
+        assert_eq!(pkgbuild.list_variable("pkgver").await?, &["1.0"]);
+        assert_eq!(
+            pkgbuild.list_sources("source").await?,
+            &[Source::UrlWithFilename((
+                "https://example.com/0001.patch".to_string(),
+                "foo.patch".to_string()
+            ))]
+        );
+        assert_eq!(
+            pkgbuild.list_checksum_arrays().await?,
+            &["b2sums_x86_64", "sha256sums"]
+        );
+        assert!(!pkgbuild.has_function("pkgver").await?);
+        Ok(())
+    }
 }
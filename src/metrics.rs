@@ -0,0 +1,194 @@
+use crate::errors::*;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+const PREFIX: &str = "archlinux_inputs_fsck";
+
+pub static METRICS: Metrics = Metrics::new();
+
+/// Process wide counters, exposed in the prometheus text format with `--metrics-listen`
+pub struct Metrics {
+    started: OnceLock<Instant>,
+    packages_checked: AtomicU64,
+    package_errors: AtomicU64,
+    check_duration_micros: AtomicU64,
+    findings: Mutex<BTreeMap<&'static str, u64>>,
+    github_ratelimit_remaining: AtomicI64,
+}
+
+impl Metrics {
+    const fn new() -> Metrics {
+        Metrics {
+            started: OnceLock::new(),
+            packages_checked: AtomicU64::new(0),
+            package_errors: AtomicU64::new(0),
+            check_duration_micros: AtomicU64::new(0),
+            findings: Mutex::new(BTreeMap::new()),
+            github_ratelimit_remaining: AtomicI64::new(-1),
+        }
+    }
+
+    pub fn scan_started(&self) {
+        self.started.get_or_init(Instant::now);
+    }
+
+    pub fn package_checked(&self, duration: Duration, findings: &[&'static str]) {
+        self.packages_checked.fetch_add(1, Ordering::Relaxed);
+        self.check_duration_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+
+        let mut map = self.findings.lock().unwrap();
+        for kind in findings {
+            *map.entry(kind).or_default() += 1;
+        }
+    }
+
+    pub fn package_failed(&self, duration: Duration) {
+        self.packages_checked.fetch_add(1, Ordering::Relaxed);
+        self.package_errors.fetch_add(1, Ordering::Relaxed);
+        self.check_duration_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_github_ratelimit_remaining(&self, remaining: i64) {
+        self.github_ratelimit_remaining
+            .store(remaining, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, values: &[(String, String)]| {
+            writeln!(out, "# HELP {}_{} {}", PREFIX, name, help).ok();
+            writeln!(out, "# TYPE {}_{} {}", PREFIX, name, kind).ok();
+            for (labels, value) in values {
+                writeln!(out, "{}_{}{} {}", PREFIX, name, labels, value).ok();
+            }
+        };
+
+        let packages = self.packages_checked.load(Ordering::Relaxed);
+        let duration = self.check_duration_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let uptime = self
+            .started
+            .get()
+            .map(|started| started.elapsed().as_secs_f64())
+            .unwrap_or_default();
+
+        metric(
+            "packages_checked_total",
+            "counter",
+            "Number of packages that have been checked",
+            &[(String::new(), packages.to_string())],
+        );
+        metric(
+            "package_errors_total",
+            "counter",
+            "Number of packages that failed to be checked",
+            &[(
+                String::new(),
+                self.package_errors.load(Ordering::Relaxed).to_string(),
+            )],
+        );
+        metric(
+            "package_check_duration_seconds",
+            "summary",
+            "Time spent checking packages",
+            &[
+                ("_sum".to_string(), duration.to_string()),
+                ("_count".to_string(), packages.to_string()),
+            ],
+        );
+        metric(
+            "scan_duration_seconds",
+            "gauge",
+            "Time since the scan has been started",
+            &[(String::new(), uptime.to_string())],
+        );
+
+        let findings = self
+            .findings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(kind, count)| (format!("{{kind={:?}}}", kind), count.to_string()))
+            .collect::<Vec<_>>();
+        metric(
+            "findings_total",
+            "counter",
+            "Number of findings by issue type",
+            &findings,
+        );
+
+        let remaining = self.github_ratelimit_remaining.load(Ordering::Relaxed);
+        if remaining >= 0 {
+            metric(
+                "github_ratelimit_remaining",
+                "gauge",
+                "Remaining requests for the GitHub api, as reported by the last response",
+                &[(String::new(), remaining.to_string())],
+            );
+        }
+
+        out
+    }
+}
+
+async fn respond(stream: TcpStream) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+
+    let mut request = String::new();
+    stream.read_line(&mut request).await?;
+    // consume the remaining headers
+    let mut line = String::new();
+    while stream.read_line(&mut line).await? > 2 {
+        line.clear();
+    }
+
+    let path = request.split(' ').nth(1).unwrap_or_default();
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", METRICS.render())
+    } else {
+        ("404 Not Found", "Not found, try /metrics\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serve `/metrics` in the background for as long as the process is running
+pub async fn listen(addr: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| anyhow!("Failed to bind metrics endpoint: {:?}", addr))?;
+    info!("Serving metrics on http://{}/metrics", addr);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(err) = respond(stream).await {
+                            debug!("Failed to serve metrics request: {:#}", err);
+                        }
+                    });
+                }
+                Err(err) => warn!("Failed to accept metrics connection: {:#}", err),
+            }
+        }
+    });
+
+    Ok(())
+}
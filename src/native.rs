@@ -0,0 +1,1135 @@
+use crate::errors::*;
+use glob::Pattern;
+use std::collections::BTreeMap;
+
+/// The value of a variable, or the reason why it can't be known without executing the PKGBUILD
+type Value = std::result::Result<Vec<String>, String>;
+
+/// Top-level commands that start a compound command, everything up to the matching end is skipped
+const COMPOUND_COMMANDS: &[(&str, &str)] = &[
+    ("if", "fi"),
+    ("case", "esac"),
+    ("for", "done"),
+    ("while", "done"),
+    ("until", "done"),
+    ("select", "done"),
+    ("{", "}"),
+];
+
+const OPERATORS: &[&str] = &[
+    ";;&", "<<-", "<<<", ";;", "&&", "||", "<<", ">>", "<&", ">&", "&>", "|&", ";", "&", "|", "<",
+    ">", "(", ")",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+enum TokenKind {
+    Word(String),
+    Operator(&'static str),
+    Newline,
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl Token {
+    fn word(&self) -> Option<&str> {
+        match &self.kind {
+            TokenKind::Word(word) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn is_operator(&self, op: &str) -> bool {
+        matches!(self.kind, TokenKind::Operator(other) if other == op)
+    }
+
+    fn ends_command(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Newline | TokenKind::Operator(";" | "&" | ";;" | ";;&")
+        )
+    }
+}
+
+/// Split a PKGBUILD into words and operators, comments and here-documents are dropped
+struct Lexer<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    fn is_metachar(c: u8) -> bool {
+        matches!(
+            c,
+            b' ' | b'\t' | b'\r' | b'\n' | b';' | b'&' | b'|' | b'<' | b'>' | b'(' | b')'
+        )
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        let mut heredocs = Vec::<(String, bool)>::new();
+        let mut expect_heredoc = None;
+
+        while let Some(c) = self.peek() {
+            match c {
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b'\\' if self.peek_at(1) == Some(b'\n') => self.pos += 2,
+                b'\n' => {
+                    tokens.push(Token {
+                        kind: TokenKind::Newline,
+                        start: self.pos,
+                        end: self.pos + 1,
+                    });
+                    self.pos += 1;
+                    for (delimiter, strip_tabs) in heredocs.drain(..) {
+                        self.skip_heredoc(&delimiter, strip_tabs);
+                    }
+                }
+                b'#' => {
+                    while self.peek().is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                c if Self::is_metachar(c) => {
+                    let rest = &self.text[self.pos..];
+                    let op = OPERATORS
+                        .iter()
+                        .find(|op| rest.starts_with(**op))
+                        .context("Unknown operator")?;
+                    if *op == "<<" || *op == "<<-" {
+                        expect_heredoc = Some(*op == "<<-");
+                    }
+                    tokens.push(Token {
+                        kind: TokenKind::Operator(op),
+                        start: self.pos,
+                        end: self.pos + op.len(),
+                    });
+                    self.pos += op.len();
+                }
+                _ => {
+                    let start = self.pos;
+                    self.skip_word()?;
+                    let word = &self.text[start..self.pos];
+                    if let Some(strip_tabs) = expect_heredoc.take() {
+                        let delimiter = word.replace(['\'', '"', '\\'], "");
+                        heredocs.push((delimiter, strip_tabs));
+                    }
+                    tokens.push(Token {
+                        kind: TokenKind::Word(word.to_string()),
+                        start,
+                        end: self.pos,
+                    });
+                }
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn skip_heredoc(&mut self, delimiter: &str, strip_tabs: bool) {
+        while self.pos < self.bytes.len() {
+            let rest = &self.text[self.pos..];
+            let (line, len) = match rest.find('\n') {
+                Some(idx) => (&rest[..idx], idx + 1),
+                None => (rest, rest.len()),
+            };
+            self.pos += len;
+            let line = if strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if line == delimiter {
+                break;
+            }
+        }
+    }
+
+    fn skip_word(&mut self) -> Result<()> {
+        while let Some(c) = self.peek() {
+            match c {
+                c if Self::is_metachar(c) => break,
+                b'\\' => self.skip_escape(),
+                b'\'' => self.skip_single_quoted()?,
+                b'"' => self.skip_double_quoted()?,
+                b'`' => self.skip_backticks()?,
+                b'$' => self.skip_dollar()?,
+                _ => self.pos += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip a backslash and the character it escapes, which might not be ascii
+    fn skip_escape(&mut self) {
+        self.pos += 1;
+        if let Some(c) = self
+            .text
+            .get(self.pos..)
+            .and_then(|rest| rest.chars().next())
+        {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn skip_single_quoted(&mut self) -> Result<()> {
+        let rest = &self.text[self.pos + 1..];
+        let len = rest.find('\'').context("Unterminated single quote")?;
+        self.pos += len + 2;
+        Ok(())
+    }
+
+    fn skip_double_quoted(&mut self) -> Result<()> {
+        self.pos += 1;
+        loop {
+            match self.peek().context("Unterminated double quote")? {
+                b'\\' => self.skip_escape(),
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                b'`' => self.skip_backticks()?,
+                b'$' => self.skip_dollar()?,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_backticks(&mut self) -> Result<()> {
+        self.pos += 1;
+        loop {
+            match self.peek().context("Unterminated command substitution")? {
+                b'\\' => self.skip_escape(),
+                b'`' => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skip `$name`, `${...}` and `$(...)`, the braces and parentheses can be nested
+    fn skip_dollar(&mut self) -> Result<()> {
+        let (open, close) = match self.peek_at(1) {
+            Some(b'{') => (b'{', b'}'),
+            Some(b'(') => (b'(', b')'),
+            _ => {
+                self.pos += 1;
+                return Ok(());
+            }
+        };
+        self.pos += 2;
+        let mut depth = 1;
+        loop {
+            match self.peek().context("Unterminated expansion")? {
+                b'\\' => self.skip_escape(),
+                b'\'' => self.skip_single_quoted()?,
+                b'"' => self.skip_double_quoted()?,
+                b'`' => self.skip_backticks()?,
+                b'$' => self.skip_dollar()?,
+                c if c == open => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                c if c == close => {
+                    depth -= 1;
+                    self.pos += 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Split `name=value` and `name+=value`
+fn split_assignment(word: &str) -> Option<(&str, bool, &str)> {
+    let (name, value) = word.split_once('=')?;
+    let (name, append) = match name.strip_suffix('+') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    is_name(name).then_some((name, append, value))
+}
+
+/// The fields a word expands to, like the word splitting of bash
+#[derive(Default)]
+struct Fields {
+    fields: Vec<String>,
+    current: String,
+    has_current: bool,
+}
+
+impl Fields {
+    fn push_str(&mut self, s: &str) {
+        self.current.push_str(s);
+        self.has_current = true;
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.current.push(c);
+        self.has_current = true;
+    }
+
+    /// Each value becomes its own field, the first and last one are joined with the surrounding text
+    fn push_fields<I: IntoIterator<Item = String>>(&mut self, values: I) {
+        for (i, value) in values.into_iter().enumerate() {
+            if i > 0 {
+                self.fields.push(std::mem::take(&mut self.current));
+            }
+            self.push_str(&value);
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.has_current {
+            self.fields.push(self.current);
+        }
+        self.fields
+    }
+}
+
+fn char_boundaries(s: &str) -> Vec<usize> {
+    s.char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(s.len()))
+        .collect()
+}
+
+/// `${var#pattern}` and `${var##pattern}`
+fn remove_prefix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut bounds = char_boundaries(value);
+    if longest {
+        bounds.reverse();
+    }
+    for idx in bounds {
+        if pattern.matches(&value[..idx]) {
+            return value[idx..].to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// `${var%pattern}` and `${var%%pattern}`
+fn remove_suffix(value: &str, pattern: &Pattern, longest: bool) -> String {
+    let mut bounds = char_boundaries(value);
+    if !longest {
+        bounds.reverse();
+    }
+    for idx in bounds {
+        if pattern.matches(&value[idx..]) {
+            return value[..idx].to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// `${var/pattern/replacement}` and its variants, the longest match at the earliest position is replaced
+fn replace(
+    value: &str,
+    pattern: &Pattern,
+    replacement: &str,
+    all: bool,
+    anchor: Option<char>,
+) -> String {
+    let bounds = char_boundaries(value);
+    let mut out = String::new();
+    let mut pos = 0;
+    let mut i = 0;
+    while i < bounds.len() {
+        let start = bounds[i];
+        let matched = bounds[i + 1..]
+            .iter()
+            .rev()
+            .copied()
+            .filter(|end| anchor != Some('%') || *end == value.len())
+            .find(|end| pattern.matches(&value[start..*end]));
+        if let Some(end) = matched {
+            out.push_str(&value[pos..start]);
+            out.push_str(replacement);
+            pos = end;
+            if !all {
+                break;
+            }
+            i = bounds
+                .iter()
+                .position(|b| *b == end)
+                .unwrap_or(bounds.len());
+        } else {
+            i += 1;
+        }
+        if anchor == Some('#') {
+            break;
+        }
+    }
+    out.push_str(&value[pos..]);
+    out
+}
+
+/// `${var:offset}` and `${var:offset:length}`
+fn substring(value: &str, spec: &str) -> Result<String> {
+    let parse = |s: &str| -> Result<i64> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .unwrap_or(s);
+        s.trim()
+            .parse()
+            .with_context(|| anyhow!("Unsupported substring offset: {:?}", s))
+    };
+
+    let chars = value.chars().collect::<Vec<_>>();
+    let len = chars.len() as i64;
+    let (offset, length) = match spec.split_once(':') {
+        Some((offset, length)) => (parse(offset)?, Some(parse(length)?)),
+        None => (parse(spec)?, None),
+    };
+    let start = if offset < 0 { len + offset } else { offset }.clamp(0, len);
+    let end = match length {
+        Some(length) if length < 0 => len + length,
+        Some(length) => start.saturating_add(length),
+        None => len,
+    }
+    .clamp(start, len);
+    Ok(chars[start as usize..end as usize].iter().collect())
+}
+
+/// Expand words with the variables that have been assigned so far
+struct Expander<'a> {
+    variables: &'a BTreeMap<String, Value>,
+}
+
+impl Expander<'_> {
+    fn lookup(&self, name: &str) -> Result<Option<&[String]>> {
+        match self.variables.get(name) {
+            Some(Ok(values)) => Ok(Some(values)),
+            Some(Err(err)) => bail!("Variable {:?} can't be evaluated: {}", name, err),
+            None => Ok(None),
+        }
+    }
+
+    /// Expand a word like it's in double quotes, eg. the pattern of `${var#pattern}`
+    fn expand_string(&self, word: &str) -> Result<String> {
+        Ok(self.expand(word, false)?.join(" "))
+    }
+
+    /// Expand a word into fields, with `split` the results of unquoted expansions are split on whitespace
+    fn expand(&self, word: &str, split: bool) -> Result<Vec<String>> {
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut fields = Fields::default();
+        let mut in_double = false;
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '\\' => {
+                    match chars.get(i + 1) {
+                        Some('\n') => (),
+                        Some(c) if !in_double || matches!(c, '$' | '`' | '"' | '\\') => {
+                            fields.push_char(*c)
+                        }
+                        Some(c) => {
+                            fields.push_char('\\');
+                            fields.push_char(*c);
+                        }
+                        None => fields.push_char('\\'),
+                    }
+                    i += 2;
+                }
+                '\'' if !in_double => {
+                    let len = chars[i + 1..]
+                        .iter()
+                        .position(|c| *c == '\'')
+                        .context("Unterminated single quote")?;
+                    fields.push_str(&chars[i + 1..i + 1 + len].iter().collect::<String>());
+                    i += len + 2;
+                }
+                '"' => {
+                    in_double = !in_double;
+                    fields.has_current = true;
+                    i += 1;
+                }
+                '`' => bail!("Command substitution is not supported: {:?}", word),
+                '$' => i = self.expand_dollar(&chars, i, in_double, split, &mut fields)?,
+                c => {
+                    fields.push_char(c);
+                    i += 1;
+                }
+            }
+        }
+        if in_double {
+            bail!("Unterminated double quote: {:?}", word);
+        }
+        Ok(fields.finish())
+    }
+
+    fn expand_dollar(
+        &self,
+        chars: &[char],
+        i: usize,
+        quoted: bool,
+        split: bool,
+        fields: &mut Fields,
+    ) -> Result<usize> {
+        let (values, multi, next) = match chars.get(i + 1) {
+            Some('{') => {
+                let mut depth = 0;
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|c| {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => (),
+                        }
+                        depth == 0
+                    })
+                    .map(|len| i + 1 + len)
+                    .context("Unterminated parameter expansion")?;
+                let inner = chars[i + 2..close].iter().collect::<String>();
+                let (values, multi) = self.parameter(&inner)?;
+                (values, multi, close + 1)
+            }
+            Some('(') => bail!(
+                "Command substitution and arithmetic expansion are not supported: {:?}",
+                chars.iter().collect::<String>()
+            ),
+            Some('\'') => bail!("ANSI-C quoting is not supported"),
+            Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                let len = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                    .count();
+                let name = chars[i + 1..i + 1 + len].iter().collect::<String>();
+                let value = self
+                    .lookup(&name)?
+                    .and_then(|values| values.first())
+                    .cloned()
+                    .unwrap_or_default();
+                (vec![value], false, i + 1 + len)
+            }
+            // positional and special parameters are empty when sourcing the PKGBUILD
+            Some(c) if c.is_ascii_digit() || "@*#?$!-".contains(*c) => (Vec::new(), false, i + 2),
+            _ => {
+                fields.push_char('$');
+                return Ok(i + 1);
+            }
+        };
+
+        if quoted && multi {
+            fields.push_fields(values);
+        } else if quoted || !split {
+            fields.push_str(&values.join(" "));
+        } else {
+            fields.push_fields(
+                values
+                    .iter()
+                    .flat_map(|value| value.split_whitespace())
+                    .map(String::from)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        Ok(next)
+    }
+
+    /// Evaluate the inside of `${...}`, returns the values and if they are separate fields (`[@]`)
+    fn parameter(&self, inner: &str) -> Result<(Vec<String>, bool)> {
+        if inner.starts_with('!') {
+            bail!("Indirect expansion is not supported: ${{{}}}", inner);
+        }
+        let (length, rest) = match inner.strip_prefix('#') {
+            Some(rest) if !rest.is_empty() => (true, rest),
+            _ => (false, inner),
+        };
+
+        let name_len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_len);
+        if name.is_empty() {
+            bail!("Unsupported parameter expansion: ${{{}}}", inner);
+        }
+        let (subscript, op) = match rest.strip_prefix('[') {
+            Some(rest) => {
+                let (subscript, op) = rest
+                    .split_once(']')
+                    .with_context(|| anyhow!("Invalid array subscript: ${{{}}}", inner))?;
+                (Some(subscript), op)
+            }
+            None => (None, rest),
+        };
+
+        let set = self.lookup(name)?;
+        let (values, multi) = match subscript {
+            None => (
+                set.map(|v| vec![v.first().cloned().unwrap_or_default()]),
+                false,
+            ),
+            Some("@") => (set.map(<[String]>::to_vec), true),
+            Some("*") => (set.map(|v| vec![v.join(" ")]), false),
+            Some(idx) => {
+                let idx = idx
+                    .parse::<usize>()
+                    .with_context(|| anyhow!("Unsupported array subscript: ${{{}}}", inner))?;
+                (set.and_then(|v| v.get(idx)).map(|v| vec![v.clone()]), false)
+            }
+        };
+
+        if length {
+            if !op.is_empty() {
+                bail!("Unsupported parameter expansion: ${{{}}}", inner);
+            }
+            let len = if multi {
+                values.map(|v| v.len()).unwrap_or_default()
+            } else {
+                values
+                    .and_then(|v| v.first().map(|v| v.chars().count()))
+                    .unwrap_or_default()
+            };
+            return Ok((vec![len.to_string()], false));
+        }
+
+        let values = if op.is_empty() {
+            values.unwrap_or_default()
+        } else if let Some(word) = op.strip_prefix(":-") {
+            match values {
+                Some(values) if values.iter().any(|v| !v.is_empty()) => values,
+                _ => return Ok((vec![self.expand_string(word)?], false)),
+            }
+        } else if let Some(spec) = op
+            .strip_prefix(':')
+            .filter(|s| !s.starts_with(['=', '+', '?']))
+        {
+            values
+                .unwrap_or_default()
+                .iter()
+                .map(|v| substring(v, spec))
+                .collect::<Result<_>>()?
+        } else if let Some(word) = op.strip_prefix('-') {
+            match values {
+                Some(values) => values,
+                None => return Ok((vec![self.expand_string(word)?], false)),
+            }
+        } else {
+            let values = values.unwrap_or_default();
+            self.apply_operator(inner, op, values)?
+        };
+        Ok((values, multi))
+    }
+
+    fn apply_operator(&self, inner: &str, op: &str, values: Vec<String>) -> Result<Vec<String>> {
+        let pattern = |word: &str| -> Result<Pattern> {
+            let word = self.expand_string(word)?;
+            Pattern::new(&word).with_context(|| anyhow!("Invalid pattern: {:?}", word))
+        };
+
+        let values = if let Some(word) = op.strip_prefix("##") {
+            let pattern = pattern(word)?;
+            values
+                .iter()
+                .map(|v| remove_prefix(v, &pattern, true))
+                .collect()
+        } else if let Some(word) = op.strip_prefix('#') {
+            let pattern = pattern(word)?;
+            values
+                .iter()
+                .map(|v| remove_prefix(v, &pattern, false))
+                .collect()
+        } else if let Some(word) = op.strip_prefix("%%") {
+            let pattern = pattern(word)?;
+            values
+                .iter()
+                .map(|v| remove_suffix(v, &pattern, true))
+                .collect()
+        } else if let Some(word) = op.strip_prefix('%') {
+            let pattern = pattern(word)?;
+            values
+                .iter()
+                .map(|v| remove_suffix(v, &pattern, false))
+                .collect()
+        } else if let Some(rest) = op.strip_prefix('/') {
+            let (all, anchor, rest) = match rest.chars().next() {
+                Some('/') => (true, None, &rest[1..]),
+                Some(c @ ('#' | '%')) => (false, Some(c), &rest[1..]),
+                _ => (false, None, rest),
+            };
+            let (word, replacement) = rest.split_once('/').unwrap_or((rest, ""));
+            if word.is_empty() {
+                bail!("Unsupported parameter expansion: ${{{}}}", inner);
+            }
+            let pattern = pattern(word)?;
+            let replacement = self.expand_string(replacement)?;
+            values
+                .iter()
+                .map(|v| replace(v, &pattern, &replacement, all, anchor))
+                .collect()
+        } else if op == "^^" {
+            values.iter().map(|v| v.to_uppercase()).collect()
+        } else if op == ",," {
+            values.iter().map(|v| v.to_lowercase()).collect()
+        } else if op == "^" || op == "," {
+            values
+                .iter()
+                .map(|v| {
+                    let mut chars = v.chars();
+                    let first = chars.next().map(|c| {
+                        if op == "^" {
+                            c.to_uppercase().to_string()
+                        } else {
+                            c.to_lowercase().to_string()
+                        }
+                    });
+                    first.unwrap_or_default() + chars.as_str()
+                })
+                .collect()
+        } else {
+            bail!("Unsupported parameter expansion: ${{{}}}", inner);
+        };
+        Ok(values)
+    }
+}
+
+/// Expand `{a,b}` in a word, this happens before any other expansion
+fn brace_expand(word: &str) -> Vec<String> {
+    let bytes = word.as_bytes();
+    let mut i = 0;
+    let mut quote = None;
+    while i < bytes.len() {
+        match (bytes[i], quote) {
+            (b'\\', _) => i += 1,
+            (b'\'' | b'"', None) => quote = Some(bytes[i]),
+            (c, Some(q)) if c == q => quote = None,
+            (b'$', None) if bytes.get(i + 1) == Some(&b'{') => {
+                // skip over the parameter expansion
+                let mut depth = 0;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'{' => depth += 1,
+                        b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => (),
+                    }
+                    i += 1;
+                }
+            }
+            (b'{', None) => {
+                if let Some(alternatives) = brace_alternatives(word, i) {
+                    let (close, alternatives) = alternatives;
+                    let prefix = &word[..i];
+                    let suffix = &word[close + 1..];
+                    return alternatives
+                        .iter()
+                        .flat_map(|alt| brace_expand(&format!("{}{}{}", prefix, alt, suffix)))
+                        .collect();
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    vec![word.to_string()]
+}
+
+/// Find the closing brace and the comma separated alternatives of `{a,b}`
+fn brace_alternatives(word: &str, open: usize) -> Option<(usize, Vec<&str>)> {
+    let bytes = word.as_bytes();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut commas = Vec::new();
+    let mut i = open;
+    while i < bytes.len() {
+        match (bytes[i], quote) {
+            (b'\\', _) => i += 1,
+            (b'\'' | b'"', None) => quote = Some(bytes[i]),
+            (c, Some(q)) if c == q => quote = None,
+            (b'{', None) => depth += 1,
+            (b',', None) if depth == 1 => commas.push(i),
+            (b'}', None) => {
+                depth -= 1;
+                if depth == 0 {
+                    if commas.is_empty() {
+                        return None;
+                    }
+                    let mut alternatives = Vec::new();
+                    let mut start = open + 1;
+                    for comma in commas {
+                        alternatives.push(&word[start..comma]);
+                        start = comma + 1;
+                    }
+                    alternatives.push(&word[start..i]);
+                    return Some((i, alternatives));
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// A PKGBUILD that has been parsed without executing it
+///
+/// Only top-level assignments are evaluated. Conditionals, loops and other commands
+/// are skipped, variables that use command substitution or arithmetic can't be read.
+#[derive(Debug, Default)]
+pub struct Script {
+    variables: BTreeMap<String, Value>,
+    functions: BTreeMap<String, String>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self> {
+        let tokens = Lexer::new(text).tokenize()?;
+        let mut script = Script::default();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let token = &tokens[i];
+            if token.ends_command() {
+                i += 1;
+                continue;
+            }
+            let Some(word) = token.word() else {
+                i = skip_command(&tokens, i);
+                continue;
+            };
+
+            if let Some((name, append, value)) = split_assignment(word) {
+                let is_array = value.is_empty()
+                    && tokens
+                        .get(i + 1)
+                        .is_some_and(|t| t.is_operator("(") && t.start == token.end);
+                let value = if is_array {
+                    let close = (i + 2..tokens.len())
+                        .find(|idx| tokens[*idx].is_operator(")"))
+                        .with_context(|| anyhow!("Unterminated array: {:?}", name))?;
+                    let words = tokens[i + 2..close].iter().filter_map(Token::word);
+                    i = close + 1;
+                    script.expand_array(words)
+                } else {
+                    i += 1;
+                    script.expand_scalar(value).map(|value| vec![value])
+                };
+                script.assign(name, append, value);
+                continue;
+            }
+
+            if let Some((name, body)) = function_header(&tokens, i) {
+                let (body, next) = function_body(&tokens, body)
+                    .with_context(|| anyhow!("Failed to parse function {:?}", name))?;
+                script.functions.insert(name.to_string(), body);
+                i = next;
+                continue;
+            }
+
+            debug!("Skipping top-level command in PKGBUILD: {:?}", word);
+            i = skip_command(&tokens, i);
+        }
+
+        Ok(script)
+    }
+
+    fn expand_scalar(&self, word: &str) -> std::result::Result<String, String> {
+        let expander = Expander {
+            variables: &self.variables,
+        };
+        expander
+            .expand(word, false)
+            .map(|fields| fields.join(" "))
+            .map_err(|err| format!("{:#}", err))
+    }
+
+    fn expand_array<'a, I: Iterator<Item = &'a str>>(&self, words: I) -> Value {
+        let expander = Expander {
+            variables: &self.variables,
+        };
+        let mut values = Vec::new();
+        for word in words {
+            for word in brace_expand(word) {
+                values.extend(
+                    expander
+                        .expand(&word, true)
+                        .map_err(|err| format!("{:#}", err))?,
+                );
+            }
+        }
+        Ok(values)
+    }
+
+    fn assign(&mut self, name: &str, append: bool, value: Value) {
+        let previous = self.variables.remove(name);
+        let value = match (append, previous, value) {
+            (true, Some(Ok(mut previous)), Ok(value)) => {
+                previous.extend(value);
+                Ok(previous)
+            }
+            (true, Some(Err(err)), _) => Err(err),
+            (_, _, value) => value,
+        };
+        self.variables.insert(name.to_string(), value);
+    }
+
+    /// The values of a variable, a variable that isn't set is empty
+    pub fn variable(&self, name: &str) -> Result<Vec<String>> {
+        match self.variables.get(name) {
+            Some(Ok(values)) => Ok(values.clone()),
+            Some(Err(err)) => bail!(
+                "Variable {:?} can't be evaluated without executing the PKGBUILD: {}",
+                name,
+                err
+            ),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.variables.keys().map(String::as_str)
+    }
+
+    /// The body of a function, comments and here-documents are removed
+    pub fn function(&self, name: &str) -> Option<&str> {
+        self.functions.get(name).map(String::as_str)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
+}
+
+/// Detect `name() {` and `function name {`, returns the name and the index of the body
+fn function_header(tokens: &[Token], i: usize) -> Option<(&str, usize)> {
+    let has_parens = |idx: usize| {
+        tokens.get(idx).is_some_and(|t| t.is_operator("("))
+            && tokens.get(idx + 1).is_some_and(|t| t.is_operator(")"))
+    };
+
+    let word = tokens[i].word()?;
+    if word == "function" {
+        let name = tokens.get(i + 1)?.word()?;
+        let body = if has_parens(i + 2) { i + 4 } else { i + 2 };
+        Some((name, body))
+    } else if is_name(word) && has_parens(i + 1) {
+        Some((word, i + 3))
+    } else {
+        None
+    }
+}
+
+/// Read a function body in braces, returns the normalized body and the index after the function
+fn function_body(tokens: &[Token], mut i: usize) -> Result<(String, usize)> {
+    while tokens.get(i).is_some_and(|t| t.kind == TokenKind::Newline) {
+        i += 1;
+    }
+    if tokens.get(i).and_then(Token::word) != Some("{") {
+        bail!("Only function bodies in braces are supported");
+    }
+    let Some(end) = skip_compound(tokens, i, "{", "}") else {
+        bail!("Unterminated function body");
+    };
+
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    for token in &tokens[i + 1..end.saturating_sub(1)] {
+        match &token.kind {
+            TokenKind::Word(word) => line.push(word.as_str()),
+            TokenKind::Operator(op) => line.push(op),
+            TokenKind::Newline => {
+                if !line.is_empty() {
+                    lines.push(line.join(" "));
+                }
+                line.clear();
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line.join(" "));
+    }
+    Ok((lines.join("\n"), end))
+}
+
+/// Skip a compound command like `if ...; fi`, returns the index after the closing keyword
+/// or `None` if the compound command isn't terminated
+fn skip_compound(tokens: &[Token], i: usize, open: &str, close: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, token) in tokens.iter().enumerate().skip(i) {
+        match token.word() {
+            Some(word) if word == open => depth += 1,
+            Some(word) if word == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx + 1);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Skip a command up to the next newline or `;`, including any compound commands in it
+fn skip_command(tokens: &[Token], mut i: usize) -> usize {
+    let mut parens = 0;
+    while let Some(token) = tokens.get(i) {
+        if parens == 0 && token.ends_command() {
+            break;
+        }
+        match &token.kind {
+            TokenKind::Operator("(") => parens += 1,
+            TokenKind::Operator(")") => parens -= 1,
+            TokenKind::Word(word) => {
+                if let Some((open, close)) = COMPOUND_COMMANDS.iter().find(|(open, _)| open == word)
+                {
+                    i = skip_compound(tokens, i, open, close).unwrap_or(tokens.len());
+                    continue;
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pkgbuild() -> Result<()> {
+        let script = Script::parse(
+            r#"# Maintainer: Foo <foo@example.com>
+pkgbase=foo
+pkgname=('foo' "foo-docs")
+pkgver=1.2.3
+pkgrel=1
+_commit=0123456789abcdef0123456789abcdef01234567
+_tag="v${pkgver//./_}"
+arch=(x86_64)
+source=("https://example.com/$pkgbase-$pkgver.tar.gz"{,.sig}
+        "git+https://example.com/foo.git#commit=${_commit}"
+        "${pkgname[1]}-${_commit:0:7}.patch::https://example.com/p/${_tag#v}" # comment
+        fix.patch)
+sha256sums=('aaaa'
+            'SKIP'
+            'SKIP' 'bbbb')
+validpgpkeys=(ABCDEF) # Foo
+if [[ $CARCH == x86_64 ]]; then
+  source+=(x86.patch)
+fi
+[[ -n $DEBUG ]] && arch+=(any)
+depends+=(glibc)
+pkgdesc=$(cat description)
+
+pkgver() {
+  cd foo
+  # git describe can't be used here
+  cat <<EOF
+don't break
+EOF
+  git describe --tags | sed 's/^v//'
+}
+
+function package_foo {
+  make DESTDIR="$pkgdir" install
+}
+"#,
+        )?;
+
+        assert_eq!(script.variable("pkgname")?, &["foo", "foo-docs"]);
+        assert_eq!(script.variable("_tag")?, &["v1_2_3"]);
+        assert_eq!(
+            script.variable("source")?,
+            &[
+                "https://example.com/foo-1.2.3.tar.gz",
+                "https://example.com/foo-1.2.3.tar.gz.sig",
+                "git+https://example.com/foo.git#commit=0123456789abcdef0123456789abcdef01234567",
+                "foo-docs-0123456.patch::https://example.com/p/1_2_3",
+                "fix.patch",
+            ]
+        );
+        assert_eq!(
+            script.variable("sha256sums")?,
+            &["aaaa", "SKIP", "SKIP", "bbbb"]
+        );
+        assert_eq!(script.variable("validpgpkeys")?, &["ABCDEF"]);
+        assert_eq!(script.variable("arch")?, &["x86_64"]);
+        assert_eq!(script.variable("depends")?, &["glibc"]);
+        assert!(script.variable("unset")?.is_empty());
+        assert!(script.variable("pkgdesc").is_err());
+
+        assert_eq!(
+            script.functions().collect::<Vec<_>>(),
+            &["package_foo", "pkgver"]
+        );
+        assert_eq!(
+            script.function("pkgver"),
+            Some("cd foo\ncat << EOF\ngit describe --tags | sed 's/^v//'")
+        );
+        assert_eq!(
+            script.function("package_foo"),
+            Some("make DESTDIR=\"$pkgdir\" install")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_expansion() -> Result<()> {
+        let script = Script::parse(
+            r#"pkgname=python-foo-git
+_name=${pkgname#python-}
+_name=${_name%-git}
+_upper=${_name^^}
+_url=${URL:-https://example.com}
+_file=foo.tar.gz
+_base=${_file%%.*}
+_tail=${_file: -2}
+"#,
+        )?;
+        assert_eq!(script.variable("_name")?, &["foo"]);
+        assert_eq!(script.variable("_upper")?, &["FOO"]);
+        assert_eq!(script.variable("_url")?, &["https://example.com"]);
+        assert_eq!(script.variable("_base")?, &["foo"]);
+        assert_eq!(script.variable("_tail")?, &["gz"]);
+
+        let script = Script::parse("_file=foo.tar.gz\n_head=${_file:0:9223372036854775807}\n")?;
+        assert_eq!(script.variable("_head")?, &["foo.tar.gz"]);
+        assert!(Script::parse("pkgname=foo\nbuild() {").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::errors::*;
+use crate::fsck::Checksum;
+use crate::golang::hex;
+use base64::Engine;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TarballUrl {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Version {
+    pub dist: Dist,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Dist {
+    pub integrity: Option<String>,
+    pub shasum: Option<String>,
+}
+
+impl Dist {
+    /// The digest published by the registry for the algorithm of this checksum, if any
+    pub fn get(&self, checksum: &Checksum) -> Option<String> {
+        match checksum {
+            Checksum::Sha512(_) => {
+                let integrity = self.integrity.as_deref()?.strip_prefix("sha512-")?;
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(integrity)
+                    .ok()?;
+                Some(hex(&bytes))
+            }
+            Checksum::Sha1(_) => self.shasum.clone(),
+            _ => None,
+        }
+    }
+}
+
+pub fn detect_tarball_from_url(url: &str) -> Result<Option<TarballUrl>> {
+    let re = Regex::new(
+        r"^https://registry.(?:npmjs.org|yarnpkg.com)/((?:@[^/]+/)?([^/@]+))/-/([^/]+)\.tgz$",
+    )?;
+    if let Some(caps) = re.captures(url) {
+        let name = &caps[1];
+        let basename = &caps[2];
+        let filename = &caps[3];
+
+        if let Some(version) = filename
+            .strip_prefix(basename)
+            .and_then(|v| v.strip_prefix('-'))
+        {
+            return Ok(Some(TarballUrl {
+                name: name.to_string(),
+                version: version.to_string(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+pub async fn fetch_version(client: &Client, name: &str, version: &str) -> Result<Option<Version>> {
+    let url = format!("https://registry.npmjs.org/{}/{}", name, version);
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let json = response.error_for_status()?.json().await?;
+
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_url_matching() -> Result<()> {
+        let x = detect_tarball_from_url("https://registry.npmjs.org/yarn/-/yarn-1.22.19.tgz")?;
+        assert_eq!(
+            x,
+            Some(TarballUrl {
+                name: "yarn".to_string(),
+                version: "1.22.19".to_string(),
+            })
+        );
+
+        let x = detect_tarball_from_url("https://registry.npmjs.org/@vue/cli/-/cli-5.0.8.tgz")?;
+        assert_eq!(
+            x,
+            Some(TarballUrl {
+                name: "@vue/cli".to_string(),
+                version: "5.0.8".to_string(),
+            })
+        );
+
+        Ok(())
+    }
+}
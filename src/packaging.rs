@@ -0,0 +1,176 @@
+use crate::errors::*;
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub const GITLAB_URL: &str = "https://gitlab.archlinux.org";
+pub const PACKAGES_GROUP: &str = "archlinux/packaging/packages";
+
+#[derive(Debug, Deserialize)]
+struct Project {
+    default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Map a pkgbase to the name of its gitlab project, this follows `gitlab_project_name_to_path` in devtools
+pub fn project_name(pkgbase: &str) -> Result<String> {
+    let name = Regex::new(r"([a-zA-Z0-9]+)\+([a-zA-Z]+)")?.replace_all(pkgbase, "${1}-${2}");
+    let name = name.replace('+', "plus");
+    let name = Regex::new(r"[^a-zA-Z0-9_\-.]")?.replace_all(&name, "-");
+    let name = Regex::new(r"[_\-]{2,}")?.replace_all(&name, "-");
+    if name == "tree" {
+        Ok("unix-tree".to_string())
+    } else {
+        Ok(name.into_owned())
+    }
+}
+
+/// Release tags use the package version with the epoch separator replaced, eg. `1:2.3-1` -> `1-2.3-1`
+pub fn version_ref(version: &str) -> String {
+    version.replace(':', "-")
+}
+
+/// Percent-encode everything except the unreserved characters of RFC 3986
+pub(crate) fn encode(s: &str) -> String {
+    let mut out = String::new();
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// Only write files into the checkout, never outside of it
+fn is_safe_path(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(client: &Client, url: &str) -> Result<T> {
+    info!("Url={}", url);
+    let json = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(json)
+}
+
+async fn list_files(client: &Client, project: &str, git_ref: &str) -> Result<Vec<String>> {
+    const PER_PAGE: usize = 100;
+
+    let mut files = Vec::new();
+    for page in 1.. {
+        let url = format!(
+            "{}/api/v4/projects/{}/repository/tree?ref={}&recursive=true&per_page={}&page={}",
+            GITLAB_URL,
+            encode(project),
+            encode(git_ref),
+            PER_PAGE,
+            page
+        );
+        let entries = get_json::<Vec<TreeEntry>>(client, &url).await?;
+        let done = entries.len() < PER_PAGE;
+        files.extend(
+            entries
+                .into_iter()
+                .filter(|entry| entry.kind == "blob")
+                .map(|entry| entry.path),
+        );
+        if done {
+            break;
+        }
+    }
+    Ok(files)
+}
+
+/// Download the packaging repository of an official package over https, without git or devtools
+pub async fn checkout_package(
+    client: &Client,
+    directory: &Path,
+    pkgbase: &str,
+    version: Option<&str>,
+) -> Result<PathBuf> {
+    let project = format!("{}/{}", PACKAGES_GROUP, project_name(pkgbase)?);
+    let git_ref = if let Some(version) = version {
+        version_ref(version)
+    } else {
+        let url = format!("{}/api/v4/projects/{}", GITLAB_URL, encode(&project));
+        get_json::<Project>(client, &url).await?.default_branch
+    };
+
+    debug!(
+        "Downloading {:?} at {:?} to {:?}",
+        project, git_ref, directory
+    );
+    let path = directory.join(pkgbase);
+    fs::create_dir(&path)?;
+    for file in list_files(client, &project, &git_ref).await? {
+        if !is_safe_path(&file) {
+            bail!("Refusing to write file outside of checkout: {:?}", file);
+        }
+
+        let url = format!(
+            "{}/api/v4/projects/{}/repository/files/{}/raw?ref={}",
+            GITLAB_URL,
+            encode(&project),
+            encode(&file),
+            encode(&git_ref)
+        );
+        info!("Url={}", url);
+        let bytes = client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        let dest = path.join(&file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&dest, &bytes).with_context(|| anyhow!("Failed to write file: {:?}", dest))?;
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_name() -> Result<()> {
+        assert_eq!(project_name("linux")?, "linux");
+        assert_eq!(project_name("libc++")?, "libcplusplus");
+        assert_eq!(project_name("dvd+rw-tools")?, "dvd-rw-tools");
+        assert_eq!(project_name("tree")?, "unix-tree");
+        assert_eq!(project_name("python-foo__bar")?, "python-foo-bar");
+        assert_eq!(project_name("foo@bar")?, "foo-bar");
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_path() {
+        assert!(is_safe_path("PKGBUILD"));
+        assert!(is_safe_path("keys/pgp/ABCD.asc"));
+        assert!(!is_safe_path("../PKGBUILD"));
+        assert!(!is_safe_path("/etc/passwd"));
+        assert!(!is_safe_path("keys/../../x"));
+    }
+}
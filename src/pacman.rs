@@ -0,0 +1,166 @@
+use crate::errors::*;
+use flate2::read::GzDecoder;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+pub const SYNC_DIR: &str = "/var/lib/pacman/sync";
+
+/// A package entry of a sync database
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Package {
+    pub name: String,
+    pub base: String,
+    pub depends: Vec<String>,
+    pub makedepends: Vec<String>,
+    pub checkdepends: Vec<String>,
+    pub provides: Vec<String>,
+}
+
+impl Package {
+    /// Parse the `desc` file of a package, the pkgbase falls back to the pkgname
+    pub fn parse_desc(desc: &str) -> Option<Package> {
+        let mut pkg = Package::default();
+        let mut section = None;
+        for line in desc.lines() {
+            if line.is_empty() {
+                section = None;
+            } else if line.starts_with('%') && line.ends_with('%') {
+                section = Some(line);
+            } else {
+                let value = line.to_string();
+                match section {
+                    Some("%NAME%") => pkg.name = value,
+                    Some("%BASE%") => pkg.base = value,
+                    Some("%DEPENDS%") => pkg.depends.push(value),
+                    Some("%MAKEDEPENDS%") => pkg.makedepends.push(value),
+                    Some("%CHECKDEPENDS%") => pkg.checkdepends.push(value),
+                    Some("%PROVIDES%") => pkg.provides.push(value),
+                    _ => (),
+                }
+            }
+        }
+
+        if pkg.name.is_empty() {
+            return None;
+        }
+        if pkg.base.is_empty() {
+            pkg.base = pkg.name.clone();
+        }
+        Some(pkg)
+    }
+}
+
+/// Strip the version requirement from a dependency, eg. `glibc>=2.37` -> `glibc`
+pub fn dependency_name(dep: &str) -> &str {
+    dep.split(['<', '>', '=']).next().unwrap_or(dep)
+}
+
+/// Read all packages in a sync database, eg. `core.db`
+pub fn read_sync_db(bytes: &[u8]) -> Result<Vec<Package>> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        bail!("Unsupported compression, only gzip compressed databases are supported");
+    }
+
+    let mut pkgs = Vec::new();
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.file_name() != Some("desc".as_ref()) {
+            continue;
+        }
+
+        let mut desc = String::new();
+        entry.read_to_string(&mut desc)?;
+        pkgs.extend(Package::parse_desc(&desc));
+    }
+
+    Ok(pkgs)
+}
+
+/// Read the packages of a sync database, or of all `*.db` files if this is a directory
+pub fn read_packages(path: &Path) -> Result<Vec<Package>> {
+    let mut files = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.extension() == Some("db".as_ref()) {
+                files.push(path);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut pkgs = Vec::new();
+    for file in files {
+        debug!("Reading sync database: {:?}", file);
+        let bytes =
+            fs::read(&file).with_context(|| anyhow!("Failed to read sync database: {:?}", file))?;
+        let db = read_sync_db(&bytes)
+            .with_context(|| anyhow!("Failed to parse sync database: {:?}", file))?;
+        pkgs.extend(db);
+    }
+    Ok(pkgs)
+}
+
+/// Read the pkgbases of a sync database, or of all `*.db` files if this is a directory
+pub fn list_pkgbases(path: &Path) -> Result<BTreeSet<String>> {
+    let pkgs = read_packages(path)?;
+    Ok(pkgs.into_iter().map(|pkg| pkg.base).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn add_file(builder: &mut tar::Builder<GzEncoder<Vec<u8>>>, path: &str, data: &str) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, data.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_read_sync_db() -> Result<()> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        add_file(
+            &mut builder,
+            "linux-6.1.1.arch1-1/desc",
+            "%FILENAME%\nlinux-6.1.1.arch1-1-x86_64.pkg.tar.zst\n\n%NAME%\nlinux\n\n%BASE%\nlinux\n",
+        );
+        add_file(
+            &mut builder,
+            "linux-headers-6.1.1.arch1-1/desc",
+            "%NAME%\nlinux-headers\n\n%BASE%\nlinux\n\n%DEPENDS%\npahole\nglibc>=2.37\n\n%PROVIDES%\nlinux-api-headers=6.1\n",
+        );
+        add_file(&mut builder, "zlib-1:1.2.13-1/desc", "%NAME%\nzlib\n");
+        add_file(&mut builder, "zlib-1:1.2.13-1/files", "%FILES%\nusr/\n");
+        let bytes = builder.into_inner()?.finish()?;
+
+        let pkgs = read_sync_db(&bytes)?;
+        assert_eq!(
+            pkgs.iter().map(|pkg| pkg.base.as_str()).collect::<Vec<_>>(),
+            &["linux", "linux", "zlib"]
+        );
+        assert_eq!(
+            pkgs[1],
+            Package {
+                name: "linux-headers".to_string(),
+                base: "linux".to_string(),
+                depends: vec!["pahole".to_string(), "glibc>=2.37".to_string()],
+                provides: vec!["linux-api-headers=6.1".to_string()],
+                ..Default::default()
+            }
+        );
+        assert_eq!(dependency_name(&pkgs[1].depends[1]), "glibc");
+        Ok(())
+    }
+}
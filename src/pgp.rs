@@ -0,0 +1,458 @@
+use crate::errors::*;
+use crate::packaging;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::SystemTime;
+use strum::{Display, IntoStaticStr};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+pub const DEFAULT_KEYSERVER: &str = "https://keys.openpgp.org";
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum FingerprintIssue {
+    /// A short (8 or 16 characters) key id instead of a full fingerprint
+    ShortKeyId,
+    /// A fingerprint with whitespace or a `0x` prefix
+    Formatting,
+    /// Not a hex string
+    InvalidHex,
+    /// A hex string that is neither a key id nor a fingerprint
+    WrongLength,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyStatus {
+    Valid,
+    Expired,
+    Revoked,
+    NotFound,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, IntoStaticStr, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "kebab_case")]
+#[serde(rename_all = "kebab-case")]
+pub enum SignatureIssue {
+    /// The tag is lightweight or has no signature attached
+    Unsigned,
+    /// The signature doesn't verify
+    BadSignature,
+    /// The signature was made by a key that isn't listed in `validpgpkeys=`
+    UnexpectedKey,
+}
+
+/// The details gpg reports about a public key
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KeyInfo {
+    pub status: KeyStatus,
+    pub fingerprints: Vec<String>,
+    pub emails: Vec<String>,
+}
+
+/// Download a key by fingerprint from a keyserver with the hkp protocol
+pub async fn fetch_key(
+    client: &Client,
+    keyserver: &str,
+    fingerprint: &str,
+) -> Result<Option<Vec<u8>>> {
+    let url = format!(
+        "{}/pks/lookup?op=get&options=mr&search=0x{}",
+        keyserver.trim_end_matches('/'),
+        fingerprint
+    );
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let key = response.error_for_status()?.bytes().await?;
+    if !String::from_utf8_lossy(&key).contains("-----BEGIN PGP PUBLIC KEY BLOCK-----") {
+        return Ok(None);
+    }
+
+    Ok(Some(key.to_vec()))
+}
+
+/// Inspect a key (armored or binary) with gpg, without importing it into any keyring
+pub async fn inspect_key(key: &[u8]) -> Result<KeyInfo> {
+    let home = tempfile::tempdir()?;
+    let mut child = Command::new("gpg")
+        .arg("--homedir")
+        .arg(home.path())
+        .args([
+            "--batch",
+            "--show-keys",
+            "--with-colons",
+            "--fixed-list-mode",
+        ])
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to run gpg")?;
+
+    let mut stdin = child.stdin.take().context("Failed to open gpg stdin")?;
+    stdin.write_all(key).await?;
+    drop(stdin);
+
+    let out = child.wait_with_output().await?;
+    if !out.status.success() {
+        bail!("gpg exited with error: {:?}", out.status);
+    }
+    let out = String::from_utf8(out.stdout).context("gpg output contains invalid utf8")?;
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    let mut status = None;
+    let mut fingerprints = Vec::new();
+    let mut emails = Vec::new();
+    for line in out.lines() {
+        let fields = line.split(':').collect::<Vec<_>>();
+        match fields.first() {
+            Some(&"pub") if status.is_none() => {
+                let expires = fields.get(6).and_then(|x| x.parse::<u64>().ok());
+                status = Some(match fields.get(1) {
+                    Some(&"r") => KeyStatus::Revoked,
+                    Some(&"e") => KeyStatus::Expired,
+                    _ if expires.is_some_and(|expires| expires < now) => KeyStatus::Expired,
+                    _ => KeyStatus::Valid,
+                });
+            }
+            Some(&"fpr") => {
+                if let Some(fpr) = fields.get(9) {
+                    fingerprints.push(fpr.to_string());
+                }
+            }
+            Some(&"uid") => {
+                if let Some(email) = fields
+                    .get(9)
+                    .and_then(|uid| uid.rsplit_once('<'))
+                    .and_then(|(_, email)| email.strip_suffix('>'))
+                {
+                    emails.push(email.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(KeyInfo {
+        status: status.context("gpg didn't output a public key")?,
+        fingerprints,
+        emails,
+    })
+}
+
+/// Download a fingerprint from the configured keyservers, the first keyserver that knows the key wins
+///
+/// Keyservers that can't be reached are skipped, this only fails if every keyserver failed.
+pub async fn find_key(
+    client: &Client,
+    keyservers: &[String],
+    fingerprint: &str,
+) -> Result<Option<Vec<u8>>> {
+    let mut errors = Vec::new();
+    for keyserver in keyservers {
+        match fetch_key(client, keyserver, fingerprint).await {
+            Ok(Some(key)) => return Ok(Some(key)),
+            Ok(None) => (),
+            Err(err) => {
+                warn!("Failed to lookup key on {:?}: {:#}", keyserver, err);
+                errors.push(err);
+            }
+        }
+    }
+
+    // only fail if no keyserver could answer at all
+    if errors.len() == keyservers.len() {
+        if let Some(err) = errors.pop() {
+            return Err(err.context("Failed to lookup key on any keyserver"));
+        }
+    }
+    Ok(None)
+}
+
+/// Lookup a fingerprint on the configured keyservers
+pub async fn lookup_key(
+    client: &Client,
+    keyservers: &[String],
+    fingerprint: &str,
+) -> Result<Option<KeyInfo>> {
+    if let Some(key) = find_key(client, keyservers, fingerprint).await? {
+        inspect_key(&key).await.map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// A temporary gpg home directory that only contains the keys imported into it
+pub struct Keyring {
+    home: tempfile::TempDir,
+}
+
+impl Keyring {
+    pub fn new() -> Result<Self> {
+        let home = tempfile::tempdir()?;
+        Ok(Keyring { home })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.home.path()
+    }
+
+    pub async fn import(&self, key: &[u8]) -> Result<()> {
+        let mut child = Command::new("gpg")
+            .arg("--homedir")
+            .arg(self.path())
+            .args(["--batch", "--quiet", "--import"])
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("Failed to run gpg")?;
+
+        let mut stdin = child.stdin.take().context("Failed to open gpg stdin")?;
+        stdin.write_all(key).await?;
+        drop(stdin);
+
+        let status = child.wait().await?;
+        if !status.success() {
+            bail!("gpg exited with error: {:?}", status);
+        }
+        Ok(())
+    }
+}
+
+/// Evaluate the `[GNUPG:]` status lines of a signature verification against `validpgpkeys=`
+///
+/// Returns the issue and, if known, the key that made the signature.
+pub fn check_signature_status(
+    status: &str,
+    validpgpkeys: &[String],
+) -> Option<(SignatureIssue, Option<String>)> {
+    let mut signer = None;
+    for line in status.lines() {
+        let Some(line) = line.strip_prefix("[GNUPG:] ") else {
+            continue;
+        };
+        let fields = line.split(' ').collect::<Vec<_>>();
+        match fields.as_slice() {
+            ["BADSIG", key, ..] => {
+                return Some((SignatureIssue::BadSignature, Some(key.to_string())));
+            }
+            ["ERRSIG", key, ..] | ["NO_PUBKEY", key, ..] => {
+                signer = Some((SignatureIssue::UnexpectedKey, Some(key.to_string())));
+            }
+            ["VALIDSIG", fields @ ..] => {
+                // the fingerprint of the primary key is the last field, the first one might be a subkey
+                let Some(primary) = fields.get(9).or_else(|| fields.first()) else {
+                    continue;
+                };
+                if validpgpkeys
+                    .iter()
+                    .any(|key| key.eq_ignore_ascii_case(primary))
+                {
+                    return None;
+                }
+                signer = Some((SignatureIssue::UnexpectedKey, Some(primary.to_string())));
+            }
+            _ => (),
+        }
+    }
+    Some(signer.unwrap_or((SignatureIssue::Unsigned, None)))
+}
+
+fn zbase32(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+    let mut out = String::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+/// The urls to query for an email address with the advanced and the direct method of the Web Key Directory
+pub fn wkd_urls(email: &str) -> Option<[String; 2]> {
+    let (local, domain) = email.rsplit_once('@')?;
+    let domain = domain.to_lowercase();
+    let hash = zbase32(&Sha1::digest(local.to_lowercase().as_bytes()));
+    let local = packaging::encode(local);
+    Some([
+        format!(
+            "https://openpgpkey.{}/.well-known/openpgpkey/{}/hu/{}?l={}",
+            domain, domain, hash, local
+        ),
+        format!(
+            "https://{}/.well-known/openpgpkey/hu/{}?l={}",
+            domain, hash, local
+        ),
+    ])
+}
+
+/// Check if the key with this fingerprint can be discovered with the Web Key Directory of an email address
+pub async fn wkd_has_key(client: &Client, email: &str, fingerprint: &str) -> Result<bool> {
+    let Some(urls) = wkd_urls(email) else {
+        return Ok(false);
+    };
+
+    for url in urls {
+        info!("Url={}", url);
+        let key = match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response.bytes().await?,
+            Ok(_) => continue,
+            Err(err) => {
+                debug!("Failed to query wkd {:?}: {:#}", url, err);
+                continue;
+            }
+        };
+
+        if let Ok(info) = inspect_key(&key).await {
+            if info
+                .fingerprints
+                .iter()
+                .any(|fpr| fpr.eq_ignore_ascii_case(fingerprint))
+            {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+/// Check that a `validpgpkeys=` entry is a full fingerprint, like makepkg expects it
+pub fn validate_fingerprint(key: &str) -> Result<(), FingerprintIssue> {
+    let stripped = key
+        .strip_prefix("0x")
+        .or_else(|| key.strip_prefix("0X"))
+        .unwrap_or(key)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    if stripped.is_empty() || !stripped.chars().all(|c| c.is_ascii_hexdigit()) {
+        Err(FingerprintIssue::InvalidHex)
+    } else if stripped.len() == 8 || stripped.len() == 16 {
+        Err(FingerprintIssue::ShortKeyId)
+    } else if stripped.len() != 40 && stripped.len() != 64 {
+        Err(FingerprintIssue::WrongLength)
+    } else if stripped != key {
+        Err(FingerprintIssue::Formatting)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wkd_urls() {
+        let urls = wkd_urls("Joe.Doe@Example.ORG").unwrap();
+        assert_eq!(
+            urls,
+            [
+                "https://openpgpkey.example.org/.well-known/openpgpkey/example.org/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe".to_string(),
+                "https://example.org/.well-known/openpgpkey/hu/iy9q119eutrkn8s1mk4r39qejnbu3n5q?l=Joe.Doe".to_string(),
+            ]
+        );
+
+        let urls = wkd_urls("foo+bar@example.org").unwrap();
+        assert!(urls[1].ends_with("?l=foo%2Bbar"), "{}", urls[1]);
+    }
+
+    #[test]
+    fn test_check_signature_status() {
+        let validpgpkeys = ["64B13F7117D6E07D661BBCE0FE763A64F5E54FD6".to_string()];
+        let status = "[GNUPG:] NEWSIG
+[GNUPG:] KEY_CONSIDERED 64B13F7117D6E07D661BBCE0FE763A64F5E54FD6 0
+[GNUPG:] GOODSIG FE763A64F5E54FD6 kpcyrd <git@rxv.cc>
+[GNUPG:] VALIDSIG 0A79CB1D4D8AB3EF2D39EC9F8D7C3A1C6C5EF5BB 2022-10-01 1664582400 0 4 0 22 10 00 64B13F7117D6E07D661BBCE0FE763A64F5E54FD6
+";
+        assert_eq!(check_signature_status(status, &validpgpkeys), None);
+
+        let status = "[GNUPG:] NEWSIG
+[GNUPG:] ERRSIG 8D7C3A1C6C5EF5BB 22 10 00 1664582400 9 -
+[GNUPG:] NO_PUBKEY 8D7C3A1C6C5EF5BB
+";
+        assert_eq!(
+            check_signature_status(status, &validpgpkeys),
+            Some((
+                SignatureIssue::UnexpectedKey,
+                Some("8D7C3A1C6C5EF5BB".to_string())
+            ))
+        );
+
+        let status = "[GNUPG:] NEWSIG
+[GNUPG:] BADSIG FE763A64F5E54FD6 kpcyrd <git@rxv.cc>
+";
+        assert_eq!(
+            check_signature_status(status, &validpgpkeys),
+            Some((
+                SignatureIssue::BadSignature,
+                Some("FE763A64F5E54FD6".to_string())
+            ))
+        );
+
+        assert_eq!(
+            check_signature_status("", &validpgpkeys),
+            Some((SignatureIssue::Unsigned, None))
+        );
+    }
+
+    #[test]
+    fn test_validate_fingerprint() {
+        assert_eq!(
+            validate_fingerprint("64B13F7117D6E07D661BBCE0FE763A64F5E54FD6"),
+            Ok(())
+        );
+        assert_eq!(
+            validate_fingerprint("0x64B13F7117D6E07D661BBCE0FE763A64F5E54FD6"),
+            Err(FingerprintIssue::Formatting)
+        );
+        assert_eq!(
+            validate_fingerprint("64B1 3F71 17D6 E07D 661B  BCE0 FE76 3A64 F5E5 4FD6"),
+            Err(FingerprintIssue::Formatting)
+        );
+        assert_eq!(
+            validate_fingerprint("FE763A64F5E54FD6"),
+            Err(FingerprintIssue::ShortKeyId)
+        );
+        assert_eq!(
+            validate_fingerprint("F5E54FD6"),
+            Err(FingerprintIssue::ShortKeyId)
+        );
+        assert_eq!(
+            validate_fingerprint("kpcyrd <git@rxv.cc>"),
+            Err(FingerprintIssue::InvalidHex)
+        );
+        assert_eq!(
+            validate_fingerprint("64B13F7117D6E07D661BBCE0FE763A64F5E54F"),
+            Err(FingerprintIssue::WrongLength)
+        );
+    }
+}
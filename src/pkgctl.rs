@@ -0,0 +1,48 @@
+use crate::errors::*;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+pub async fn checkout_package(
+    directory: &Path,
+    pkgbase: &str,
+    version: Option<&str>,
+) -> Result<PathBuf> {
+    if pkgbase.starts_with('-') {
+        bail!("Invalid package name: {:?}", pkgbase);
+    }
+
+    debug!("Cloning {:?} to {:?} with pkgctl", pkgbase, directory);
+    let mut args = vec![
+        "repo".to_string(),
+        "clone".to_string(),
+        "--protocol=https".to_string(),
+    ];
+    if let Some(version) = version {
+        args.push(format!("--switch={}", version));
+    }
+    args.push(pkgbase.to_string());
+
+    let cmd = Command::new("pkgctl")
+        .args(&args)
+        .kill_on_drop(true)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        // TODO: find a better way to make it silent without discarding stderr
+        .stderr(Stdio::null())
+        .current_dir(directory)
+        .spawn()
+        .with_context(|| anyhow!("Failed to run pkgctl repo clone {:?}", pkgbase))?;
+
+    let out = cmd.wait_with_output().await?;
+    if !out.status.success() {
+        bail!(
+            "Process (pkgctl repo clone {:?}) exited with error: {:?}",
+            pkgbase,
+            out.status
+        );
+    }
+
+    // the packaging repositories on gitlab have the PKGBUILD at the top
+    Ok(directory.join(pkgbase))
+}
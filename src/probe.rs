@@ -0,0 +1,149 @@
+use crate::errors::*;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::{Client, Method, StatusCode};
+use std::time::Duration;
+
+const AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Availability {
+    Available { size: Option<u64> },
+    Status(u16),
+    Error(String),
+}
+
+/// The https equivalent of an url with an unauthenticated scheme, if there is one
+pub fn https_upgrade(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    // the git protocol, eg. `git://` or `git+git://`
+    if scheme == "git" || scheme == "git+git" {
+        return Some(format!("git+https://{}", rest));
+    }
+    let (prefix, scheme) = match scheme.split_once('+') {
+        Some((vcs, scheme)) => (Some(vcs), scheme),
+        None => (None, scheme),
+    };
+    if !matches!(scheme, "http" | "ftp") {
+        return None;
+    }
+
+    Some(match prefix {
+        Some(vcs) => format!("{}+https://{}", vcs, rest),
+        None => format!("https://{}", rest),
+    })
+}
+
+/// Check if the same path is also served over https, returns the url that should be used instead
+pub async fn probe_https(client: &Client, url: &str) -> Result<Option<String>> {
+    let Some(upgraded) = https_upgrade(url) else {
+        return Ok(None);
+    };
+    let probe = upgraded
+        .split_once("+https://")
+        .map(|(_, rest)| format!("https://{}", rest))
+        .unwrap_or_else(|| upgraded.clone());
+    let probe = probe.split('#').next().unwrap_or(&probe);
+
+    let is_git = upgraded.starts_with("git+https://");
+    let response = if is_git {
+        // a plain http request to a git repository isn't meaningful, ask for the refs like git does
+        let probe = format!(
+            "{}/info/refs?service=git-upload-pack",
+            probe.trim_end_matches('/')
+        );
+        info!("Url={}", probe);
+        client.get(probe).send().await
+    } else {
+        info!("Url={}", probe);
+        client.head(probe).send().await
+    };
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            let content_type = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default();
+            if is_git && content_type != "application/x-git-upload-pack-advertisement" {
+                debug!("Https probe for {:?} is not a git repository", probe);
+                Ok(None)
+            } else {
+                Ok(Some(upgraded))
+            }
+        }
+        Ok(response) => {
+            debug!("Https probe failed for {:?}: {}", probe, response.status());
+            Ok(None)
+        }
+        Err(err) => {
+            debug!("Https probe failed for {:?}: {:#}", probe, err);
+            Ok(None)
+        }
+    }
+}
+
+/// Check if an url is still served, some servers don't support HEAD so GET is used as a fallback
+pub async fn check_availability(client: &Client, url: &str) -> Availability {
+    let mut method = Method::HEAD;
+    loop {
+        info!("Url={}", url);
+        let response = client
+            .request(method.clone(), url)
+            .timeout(AVAILABILITY_TIMEOUT)
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => {
+                let size = response
+                    .headers()
+                    .get(CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                return Availability::Available { size };
+            }
+            Ok(response)
+                if method == Method::HEAD
+                    && matches!(
+                        response.status(),
+                        StatusCode::METHOD_NOT_ALLOWED | StatusCode::FORBIDDEN
+                    ) =>
+            {
+                method = Method::GET;
+            }
+            Ok(response) => return Availability::Status(response.status().as_u16()),
+            Err(err) if err.is_timeout() => return Availability::Error("timeout".to_string()),
+            Err(err) => return Availability::Error(format!("{:#}", err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_https_upgrade() {
+        assert_eq!(
+            https_upgrade("http://example.com/foo-1.0.tar.gz"),
+            Some("https://example.com/foo-1.0.tar.gz".to_string())
+        );
+        assert_eq!(
+            https_upgrade("ftp://ftp.example.com/pub/foo-1.0.tar.gz"),
+            Some("https://ftp.example.com/pub/foo-1.0.tar.gz".to_string())
+        );
+        assert_eq!(
+            https_upgrade("git+http://example.com/foo.git#tag=v1.0"),
+            Some("git+https://example.com/foo.git#tag=v1.0".to_string())
+        );
+        assert_eq!(
+            https_upgrade("git://example.com/foo.git#commit=abcd"),
+            Some("git+https://example.com/foo.git#commit=abcd".to_string())
+        );
+        assert_eq!(
+            https_upgrade("git+git://example.com/foo.git"),
+            Some("git+https://example.com/foo.git".to_string())
+        );
+        assert_eq!(https_upgrade("https://example.com/foo-1.0.tar.gz"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::errors::*;
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// How often a progress line is logged if stderr is not a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Progress of a scan, drawn as a single status line on terminals
+pub struct Progress {
+    total: usize,
+    done: usize,
+    started: Instant,
+    last_log: Instant,
+    enabled: bool,
+    terminal: bool,
+    drawn: bool,
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Progress {
+        let now = Instant::now();
+        Progress {
+            total,
+            done: 0,
+            started: now,
+            last_log: now,
+            enabled: log_enabled!(log::Level::Info),
+            terminal: io::stderr().is_terminal(),
+            drawn: false,
+        }
+    }
+
+    pub fn eta(&self) -> Option<Duration> {
+        if self.done == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.done) as u32;
+        Some(self.started.elapsed() / self.done as u32 * remaining)
+    }
+
+    fn status(&self, running: usize) -> String {
+        let eta = self
+            .eta()
+            .map(format_duration)
+            .unwrap_or_else(|| "-".to_string());
+        format!(
+            "[{}/{}] eta {}, {} running",
+            self.done, self.total, eta, running
+        )
+    }
+
+    /// Remove the status line, this should be called before writing anything else to stderr
+    pub fn clear(&mut self) {
+        if self.drawn {
+            eprint!("\r\x1b[2K");
+            self.drawn = false;
+        }
+    }
+
+    pub fn finished_one(&mut self) {
+        self.done += 1;
+    }
+
+    pub fn draw(&mut self, running: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.terminal {
+            eprint!("\r\x1b[2K{}", self.status(running));
+            io::stderr().flush().ok();
+            self.drawn = true;
+        } else if self.last_log.elapsed() >= LOG_INTERVAL || self.done == self.total {
+            info!("{}", self.status(running));
+            self.last_log = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+        assert_eq!(format_duration(Duration::from_secs(7320)), "2h02m");
+    }
+}
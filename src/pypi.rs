@@ -0,0 +1,149 @@
+use crate::errors::*;
+use crate::fsck::Checksum;
+use regex::Regex;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SdistUrl {
+    pub project: String,
+    pub version: Option<String>,
+    pub filename: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub urls: Vec<ReleaseFile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseFile {
+    pub filename: String,
+    pub digests: Digests,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Digests {
+    pub md5: Option<String>,
+    pub sha256: Option<String>,
+}
+
+impl Digests {
+    /// The digest published by pypi for the algorithm of this checksum, if any
+    pub fn get(&self, checksum: &Checksum) -> Option<&str> {
+        match checksum {
+            Checksum::Md5(_) => self.md5.as_deref(),
+            Checksum::Sha256(_) => self.sha256.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+fn parse_filename(filename: &str) -> Option<(&str, &str)> {
+    let re = Regex::new(r"^(.+)-(\d[^-]*)\.(tar\.gz|tar\.bz2|tar\.xz|zip)$").ok()?;
+    if let Some(caps) = re.captures(filename) {
+        return Some((caps.get(1)?.as_str(), caps.get(2)?.as_str()));
+    }
+
+    let re = Regex::new(r"^([^-]+)-(\d[^-]*)-.+\.whl$").ok()?;
+    if let Some(caps) = re.captures(filename) {
+        return Some((caps.get(1)?.as_str(), caps.get(2)?.as_str()));
+    }
+
+    None
+}
+
+pub fn detect_sdist_from_url(url: &str) -> Result<Option<SdistUrl>> {
+    let re = Regex::new(
+        r"^https://(?:files\.pythonhosted\.org|pypi\.io|pypi\.org|pypi\.python\.org)/packages/source/[^/]/([^/]+)/([^/?#]+)$",
+    )?;
+    if let Some(caps) = re.captures(url) {
+        let project = &caps[1];
+        let filename = &caps[2];
+        let version = parse_filename(filename).map(|(_, version)| version.to_string());
+
+        return Ok(Some(SdistUrl {
+            project: project.to_string(),
+            version,
+            filename: filename.to_string(),
+        }));
+    }
+
+    let re = Regex::new(r"^https://files\.pythonhosted\.org/packages/(?:[^/]+/)+([^/?#]+)$")?;
+    if let Some(caps) = re.captures(url) {
+        let filename = &caps[1];
+        if let Some((project, version)) = parse_filename(filename) {
+            return Ok(Some(SdistUrl {
+                project: project.to_string(),
+                version: Some(version.to_string()),
+                filename: filename.to_string(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+pub async fn fetch_release(
+    client: &Client,
+    project: &str,
+    version: &str,
+) -> Result<Option<Release>> {
+    let url = format!("https://pypi.org/pypi/{}/{}/json", project, version);
+
+    info!("Url={}", url);
+    let response = client.get(url).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let json = response.error_for_status()?.json().await?;
+
+    Ok(Some(json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pypi_url_matching() -> Result<()> {
+        let x = detect_sdist_from_url(
+            "https://files.pythonhosted.org/packages/source/r/requests/requests-2.28.1.tar.gz",
+        )?;
+        assert_eq!(
+            x,
+            Some(SdistUrl {
+                project: "requests".to_string(),
+                version: Some("2.28.1".to_string()),
+                filename: "requests-2.28.1.tar.gz".to_string(),
+            })
+        );
+
+        let x = detect_sdist_from_url(
+            "https://files.pythonhosted.org/packages/a5/61/a867851fd5ab77277495a8709ddda0861b28163c4613b011bc00228cc724/python-dateutil-2.8.2.tar.gz",
+        )?;
+        assert_eq!(
+            x,
+            Some(SdistUrl {
+                project: "python-dateutil".to_string(),
+                version: Some("2.8.2".to_string()),
+                filename: "python-dateutil-2.8.2.tar.gz".to_string(),
+            })
+        );
+
+        let x = detect_sdist_from_url("https://pypi.io/packages/source/f/foo/foo.tar.gz")?;
+        assert_eq!(
+            x,
+            Some(SdistUrl {
+                project: "foo".to_string(),
+                version: None,
+                filename: "foo.tar.gz".to_string(),
+            })
+        );
+
+        let x = detect_sdist_from_url("https://github.com/psf/requests/archive/v2.28.1.tar.gz")?;
+        assert_eq!(x, None);
+
+        Ok(())
+    }
+}
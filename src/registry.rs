@@ -0,0 +1,170 @@
+use crate::errors::*;
+use crate::fsck::{self, AuthedSource, CheckOptions, Finding, Target};
+use crate::makepkg::Pkgbuild;
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The parsed package that is shared by all lints
+pub struct Package<'a> {
+    pub target: &'a Target,
+    pub opts: &'a CheckOptions,
+    pub client: &'a reqwest::Client,
+    /// The directory the package was checked out to
+    pub path: PathBuf,
+    pub pkgbuild: Pkgbuild,
+    pub sources: Vec<AuthedSource>,
+    /// The content of the PKGBUILD, with the srcinfo evaluator there might only be a .SRCINFO
+    pub text: Option<String>,
+    /// If local files next to the PKGBUILD are available, a PKGBUILD from stdin or a url has none
+    pub has_files: bool,
+    /// If the PKGBUILD has a `pkgver()` function
+    pub is_vcs_package: bool,
+    /// The urls of all signature files in source=
+    pub signatures: HashSet<String>,
+    pub validpgpkeys: Vec<String>,
+}
+
+impl<'a> Package<'a> {
+    pub async fn new(
+        target: &'a Target,
+        opts: &'a CheckOptions,
+        client: &'a reqwest::Client,
+        path: PathBuf,
+        pkgbuild: Pkgbuild,
+        sources: Vec<AuthedSource>,
+    ) -> Result<Package<'a>> {
+        let pkgbuild_path = path.join("PKGBUILD");
+        let text = if pkgbuild_path.exists() {
+            Some(fs::read_to_string(pkgbuild_path).context("Failed to read PKGBUILD")?)
+        } else {
+            None
+        };
+        let has_files = text.is_some() && !matches!(target, Target::Stdin(_) | Target::Url(_));
+
+        let is_vcs_package = pkgbuild.has_function("pkgver").await?;
+        let signatures = sources
+            .iter()
+            .filter_map(|source| match source {
+                AuthedSource::Url(source) if source.is_signature_file() => {
+                    Some(source.url().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        let validpgpkeys = pkgbuild.list_variable("validpgpkeys").await?;
+
+        Ok(Package {
+            target,
+            opts,
+            client,
+            path,
+            pkgbuild,
+            sources,
+            text,
+            has_files,
+            is_vcs_package,
+            signatures,
+            validpgpkeys,
+        })
+    }
+}
+
+/// A check that produces findings for a package
+#[async_trait]
+pub trait Lint: Send + Sync {
+    /// A short name of the lint in kebab-case
+    fn name(&self) -> &'static str;
+
+    /// If the lint should run with these options, eg. because it needs network access
+    fn is_enabled(&self, _opts: &CheckOptions) -> bool {
+        true
+    }
+
+    async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()>;
+}
+
+/// The lints that are run for every package, in order
+#[derive(Clone)]
+pub struct Registry {
+    lints: Vec<Arc<dyn Lint>>,
+}
+
+impl Default for Registry {
+    /// A registry with all built-in lints
+    fn default() -> Self {
+        Registry {
+            lints: fsck::builtin_lints(),
+        }
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Registry {
+    /// A registry without any lints
+    pub fn empty() -> Self {
+        Registry { lints: Vec::new() }
+    }
+
+    /// Add a lint, it runs after the lints that are already registered
+    pub fn register<L: Lint + 'static>(&mut self, lint: L) {
+        self.lints.push(Arc::new(lint));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.lints.iter().map(|lint| lint.name())
+    }
+
+    /// Run all lints that are enabled for the options the package is checked with
+    pub async fn check(&self, pkg: &Package<'_>, findings: &mut Vec<Finding>) -> Result<()> {
+        for lint in &self.lints {
+            if !lint.is_enabled(pkg.opts) {
+                continue;
+            }
+            debug!("Running lint {:?}", lint.name());
+            lint.check(pkg, findings).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Noop;
+
+    #[async_trait]
+    impl Lint for Noop {
+        fn name(&self) -> &'static str {
+            "noop"
+        }
+
+        async fn check(&self, _pkg: &Package<'_>, _findings: &mut Vec<Finding>) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_register_lint() {
+        let mut registry = Registry::default();
+        let builtin = registry.names().collect::<HashSet<_>>();
+        assert_eq!(builtin.len(), registry.names().count());
+        assert!(!builtin.contains("noop"));
+
+        registry.register(Noop);
+        assert_eq!(registry.names().last(), Some("noop"));
+
+        let mut registry = Registry::empty();
+        registry.register(Noop);
+        assert_eq!(registry.names().collect::<Vec<_>>(), &["noop"]);
+    }
+}
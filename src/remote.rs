@@ -0,0 +1,121 @@
+use crate::errors::*;
+use reqwest::{Client, StatusCode, Url};
+use std::fs;
+use std::path::Path;
+
+/// Rewrite links to the html view of a file to the raw file, eg. the AUR cgit `tree/PKGBUILD?h=foo` pages
+pub fn raw_url(url: &str) -> Result<Url> {
+    let mut url = Url::parse(url).with_context(|| anyhow!("Invalid url: {:?}", url))?;
+    if !["http", "https"].contains(&url.scheme()) {
+        bail!("Only http and https urls are supported: {:?}", url.as_str());
+    }
+
+    let path = url.path();
+    if path.contains("/cgit/") && path.contains("/tree/") {
+        let path = path.replacen("/tree/", "/plain/", 1);
+        url.set_path(&path);
+    } else if path.contains("/-/blob/") {
+        let path = path.replacen("/-/blob/", "/-/raw/", 1);
+        url.set_path(&path);
+    }
+
+    Ok(url)
+}
+
+/// The url of a file in the same directory as the PKGBUILD, keeping the query (eg. `?h=foo` in cgit)
+pub fn sibling_url(url: &Url, filename: &str) -> Result<Url> {
+    let mut sibling = url.clone();
+    sibling
+        .path_segments_mut()
+        .map_err(|_| anyhow!("Url can't have a path: {:?}", url.as_str()))?
+        .pop()
+        .push(filename);
+    Ok(sibling)
+}
+
+/// Guess the pkgbase from a PKGBUILD url, eg. the `?h=` parameter in cgit or the directory the PKGBUILD is in
+pub fn pkgbase(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    if let Some((_, branch)) = url.query_pairs().find(|(key, _)| key == "h") {
+        return Some(branch.into_owned());
+    }
+
+    let segments = url.path_segments()?.collect::<Vec<_>>();
+    // gitlab urls look like `/archlinux/packaging/packages/foo/-/raw/main/PKGBUILD`
+    let dir = if let Some(idx) = segments.iter().position(|s| *s == "-") {
+        idx.checked_sub(1)?
+    } else {
+        segments.len().checked_sub(2)?
+    };
+    segments
+        .get(dir)
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
+
+async fn download(client: &Client, url: &Url) -> Result<Option<Vec<u8>>> {
+    info!("Downloading {:?}", url.as_str());
+    let response = client.get(url.clone()).send().await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let bytes = response.error_for_status()?.bytes().await?;
+    Ok(Some(bytes.to_vec()))
+}
+
+/// Download a PKGBUILD and the `.SRCINFO` next to it (if there is one) into a directory
+pub async fn fetch_pkgbuild(client: &Client, url: &str, dir: &Path) -> Result<()> {
+    let url = raw_url(url)?;
+
+    let pkgbuild = download(client, &url)
+        .await?
+        .with_context(|| anyhow!("PKGBUILD not found: {:?}", url.as_str()))?;
+    fs::write(dir.join("PKGBUILD"), pkgbuild)?;
+
+    let srcinfo_url = sibling_url(&url, ".SRCINFO")?;
+    match download(client, &srcinfo_url).await {
+        Ok(Some(srcinfo)) => fs::write(dir.join(".SRCINFO"), srcinfo)?,
+        Ok(None) => debug!("No .SRCINFO found at {:?}", srcinfo_url.as_str()),
+        Err(err) => warn!("Failed to download .SRCINFO: {:#}", err),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_urls() -> Result<()> {
+        let url = raw_url("https://aur.archlinux.org/cgit/aur.git/tree/PKGBUILD?h=foo-git")?;
+        assert_eq!(
+            url.as_str(),
+            "https://aur.archlinux.org/cgit/aur.git/plain/PKGBUILD?h=foo-git"
+        );
+        assert_eq!(
+            sibling_url(&url, ".SRCINFO")?.as_str(),
+            "https://aur.archlinux.org/cgit/aur.git/plain/.SRCINFO?h=foo-git"
+        );
+        assert_eq!(pkgbase(url.as_str()), Some("foo-git".to_string()));
+
+        let url = raw_url(
+            "https://gitlab.archlinux.org/archlinux/packaging/packages/linux/-/blob/main/PKGBUILD",
+        )?;
+        assert_eq!(
+            url.as_str(),
+            "https://gitlab.archlinux.org/archlinux/packaging/packages/linux/-/raw/main/PKGBUILD"
+        );
+        assert_eq!(pkgbase(url.as_str()), Some("linux".to_string()));
+
+        let url = raw_url("https://example.com/pkgs/bar/PKGBUILD")?;
+        assert_eq!(
+            sibling_url(&url, ".SRCINFO")?.as_str(),
+            "https://example.com/pkgs/bar/.SRCINFO"
+        );
+        assert_eq!(pkgbase(url.as_str()), Some("bar".to_string()));
+
+        assert!(raw_url("file:///etc/passwd").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use crate::db::Diff;
+use crate::errors::*;
+use crate::fsck::{Finding, FindingRecord, PackageFindings, Target};
+use crate::junit;
+use crate::sarif;
+use crate::stats::Stats;
+use crate::suppress::Suppressed;
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use tokio::time::error::Elapsed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human readable log lines on stderr
+    Text,
+    /// A single json document on stdout, written after all packages have been checked
+    Json,
+    /// A SARIF log on stdout, eg. for GitHub code scanning
+    Sarif,
+    /// JUnit xml on stdout, with one test suite per package
+    Junit,
+    /// One json object per line on stdout, written as soon as a package has been checked
+    Ndjson,
+    /// Render each finding with a custom template on stdout, see `--template`
+    Template,
+}
+
+#[derive(Default, Serialize)]
+pub struct Report {
+    pub packages: Vec<PackageReport>,
+    pub errors: Vec<PackageError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Stats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<Diff>,
+}
+
+#[derive(Serialize)]
+pub struct PackageReport {
+    pub target: String,
+    #[serde(serialize_with = "serialize_findings")]
+    pub findings: Vec<Finding>,
+    /// Findings that are hidden by a comment in the PKGBUILD
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Suppressed>,
+}
+
+#[derive(Serialize)]
+struct NdjsonFinding<'a> {
+    target: &'a str,
+    #[serde(flatten)]
+    finding: FindingRecord<'a>,
+}
+
+#[derive(Serialize)]
+struct NdjsonSuppressed<'a> {
+    target: &'a str,
+    suppressed: bool,
+    #[serde(flatten)]
+    finding: &'a Suppressed,
+}
+
+/// Stream the findings of a package to stdout, one json object per finding
+///
+/// Suppressed findings are written too, with `"suppressed": true`.
+pub fn write_ndjson<W: Write>(
+    mut w: W,
+    target: &Target,
+    findings: Result<&PackageFindings, &Error>,
+) -> Result<()> {
+    let target = target.display();
+    match findings {
+        Ok(pkg) => {
+            for finding in &pkg.findings {
+                let line = NdjsonFinding {
+                    target: &target,
+                    finding: finding.record(),
+                };
+                serde_json::to_writer(&mut w, &line)?;
+                writeln!(w)?;
+            }
+            for finding in &pkg.suppressed {
+                let line = NdjsonSuppressed {
+                    target: &target,
+                    suppressed: true,
+                    finding,
+                };
+                serde_json::to_writer(&mut w, &line)?;
+                writeln!(w)?;
+            }
+        }
+        Err(err) => {
+            let line = PackageError {
+                target: target.into_owned(),
+                kind: ErrorKind::of(err),
+                error: format!("{:#}", err),
+            };
+            serde_json::to_writer(&mut w, &line)?;
+            writeln!(w)?;
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+fn serialize_findings<S: Serializer>(
+    findings: &[Finding],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(findings.iter().map(Finding::record))
+}
+
+/// Why a package couldn't be checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    Failed,
+    /// Checking the package took longer than `--timeout`, or evaluating the PKGBUILD timed out
+    TimedOut,
+}
+
+impl ErrorKind {
+    pub fn of(err: &Error) -> ErrorKind {
+        if err.downcast_ref::<Elapsed>().is_some() {
+            ErrorKind::TimedOut
+        } else {
+            ErrorKind::Failed
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PackageError {
+    pub target: String,
+    pub kind: ErrorKind,
+    pub error: String,
+}
+
+impl PackageError {
+    pub fn new(target: &Target, err: &Error) -> PackageError {
+        PackageError {
+            target: target.display().into_owned(),
+            kind: ErrorKind::of(err),
+            error: format!("{:#}", err),
+        }
+    }
+}
+
+/// Write the findings of a single package into `<dir>/<pkgbase>.{json,txt}`, see [`Target::file_name`]
+pub fn write_package_file(
+    dir: &Path,
+    format: Format,
+    target: &Target,
+    findings: Result<&PackageFindings, &Error>,
+) -> Result<()> {
+    let ext = if format == Format::Json {
+        "json"
+    } else {
+        "txt"
+    };
+    let path = dir.join(format!("{}.{}", target.file_name(), ext));
+    let file = File::create(&path).with_context(|| anyhow!("Failed to create file: {:?}", path))?;
+    let mut w = BufWriter::new(file);
+
+    match (format, findings) {
+        (Format::Json, Ok(pkg)) => {
+            let pkg = PackageReport {
+                target: target.display().into_owned(),
+                findings: pkg.findings.clone(),
+                suppressed: pkg.suppressed.clone(),
+            };
+            serde_json::to_writer_pretty(&mut w, &pkg)?;
+            writeln!(w)?;
+        }
+        (Format::Json, Err(err)) => {
+            let err = PackageError::new(target, err);
+            serde_json::to_writer_pretty(&mut w, &err)?;
+            writeln!(w)?;
+        }
+        (_, Ok(pkg)) => {
+            for finding in &pkg.findings {
+                let kind: &'static str = finding.into();
+                writeln!(w, "{:<6}  {}: {}", finding.severity(), kind, finding)?;
+            }
+        }
+        (_, Err(err)) => {
+            writeln!(w, "error  Failed to check package: {:#}", err)?;
+        }
+    }
+
+    w.flush()?;
+    Ok(())
+}
+
+impl Report {
+    pub fn add_findings(&mut self, target: &Target, pkg: PackageFindings) {
+        self.packages.push(PackageReport {
+            target: target.display().into_owned(),
+            findings: pkg.findings,
+            suppressed: pkg.suppressed,
+        });
+    }
+
+    pub fn add_error(&mut self, target: &Target, err: &Error) {
+        self.errors.push(PackageError::new(target, err));
+    }
+
+    pub fn write_json<W: Write>(&self, mut w: W) -> Result<()> {
+        serde_json::to_writer_pretty(&mut w, self)?;
+        writeln!(w)?;
+        Ok(())
+    }
+
+    pub fn write_sarif<W: Write>(&self, mut w: W) -> Result<()> {
+        let log = sarif::Log::from(self);
+        serde_json::to_writer_pretty(&mut w, &log)?;
+        writeln!(w)?;
+        Ok(())
+    }
+
+    pub fn print(&self, format: Format) -> Result<()> {
+        match format {
+            Format::Text | Format::Ndjson | Format::Template => (),
+            Format::Json => self.write_json(io::stdout().lock())?,
+            Format::Sarif => self.write_sarif(io::stdout().lock())?,
+            Format::Junit => junit::write(io::stdout().lock(), self)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time;
+
+    #[tokio::test]
+    async fn test_timed_out_error() -> Result<()> {
+        let err = time::timeout(
+            Duration::from_millis(1),
+            time::sleep(Duration::from_secs(60)),
+        )
+        .await
+        .context("Checking the package timed out after 0s")
+        .unwrap_err();
+        let target = Target::ArchBuildSystem("linux".to_string());
+        let value = serde_json::to_value(PackageError::new(&target, &err))?;
+        assert_eq!(value["kind"], "timed-out");
+
+        let err = anyhow!("Missing PKGBUILD");
+        assert_eq!(ErrorKind::of(&err), ErrorKind::Failed);
+        Ok(())
+    }
+}
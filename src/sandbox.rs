@@ -0,0 +1,141 @@
+use crate::errors::*;
+use clap::ValueEnum;
+use rustix::process;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Once;
+use tokio::process::Command;
+
+/// Resource limits for evaluating a PKGBUILD: cpu seconds, virtual memory in KiB and file size in KiB
+const LIMITS: &str = "ulimit -t 30 -v 4194304 -f 10240";
+
+/// Prepare the mount namespace created by unshare: a tmpfs for /tmp and $HOME and a read-only bind
+/// mount of the package directory, the directory is kept open on fd 3 while /tmp is replaced.
+/// The script then runs in a nested user namespace as the original user, makepkg refuses to run as root.
+const UNSHARE_SETUP: &str = r#"set -e
+exec 3<.
+mount -t tmpfs tmpfs /tmp
+if [ -d "$HOME" ] && [ "$HOME" != / ]; then mount -t tmpfs tmpfs "$HOME"; fi
+mkdir /tmp/pkg
+mount --bind /proc/self/fd/3 /tmp/pkg
+mount -o remount,bind,ro /tmp/pkg
+exec 3<&-
+cd /tmp/pkg
+exec unshare --map-user="$1" --map-group="$2" bash -c "$3"
+"#;
+
+/// How the PKGBUILD is isolated while it's sourced by the bash evaluator
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sandbox {
+    /// Use bubblewrap if it's installed, unshare otherwise
+    #[default]
+    Auto,
+    /// Run bash with bwrap, without network, a read-only package directory and a tmpfs for /tmp and $HOME
+    Bwrap,
+    /// Same as bwrap, but set up with unshare from util-linux, this needs unprivileged user namespaces
+    Unshare,
+    /// Run bash directly, the PKGBUILD has the same access as archlinux-inputs-fsck
+    None,
+}
+
+fn find_program(name: &str) -> bool {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false)
+}
+
+impl Sandbox {
+    /// Pick the sandbox that's available on this system for `auto`
+    pub fn resolve(self) -> Sandbox {
+        static WARN: Once = Once::new();
+        match self {
+            Sandbox::Auto if find_program("bwrap") => Sandbox::Bwrap,
+            Sandbox::Auto if find_program("unshare") => Sandbox::Unshare,
+            Sandbox::Auto => {
+                WARN.call_once(|| {
+                    warn!("Neither bwrap nor unshare are installed, PKGBUILDs are sourced without a sandbox")
+                });
+                Sandbox::None
+            }
+            sandbox => sandbox,
+        }
+    }
+
+    /// Build the command to run a bash script in the package directory
+    pub fn bash_command(self, folder: &Path, script: &str) -> Command {
+        let script = format!("{}\n{}", LIMITS, script);
+        let home = env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+
+        let mut cmd = match self.resolve() {
+            Sandbox::Bwrap => {
+                let mut cmd = Command::new("bwrap");
+                cmd.args([
+                    "--unshare-all",
+                    "--die-with-parent",
+                    "--new-session",
+                    "--ro-bind",
+                    "/",
+                    "/",
+                    "--proc",
+                    "/proc",
+                    "--dev",
+                    "/dev",
+                    "--tmpfs",
+                    "/tmp",
+                ]);
+                if home != "/" {
+                    cmd.args(["--tmpfs", &home]);
+                }
+                // the source of the bind mount is resolved outside of the sandbox, so it needs to be absolute
+                let folder = fs::canonicalize(folder).unwrap_or_else(|_| folder.to_path_buf());
+                cmd.arg("--ro-bind")
+                    .arg(folder)
+                    .args(["/tmp/pkg", "--chdir", "/tmp/pkg", "bash", "-c", &script]);
+                cmd
+            }
+            Sandbox::Unshare => {
+                let mut cmd = Command::new("unshare");
+                cmd.args([
+                    "--map-root-user",
+                    "--mount",
+                    "--net",
+                    "--ipc",
+                    "--uts",
+                    "--pid",
+                    "--fork",
+                    "--kill-child",
+                    "--mount-proc",
+                    "bash",
+                    "-c",
+                    UNSHARE_SETUP,
+                    "unshare",
+                    &process::getuid().as_raw().to_string(),
+                    &process::getgid().as_raw().to_string(),
+                    &script,
+                ]);
+                cmd
+            }
+            Sandbox::Auto | Sandbox::None => {
+                let mut cmd = Command::new("bash");
+                cmd.arg("-c")
+                    .arg(&script)
+                    .current_dir(folder)
+                    .stdin(Stdio::null())
+                    .kill_on_drop(true);
+                return cmd;
+            }
+        };
+
+        // don't hand tokens or other secrets from the environment to the PKGBUILD
+        cmd.env_clear()
+            .env("PATH", env::var_os("PATH").unwrap_or_default())
+            .env("HOME", home)
+            .env("LANG", "C.UTF-8")
+            .current_dir(folder)
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        cmd
+    }
+}
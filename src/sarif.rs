@@ -0,0 +1,176 @@
+use crate::fsck::{Finding, Severity};
+use crate::report::Report;
+use serde::Serialize;
+use std::path::Path;
+
+pub const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+pub const VERSION: &str = "2.1.0";
+
+#[derive(Debug, Serialize)]
+pub struct Log {
+    #[serde(rename = "$schema")]
+    pub schema: &'static str,
+    pub version: &'static str,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub information_uri: &'static str,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: &'static str,
+    pub short_description: Message,
+    pub help_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: &'static str,
+    pub rule_index: usize,
+    pub level: &'static str,
+    pub message: Message,
+    pub locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressions: Vec<Suppression>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Suppression {
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub justification: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub physical_location: PhysicalLocation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArtifactLocation {
+    pub uri: String,
+}
+
+impl Rule {
+    fn new(finding: &Finding) -> Rule {
+        let id: &'static str = finding.into();
+        Rule {
+            id,
+            short_description: Message {
+                text: finding.description().to_string(),
+            },
+            help_uri: format!("{}#{}", env!("CARGO_PKG_REPOSITORY"), id),
+        }
+    }
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+fn artifact_uri(target: &str) -> String {
+    let path = Path::new(target).join("PKGBUILD");
+    let path = path.strip_prefix("./").unwrap_or(&path);
+    path.to_string_lossy().into_owned()
+}
+
+impl From<&Report> for Log {
+    fn from(report: &Report) -> Log {
+        let mut rules = Vec::<Rule>::new();
+        let mut results = Vec::new();
+
+        for pkg in &report.packages {
+            let suppressed = pkg.suppressed.iter().map(|suppressed| {
+                let suppression = Suppression {
+                    kind: if suppressed.line.is_some() {
+                        "inSource"
+                    } else {
+                        "external"
+                    },
+                    justification: suppressed.reason.clone(),
+                };
+                (&suppressed.finding, Some(suppression))
+            });
+            let findings = pkg.findings.iter().map(|finding| (finding, None));
+
+            for (finding, suppression) in findings.chain(suppressed) {
+                let rule_id: &'static str = finding.into();
+                let rule_index = if let Some(idx) = rules.iter().position(|r| r.id == rule_id) {
+                    idx
+                } else {
+                    rules.push(Rule::new(finding));
+                    rules.len() - 1
+                };
+
+                results.push(SarifResult {
+                    rule_id,
+                    rule_index,
+                    level: level(finding.severity()),
+                    message: Message {
+                        text: finding.to_string(),
+                    },
+                    locations: vec![Location {
+                        physical_location: PhysicalLocation {
+                            artifact_location: ArtifactLocation {
+                                uri: artifact_uri(&pkg.target),
+                            },
+                        },
+                    }],
+                    suppressions: suppression.into_iter().collect(),
+                });
+            }
+        }
+
+        Log {
+            schema: SCHEMA,
+            version: VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules,
+                    },
+                },
+                results,
+            }],
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::errors::*;
+use crate::fsck::{self, AuthedSource, Checksum};
+use crate::makepkg::{Evaluator, Pkgbuild};
+use crate::sandbox::Sandbox;
+use serde::Serialize;
+use std::path::Path;
+
+pub const SPEC_VERSION: &str = "1.5";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    pub bom_format: &'static str,
+    pub spec_version: &'static str,
+    pub version: u32,
+    pub metadata: Metadata,
+    pub components: Vec<Component>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Metadata {
+    pub tools: Vec<Tool>,
+    pub component: Component,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tool {
+    pub name: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Component {
+    pub r#type: &'static str,
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    pub bom_ref: Option<String>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hashes: Vec<Hash>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_references: Vec<ExternalReference>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hash {
+    pub alg: &'static str,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExternalReference {
+    pub r#type: &'static str,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+}
+
+impl Property {
+    fn new<N: Into<String>, V: Into<String>>(name: N, value: V) -> Property {
+        Property {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// The name of the hash algorithm in CycloneDX, sha224 and cksum are not supported by the spec
+fn hash_alg(checksum: &Checksum) -> Option<&'static str> {
+    match checksum {
+        Checksum::Md5(_) => Some("MD5"),
+        Checksum::Sha1(_) => Some("SHA-1"),
+        Checksum::Sha256(_) => Some("SHA-256"),
+        Checksum::Sha384(_) => Some("SHA-384"),
+        Checksum::Sha512(_) => Some("SHA-512"),
+        Checksum::B2(_) => Some("BLAKE2b-512"),
+        Checksum::Sha224(_) => None,
+        Checksum::Cksum(_) => None,
+    }
+}
+
+fn filename_from_url(url: &str) -> &str {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    url.trim_end_matches('/').rsplit('/').next().unwrap_or(url)
+}
+
+fn vcs_component(
+    idx: usize,
+    url: &str,
+    pins: &[(&str, Option<&str>)],
+    version: Option<&str>,
+) -> Component {
+    let properties = pins
+        .iter()
+        .filter_map(|(key, value)| value.map(|value| Property::new(*key, value)))
+        .collect();
+
+    Component {
+        r#type: "library",
+        bom_ref: Some(format!("source-{}", idx)),
+        name: filename_from_url(url).trim_end_matches(".git").to_string(),
+        version: version.map(String::from),
+        external_references: vec![ExternalReference {
+            r#type: "vcs",
+            url: url.to_string(),
+        }],
+        properties,
+        ..Default::default()
+    }
+}
+
+fn component(idx: usize, source: &AuthedSource) -> Component {
+    match source {
+        AuthedSource::File(name) => Component {
+            r#type: "file",
+            bom_ref: Some(format!("source-{}", idx)),
+            name: name.to_string(),
+            properties: vec![Property::new("archlinux:source:local", "true")],
+            ..Default::default()
+        },
+        AuthedSource::Url(source) => {
+            let mut hashes = Vec::new();
+            let mut properties = Vec::new();
+            for checksum in source.checksums() {
+                if let Some(alg) = hash_alg(checksum) {
+                    hashes.push(Hash {
+                        alg,
+                        content: checksum.value().to_string(),
+                    });
+                } else {
+                    properties.push(Property::new(
+                        format!("archlinux:{}", checksum.alg()),
+                        checksum.value(),
+                    ));
+                }
+            }
+            if source.is_signature_file() {
+                properties.push(Property::new("archlinux:signature", "true"));
+            }
+
+            Component {
+                r#type: "file",
+                bom_ref: Some(format!("source-{}", idx)),
+                name: source
+                    .filename()
+                    .unwrap_or_else(|| filename_from_url(source.url()))
+                    .to_string(),
+                hashes,
+                external_references: vec![ExternalReference {
+                    r#type: "distribution",
+                    url: source.url().to_string(),
+                }],
+                properties,
+                ..Default::default()
+            }
+        }
+        AuthedSource::Git(source) => {
+            let signed = source.is_signed().then_some("true");
+            vcs_component(
+                idx,
+                source.url(),
+                &[
+                    ("archlinux:git:commit", source.commit()),
+                    ("archlinux:git:tag", source.tag()),
+                    ("archlinux:git:signed", signed),
+                ],
+                source.commit().or(source.tag()),
+            )
+        }
+        AuthedSource::Svn(source) => vcs_component(
+            idx,
+            source.url(),
+            &[("archlinux:svn:revision", source.revision())],
+            source.revision(),
+        ),
+        AuthedSource::Hg(source) => vcs_component(
+            idx,
+            source.url(),
+            &[("archlinux:hg:revision", source.revision())],
+            source.revision(),
+        ),
+        AuthedSource::Bzr(source) => vcs_component(
+            idx,
+            source.url(),
+            &[("archlinux:bzr:revision", source.revision())],
+            source.revision(),
+        ),
+        AuthedSource::Fossil(source) => vcs_component(
+            idx,
+            source.url(),
+            &[("archlinux:fossil:commit", source.commit())],
+            source.commit(),
+        ),
+        AuthedSource::Darcs(source) => vcs_component(
+            idx,
+            source.url(),
+            &[("archlinux:darcs:tag", source.tag())],
+            source.tag(),
+        ),
+    }
+}
+
+/// Generate a CycloneDX document for the declared inputs of a PKGBUILD
+pub async fn generate(path: &Path, sandbox: Sandbox) -> Result<Bom> {
+    let pkgbuild = Pkgbuild::load(path, Evaluator::Bash, sandbox).await?;
+    let sources = fsck::parse_sources(&pkgbuild, &mut Vec::new()).await?;
+
+    let pkgbase = pkgbuild.list_variable("pkgbase").await?;
+    let pkgname = pkgbuild.list_variable("pkgname").await?;
+    let name = pkgbase
+        .into_iter()
+        .chain(pkgname)
+        .next()
+        .context("PKGBUILD has no pkgbase or pkgname")?;
+    let version = pkgbuild.list_variable("pkgver").await?.into_iter().next();
+
+    let components = sources
+        .iter()
+        .enumerate()
+        .map(|(idx, source)| component(idx, source))
+        .collect();
+
+    Ok(Bom {
+        bom_format: "CycloneDX",
+        spec_version: SPEC_VERSION,
+        version: 1,
+        metadata: Metadata {
+            tools: vec![Tool {
+                name: env!("CARGO_PKG_NAME"),
+                version: env!("CARGO_PKG_VERSION"),
+            }],
+            component: Component {
+                r#type: "application",
+                name,
+                version,
+                ..Default::default()
+            },
+        },
+        components,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filename_from_url() {
+        assert_eq!(
+            filename_from_url("https://example.com/foo/bar-1.0.tar.gz"),
+            "bar-1.0.tar.gz"
+        );
+        assert_eq!(
+            filename_from_url("git+https://github.com/kpcyrd/sh4d0wup.git#tag=v0.9.0?signed"),
+            "sh4d0wup.git"
+        );
+        assert_eq!(filename_from_url("https://example.com/foo/"), "foo");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::errors::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagUrl {
+    pub owner: String,
+    pub name: String,
+    pub tag: String,
+}
+
+impl TagUrl {
+    /// The url to clone the repository from, the sourcehut api requires authentication so we talk git instead
+    pub fn clone_url(&self) -> String {
+        format!("https://git.sr.ht/~{}/{}", self.owner, self.name)
+    }
+}
+
+pub fn detect_signed_tag_from_url(url: &str) -> Result<Option<TagUrl>> {
+    let re = Regex::new(r"^https://git.sr.ht/~([^/]+)/([^/]+)/archive/(.+)\.tar\.gz$")?;
+    if let Some(caps) = re.captures(url) {
+        let owner = &caps[1];
+        let name = &caps[2];
+        let tag = &caps[3];
+
+        return Ok(Some(TagUrl {
+            owner: owner.to_string(),
+            name: name.to_string(),
+            tag: tag.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sourcehut_url_matching() -> Result<()> {
+        let x =
+            detect_signed_tag_from_url("https://git.sr.ht/~sircmpwn/scdoc/archive/1.11.2.tar.gz")?;
+        assert_eq!(
+            x,
+            Some(TagUrl {
+                owner: "sircmpwn".to_string(),
+                name: "scdoc".to_string(),
+                tag: "1.11.2".to_string(),
+            })
+        );
+        assert_eq!(x.unwrap().clone_url(), "https://git.sr.ht/~sircmpwn/scdoc");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+/// Variables of the pkgbase section that makepkg writes to `.SRCINFO` if they're set
+const KEYS: &[&str] = &[
+    "pkgbase",
+    "pkgdesc",
+    "pkgver",
+    "pkgrel",
+    "epoch",
+    "url",
+    "install",
+    "changelog",
+    "arch",
+    "groups",
+    "license",
+    "noextract",
+    "options",
+    "backup",
+    "validpgpkeys",
+];
+
+/// Variables that can also have an architecture suffix, eg. `source_x86_64`
+const ARCH_KEYS: &[&str] = &[
+    "source",
+    "depends",
+    "makedepends",
+    "checkdepends",
+    "optdepends",
+    "conflicts",
+    "provides",
+    "replaces",
+];
+
+/// The `pkgbase` section of a `.SRCINFO` file, as generated by `makepkg --printsrcinfo`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SrcInfo {
+    pub values: BTreeMap<String, Vec<String>>,
+    pub pkgnames: Vec<String>,
+}
+
+impl SrcInfo {
+    pub fn parse(text: &str) -> Self {
+        let mut values = BTreeMap::<String, Vec<String>>::new();
+        let mut pkgnames = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            // the package sections follow after the pkgbase section
+            if key == "pkgname" {
+                pkgnames.push(value.to_string());
+            }
+            if !pkgnames.is_empty() {
+                continue;
+            }
+            values
+                .entry(key.to_string())
+                .or_default()
+                .push(value.to_string());
+        }
+        SrcInfo { values, pkgnames }
+    }
+
+    pub fn get(&self, key: &str) -> &[String] {
+        self.values.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// If makepkg writes this variable to `.SRCINFO`, a missing key means the variable is empty
+    pub fn covers(&self, var: &str) -> bool {
+        if KEYS.contains(&var) {
+            return true;
+        }
+        let (key, arch) = match var.split_once('_') {
+            Some((key, arch)) => (key, Some(arch)),
+            None => (var, None),
+        };
+        if let Some(arch) = arch {
+            if !self.get("arch").iter().any(|a| a == arch) {
+                return false;
+            }
+        }
+        ARCH_KEYS.contains(&key)
+            || (key.len() > "sums".len()
+                && key.ends_with("sums")
+                && key.chars().all(|c| c.is_ascii_alphanumeric()))
+    }
+
+    /// The keys that describe the inputs of the build, these are compared with the PKGBUILD
+    pub fn input_keys(&self) -> impl Iterator<Item = &str> {
+        self.values
+            .keys()
+            .map(String::as_str)
+            .filter(|key| is_input_key(key))
+    }
+}
+
+pub fn is_input_key(key: &str) -> bool {
+    let alg = key.split('_').next().unwrap_or(key);
+    key == "validpgpkeys"
+        || alg == "source"
+        || (alg.ends_with("sums") && alg.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_srcinfo() {
+        let srcinfo = SrcInfo::parse(
+            "pkgbase = foo
+	pkgver = 1.0
+	arch = x86_64
+	source = https://example.com/foo-1.0.tar.gz
+	source = foo.patch
+	source_x86_64 = https://example.com/foo-x86_64.bin
+	sha256sums = aaaa
+	sha256sums = SKIP
+	sha256sums_x86_64 = bbbb
+	validpgpkeys = 64B13F7117D6E07D661BBCE0FE763A64F5E54FD6
+
+pkgname = foo
+	depends = bar
+
+pkgname = foo-docs
+",
+        );
+        assert_eq!(
+            srcinfo.get("source"),
+            &["https://example.com/foo-1.0.tar.gz", "foo.patch"]
+        );
+        assert_eq!(srcinfo.get("sha256sums"), &["aaaa", "SKIP"]);
+        assert_eq!(srcinfo.get("depends"), &[] as &[String]);
+        assert_eq!(srcinfo.pkgnames, &["foo", "foo-docs"]);
+        assert!(srcinfo.covers("noextract"));
+        assert!(srcinfo.covers("b2sums_x86_64"));
+        assert!(!srcinfo.covers("source_aarch64"));
+        assert!(!srcinfo.covers("_commit"));
+        assert_eq!(
+            srcinfo.input_keys().collect::<Vec<_>>(),
+            &[
+                "sha256sums",
+                "sha256sums_x86_64",
+                "source",
+                "source_x86_64",
+                "validpgpkeys"
+            ]
+        );
+    }
+}
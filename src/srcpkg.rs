@@ -0,0 +1,62 @@
+use crate::errors::*;
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+pub const EXTENSION: &str = ".src.tar.gz";
+
+/// Source packages as created by `makepkg --source`, eg. `linux-6.1.1.arch1-1.src.tar.gz`
+pub fn is_source_package(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(EXTENSION))
+}
+
+/// Strip the extension, pkgver and pkgrel from the filename of a source package
+pub fn pkgbase(filename: &str) -> Option<&str> {
+    let name = filename.strip_suffix(EXTENSION)?;
+    let (name, _pkgrel) = name.rsplit_once('-')?;
+    let (name, _pkgver) = name.rsplit_once('-')?;
+    Some(name)
+}
+
+/// Extract a source package and return the directory with the PKGBUILD
+pub fn extract(tarball: &Path, directory: &Path) -> Result<PathBuf> {
+    debug!("Extracting {:?} to {:?}", tarball, directory);
+    let file = File::open(tarball)
+        .with_context(|| anyhow!("Failed to open source package: {:?}", tarball))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    // unpack refuses entries that would be written outside of the directory
+    archive
+        .unpack(directory)
+        .with_context(|| anyhow!("Failed to extract source package: {:?}", tarball))?;
+
+    // makepkg puts all files into a directory named after the pkgbase
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.join("PKGBUILD").exists() {
+            return Ok(path);
+        }
+    }
+
+    Ok(directory.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_package_pkgbase() {
+        assert_eq!(pkgbase("linux-6.1.1.arch1-1.src.tar.gz"), Some("linux"));
+        assert_eq!(
+            pkgbase("python-foo-bar-1:2.0-3.src.tar.gz"),
+            Some("python-foo-bar")
+        );
+        assert_eq!(pkgbase("linux-6.1.1.arch1-1.pkg.tar.zst"), None);
+        assert!(is_source_package(Path::new(
+            "/tmp/linux-6.1.1.arch1-1.src.tar.gz"
+        )));
+        assert!(!is_source_package(Path::new("/tmp/linux")));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::errors::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+pub const STATE_URL: &str = "https://gitlab.archlinux.org/archlinux/packaging/state.git";
+
+/// The released version of a pkgbase, as recorded in the state repository
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PackageState {
+    pub pkgbase: String,
+    pub version: String,
+    /// The repositories the pkgbase is released in, eg. `extra-x86_64`
+    pub repos: Vec<String>,
+}
+
+/// Testing and staging repositories contain packages that are not released yet
+fn is_stable_repo(repo: &str) -> bool {
+    !repo.starts_with('.') && !repo.contains("-testing") && !repo.contains("-staging")
+}
+
+/// Parse a state file, a single line with `pkgbase version tag ...`
+pub fn parse_state_file(content: &str) -> Result<(String, String)> {
+    let mut fields = content.split_whitespace();
+    let pkgbase = fields.next().context("State file is empty")?;
+    let version = fields
+        .next()
+        .with_context(|| anyhow!("State file for {:?} has no version", pkgbase))?;
+    Ok((pkgbase.to_string(), version.to_string()))
+}
+
+/// Read a checkout of the state repository, there is one directory per repository
+/// (eg. `core-x86_64`) with one file per pkgbase
+pub fn read_state(path: &Path) -> Result<BTreeMap<String, PackageState>> {
+    let mut packages = BTreeMap::<String, PackageState>::new();
+
+    let mut repos = fs::read_dir(path)
+        .with_context(|| anyhow!("Failed to read state repository: {:?}", path))?
+        .collect::<Result<Vec<_>, _>>()?;
+    repos.sort_by_key(|entry| entry.file_name());
+
+    for repo in repos {
+        let repo_name = repo.file_name().to_string_lossy().into_owned();
+        if !repo.file_type()?.is_dir() || !is_stable_repo(&repo_name) {
+            continue;
+        }
+
+        for entry in fs::read_dir(repo.path())? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(entry.path())?;
+            let (pkgbase, version) = parse_state_file(&content)
+                .with_context(|| anyhow!("Failed to parse state file: {:?}", entry.path()))?;
+
+            let state = packages
+                .entry(pkgbase.clone())
+                .or_insert_with(|| PackageState {
+                    pkgbase,
+                    version: version.clone(),
+                    repos: Vec::new(),
+                });
+            if state.version != version {
+                warn!(
+                    "Conflicting versions for {:?} in state repository: {:?} and {:?}",
+                    state.pkgbase, state.version, version
+                );
+            }
+            state.repos.push(repo_name.clone());
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Clone the state repository into a directory
+pub async fn clone(directory: &Path) -> Result<PathBuf> {
+    let path = directory.join("state");
+    debug!("Cloning {:?} to {:?}", STATE_URL, path);
+    let cmd = Command::new("git")
+        .args(["clone", "--depth=1", "--quiet", STATE_URL])
+        .arg(&path)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .spawn()
+        .with_context(|| anyhow!("Failed to run git clone {:?}", STATE_URL))?;
+
+    let out = cmd.wait_with_output().await?;
+    if !out.status.success() {
+        bail!(
+            "Process (git clone {:?}) exited with error: {:?}",
+            STATE_URL,
+            out.status
+        );
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_state() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for (repo, pkgbase, version) in [
+            ("core-x86_64", "linux", "6.1.1.arch1-1"),
+            ("core-testing-x86_64", "linux", "6.2.arch1-1"),
+            ("extra-x86_64", "zstd", "1:1.5.2-9"),
+            ("extra-staging-x86_64", "python", "3.12.0-1"),
+        ] {
+            let repo = dir.path().join(repo);
+            fs::create_dir_all(&repo)?;
+            fs::write(
+                repo.join(pkgbase),
+                format!("{} {} {} abcdef\n", pkgbase, version, version),
+            )?;
+        }
+        fs::create_dir(dir.path().join(".git"))?;
+
+        let state = read_state(dir.path())?;
+        assert_eq!(
+            state.into_values().collect::<Vec<_>>(),
+            vec![
+                PackageState {
+                    pkgbase: "linux".to_string(),
+                    version: "6.1.1.arch1-1".to_string(),
+                    repos: vec!["core-x86_64".to_string()],
+                },
+                PackageState {
+                    pkgbase: "zstd".to_string(),
+                    version: "1:1.5.2-9".to_string(),
+                    repos: vec!["extra-x86_64".to_string()],
+                },
+            ]
+        );
+        Ok(())
+    }
+}
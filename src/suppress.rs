@@ -0,0 +1,422 @@
+use crate::errors::*;
+use crate::fsck::{Finding, FindingRecord, PackageFindings, Target};
+use glob::Pattern;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use strum::VariantNames;
+
+/// A `# fsck: ignore=<kind>[,<kind>] reason="..."` comment in a PKGBUILD
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Suppression {
+    pub kinds: Vec<String>,
+    pub reason: Option<String>,
+    pub line: usize,
+}
+
+/// A finding that is hidden by a suppression comment or file, it's still reported in structured output
+#[derive(Clone)]
+pub struct Suppressed {
+    pub finding: Finding,
+    pub reason: Option<String>,
+    /// The line of the suppression comment in the PKGBUILD, if it's not from a suppressions file
+    pub line: Option<usize>,
+    /// The date the suppression expires, from a suppressions file
+    pub expires: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SuppressedBy<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct SuppressedRecord<'a> {
+    #[serde(flatten)]
+    finding: FindingRecord<'a>,
+    suppression: SuppressedBy<'a>,
+}
+
+impl Serialize for Suppressed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        SuppressedRecord {
+            finding: self.finding.record(),
+            suppression: SuppressedBy {
+                reason: self.reason.as_deref(),
+                line: self.line,
+                expires: self.expires.as_deref(),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Split the arguments of a suppression comment into key/value pairs, values may be double quoted
+fn parse_args(s: &str) -> Vec<(&str, String)> {
+    let mut args = Vec::new();
+    let mut remaining = s.trim_start();
+    while !remaining.is_empty() {
+        let (key, rest) = remaining
+            .split_once('=')
+            .unwrap_or((remaining.trim_end(), ""));
+        if key.contains(char::is_whitespace) {
+            // a bare word without value, eg. a trailing comment
+            let (word, rest) = remaining
+                .split_once(char::is_whitespace)
+                .unwrap_or((remaining, ""));
+            args.push((word, String::new()));
+            remaining = rest.trim_start();
+            continue;
+        }
+
+        let (value, rest) = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((idx, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, c)) = chars.next() {
+                            value.push(c);
+                        }
+                    }
+                    '"' => {
+                        end = idx + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+            (value, &quoted[end..])
+        } else {
+            let (value, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            (value.to_string(), rest)
+        };
+        args.push((key, value));
+        remaining = rest.trim_start();
+    }
+    args
+}
+
+/// Find the suppression comments in a PKGBUILD, finding types that don't exist are ignored with a warning
+pub fn parse(text: &str) -> Vec<Suppression> {
+    let mut suppressions = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let Some(comment) = line
+            .match_indices('#')
+            .find_map(|(idx, _)| line[idx + 1..].trim_start().strip_prefix("fsck:"))
+        else {
+            continue;
+        };
+
+        let mut suppression = Suppression {
+            kinds: Vec::new(),
+            reason: None,
+            line: idx + 1,
+        };
+        for (key, value) in parse_args(comment) {
+            match key {
+                "ignore" => {
+                    for kind in value.split(',').map(str::trim) {
+                        if Finding::VARIANTS.contains(&kind) {
+                            suppression.kinds.push(kind.to_string());
+                        } else {
+                            warn!(
+                                "Unknown finding type in suppression comment in line {}: {:?}",
+                                idx + 1,
+                                kind
+                            );
+                        }
+                    }
+                }
+                "reason" => suppression.reason = Some(value),
+                _ => warn!(
+                    "Unknown option in suppression comment in line {}: {:?}",
+                    idx + 1,
+                    key
+                ),
+            }
+        }
+
+        if !suppression.kinds.is_empty() {
+            suppressions.push(suppression);
+        }
+    }
+    suppressions
+}
+
+/// Move the findings that are suppressed by a comment into a separate list
+pub fn apply(
+    suppressions: &[Suppression],
+    findings: Vec<Finding>,
+) -> (Vec<Finding>, Vec<Suppressed>) {
+    let mut reported = Vec::new();
+    let mut suppressed = Vec::new();
+    for finding in findings {
+        let kind: &'static str = (&finding).into();
+        if let Some(suppression) = suppressions
+            .iter()
+            .find(|suppression| suppression.kinds.iter().any(|k| k == kind))
+        {
+            debug!(
+                "Finding {:?} is suppressed by comment in line {}",
+                kind, suppression.line
+            );
+            suppressed.push(Suppressed {
+                finding,
+                reason: suppression.reason.clone(),
+                line: Some(suppression.line),
+                expires: None,
+            });
+        } else {
+            reported.push(finding);
+        }
+    }
+    (reported, suppressed)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SuppressionsToml {
+    #[serde(default)]
+    suppress: Vec<EntryToml>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EntryToml {
+    pkgbase: String,
+    kind: String,
+    source: Option<String>,
+    reason: String,
+    expires: Option<String>,
+}
+
+/// A suppression from a suppressions file
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// A glob that matches the pkgbase, eg. `python-*`
+    pub pkgbase: Pattern,
+    pub kind: String,
+    /// A glob that matches the url of the source the finding is about
+    pub source: Option<Pattern>,
+    pub reason: String,
+    /// The last day the suppression applies, as `YYYY-MM-DD`
+    pub expires: Option<String>,
+}
+
+impl Entry {
+    fn matches(&self, target: &Target, finding: &Finding) -> bool {
+        let kind: &'static str = finding.into();
+        if kind != self.kind || !self.pkgbase.matches(&target.pkgbase()) {
+            return false;
+        }
+        match &self.source {
+            Some(source) => finding.url().is_some_and(|url| source.matches(url)),
+            None => true,
+        }
+    }
+}
+
+/// Convert a `YYYY-MM-DD` date to the number of days since 1970-01-01
+pub fn parse_date(s: &str) -> Result<i64> {
+    let mut parts = s.splitn(3, '-').map(str::parse::<i64>);
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        bail!("Invalid date, expected YYYY-MM-DD: {:?}", s);
+    };
+    let is_leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap => 29,
+        2 => 28,
+        _ => bail!("Invalid month in date: {:?}", s),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        bail!("Invalid day in date: {:?}", s);
+    }
+
+    // days_from_civil, see https://howardhinnant.github.io/date_algorithms.html
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Ok(era * 146097 + doe - 719468)
+}
+
+fn today() -> Result<i64> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    Ok((secs / 86400) as i64)
+}
+
+/// A list of known findings that are accepted for now, eg. to roll out the checks on the whole archive
+#[derive(Debug, Default)]
+pub struct SuppressionFile {
+    entries: Vec<Entry>,
+}
+
+impl SuppressionFile {
+    /// Parse a suppressions file, suppressions that are expired on `today` are left out
+    pub fn parse(s: &str, today: i64) -> Result<SuppressionFile> {
+        let file = toml::from_str::<SuppressionsToml>(s)?;
+        let mut entries = Vec::new();
+        for entry in file.suppress {
+            if !Finding::VARIANTS.contains(&entry.kind.as_str()) {
+                bail!(
+                    "Unknown finding type in suppressions file: {:?}",
+                    entry.kind
+                );
+            }
+            if let Some(expires) = &entry.expires {
+                if parse_date(expires)? < today {
+                    warn!(
+                        "Suppression of {:?} for {:?} expired on {}: {}",
+                        entry.kind, entry.pkgbase, expires, entry.reason
+                    );
+                    continue;
+                }
+            }
+            entries.push(Entry {
+                pkgbase: Pattern::new(&entry.pkgbase)
+                    .with_context(|| anyhow!("Invalid pkgbase pattern: {:?}", entry.pkgbase))?,
+                kind: entry.kind,
+                source: entry
+                    .source
+                    .map(|source| {
+                        Pattern::new(&source)
+                            .with_context(|| anyhow!("Invalid source pattern: {:?}", source))
+                    })
+                    .transpose()?,
+                reason: entry.reason,
+                expires: entry.expires,
+            });
+        }
+        Ok(SuppressionFile { entries })
+    }
+
+    pub fn load(path: &Path) -> Result<SuppressionFile> {
+        let s = fs::read_to_string(path)
+            .with_context(|| anyhow!("Failed to read suppressions file: {:?}", path))?;
+        Self::parse(&s, today()?)
+            .with_context(|| anyhow!("Failed to parse suppressions file: {:?}", path))
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Move the findings of a package that match a suppression into the suppressed findings
+    pub fn apply(&self, target: &Target, pkg: &mut PackageFindings) {
+        for finding in mem::take(&mut pkg.findings) {
+            if let Some(entry) = self
+                .entries
+                .iter()
+                .find(|entry| entry.matches(target, &finding))
+            {
+                pkg.suppressed.push(Suppressed {
+                    finding,
+                    reason: Some(entry.reason.clone()),
+                    line: None,
+                    expires: entry.expires.clone(),
+                });
+            } else {
+                pkg.findings.push(finding);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suppressions() {
+        let text = r#"pkgname=foo
+# fsck: ignore=git-commit-insecure-pin reason="upstream never tags"
+source=("git+https://example.com/foo.git#commit=abcdef") # fsck: ignore=unversioned-url,sudo-in-pkgbuild
+#fsck: ignore=not-a-finding
+# fsck is great
+"#;
+        assert_eq!(
+            parse(text),
+            vec![
+                Suppression {
+                    kinds: vec!["git-commit-insecure-pin".to_string()],
+                    reason: Some("upstream never tags".to_string()),
+                    line: 2,
+                },
+                Suppression {
+                    kinds: vec![
+                        "unversioned-url".to_string(),
+                        "sudo-in-pkgbuild".to_string()
+                    ],
+                    reason: None,
+                    line: 3,
+                },
+            ]
+        );
+
+        assert_eq!(
+            parse_args(r#" ignore=foo reason="a \"quoted\" reason" bar"#),
+            vec![
+                ("ignore", "foo".to_string()),
+                ("reason", "a \"quoted\" reason".to_string()),
+                ("bar", String::new()),
+            ]
+        );
+    }
+    #[test]
+    fn test_suppression_file() -> Result<()> {
+        assert_eq!(parse_date("1970-01-01")?, 0);
+        assert_eq!(parse_date("2000-03-01")?, 11017);
+        assert!(parse_date("2023-02-29").is_err());
+
+        let file = SuppressionFile::parse(
+            r#"
+[[suppress]]
+pkgbase = "python-*"
+kind = "unversioned-url"
+source = "https://example.com/*"
+reason = "upstream only publishes latest"
+expires = "2030-01-01"
+
+[[suppress]]
+pkgbase = "linux"
+kind = "unversioned-url"
+reason = "expired"
+expires = "2020-01-01"
+"#,
+            parse_date("2024-06-01")?,
+        )?;
+        assert_eq!(file.entries().len(), 1);
+
+        let finding = |url: &str| Finding::UnversionedUrl {
+            url: url.to_string(),
+            keyword: None,
+        };
+        let mut pkg = PackageFindings::from(vec![
+            finding("https://example.com/foo.tar.gz"),
+            finding("https://example.org/foo.tar.gz"),
+        ]);
+        file.apply(&Target::ArchBuildSystem("python-foo".to_string()), &mut pkg);
+        assert_eq!(pkg.findings.len(), 1);
+        assert_eq!(pkg.suppressed.len(), 1);
+        assert_eq!(pkg.suppressed[0].expires.as_deref(), Some("2030-01-01"));
+
+        let mut pkg = PackageFindings::from(vec![finding("https://example.com/foo.tar.gz")]);
+        file.apply(&Target::ArchBuildSystem("linux".to_string()), &mut pkg);
+        assert_eq!(pkg.findings.len(), 1);
+        Ok(())
+    }
+}
@@ -1,12 +1,23 @@
 use crate::errors::*;
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct SvnSource {
     url: String,
     revision: Option<String>,
 }
 
+impl SvnSource {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn revision(&self) -> Option<&str> {
+        self.revision.as_deref()
+    }
+}
+
 impl FromStr for SvnSource {
     type Err = Error;
 
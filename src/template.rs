@@ -0,0 +1,165 @@
+use crate::errors::*;
+use crate::fsck::{Finding, Target};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Var {
+    Target,
+    Pkgbase,
+    Kind,
+    Severity,
+    Message,
+    Description,
+    Url,
+    Fix,
+}
+
+impl FromStr for Var {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Var> {
+        Ok(match s {
+            "target" => Var::Target,
+            "pkgbase" => Var::Pkgbase,
+            "kind" => Var::Kind,
+            "severity" => Var::Severity,
+            "message" => Var::Message,
+            "description" => Var::Description,
+            "url" => Var::Url,
+            "fix" => Var::Fix,
+            _ => bail!("Unknown template variable: {:?}", s),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Part {
+    Literal(String),
+    Var(Var),
+}
+
+/// A user provided template that is rendered for every finding, eg. `{target}: {kind} ({severity})`
+///
+/// Supported variables are `{target}`, `{pkgbase}`, `{kind}`, `{severity}`, `{message}`,
+/// `{description}`, `{url}` and `{fix}`. Use `{{` and `}}` for literal braces and `\n`/`\t` for
+/// newlines and tabs. A newline is appended after every rendered finding.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl FromStr for Template {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Template> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '\\' if chars.peek() == Some(&'n') => {
+                    chars.next();
+                    literal.push('\n');
+                }
+                '\\' if chars.peek() == Some(&'t') => {
+                    chars.next();
+                    literal.push('\t');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => bail!("Unclosed template variable: {{{}", name),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(literal.split_off(0)));
+                    }
+                    parts.push(Part::Var(name.trim().parse()?));
+                }
+                '}' => bail!("Unexpected `}}` in template, use `}}}}` for a literal brace"),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+impl Template {
+    pub fn render(&self, target: &Target, finding: &Finding) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Var(Var::Target) => out.push_str(&target.display()),
+                Part::Var(Var::Pkgbase) => out.push_str(&target.pkgbase()),
+                Part::Var(Var::Kind) => out.push_str(finding.into()),
+                Part::Var(Var::Severity) => {
+                    write!(out, "{}", finding.severity()).ok();
+                }
+                Part::Var(Var::Message) => {
+                    write!(out, "{}", finding).ok();
+                }
+                Part::Var(Var::Description) => out.push_str(finding.description()),
+                Part::Var(Var::Url) => out.push_str(finding.url().unwrap_or_default()),
+                Part::Var(Var::Fix) => out.push_str(&finding.fix().unwrap_or_default()),
+            }
+        }
+        out
+    }
+
+    pub fn write<W: Write>(&self, mut w: W, target: &Target, findings: &[Finding]) -> Result<()> {
+        for finding in findings {
+            writeln!(w, "{}", self.render(target, finding))?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_template() -> Result<()> {
+        let t = "{{{target}}}: {kind}\\t{ severity }".parse::<Template>()?;
+        assert_eq!(
+            t.parts,
+            vec![
+                Part::Literal("{".to_string()),
+                Part::Var(Var::Target),
+                Part::Literal("}: ".to_string()),
+                Part::Var(Var::Kind),
+                Part::Literal("\t".to_string()),
+                Part::Var(Var::Severity),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid_template() {
+        assert!("{foo}".parse::<Template>().is_err());
+        assert!("{target".parse::<Template>().is_err());
+        assert!("target}".parse::<Template>().is_err());
+    }
+}
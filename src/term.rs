@@ -0,0 +1,130 @@
+use crate::errors::*;
+use crate::fsck::{Finding, Severity, Target};
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+/// Renders findings for humans, grouped by package and colored by severity
+pub struct Renderer {
+    stream: StandardStream,
+    enabled: bool,
+    show_packages: bool,
+    packages: usize,
+    packages_with_findings: usize,
+    findings: usize,
+    errors: usize,
+}
+
+fn color_choice() -> ColorChoice {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color || !io::stderr().is_terminal() {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    }
+}
+
+fn severity_color(severity: Severity) -> ColorSpec {
+    let mut spec = ColorSpec::new();
+    match severity {
+        Severity::High => spec.set_fg(Some(Color::Red)).set_bold(true),
+        Severity::Medium => spec.set_fg(Some(Color::Yellow)),
+        Severity::Low => spec.set_fg(Some(Color::Cyan)),
+        Severity::Info => spec.set_dimmed(true),
+    };
+    spec
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer {
+            stream: StandardStream::stderr(color_choice()),
+            // findings used to be logged as warnings, keep them silent with -qq
+            enabled: log_enabled!(log::Level::Warn),
+            show_packages: true,
+            packages: 0,
+            packages_with_findings: 0,
+            findings: 0,
+            errors: 0,
+        }
+    }
+
+    /// Only count findings, eg. if they are written somewhere else
+    pub fn hide_packages(mut self) -> Self {
+        self.show_packages = false;
+        self
+    }
+
+    fn write_package(&mut self, target: &Target, findings: &[Finding]) -> io::Result<()> {
+        let mut w = self.stream.lock();
+
+        w.set_color(ColorSpec::new().set_bold(true))?;
+        writeln!(w, "{}", target.display())?;
+        w.reset()?;
+
+        for finding in findings {
+            let severity = finding.severity();
+            let kind: &'static str = finding.into();
+
+            write!(w, "  ")?;
+            w.set_color(&severity_color(severity))?;
+            write!(w, "{:<6}", severity)?;
+            w.reset()?;
+            writeln!(w, "  {}: {}", kind, finding)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn package(&mut self, target: &Target, findings: &[Finding]) {
+        self.packages += 1;
+        if findings.is_empty() {
+            return;
+        }
+        self.packages_with_findings += 1;
+        self.findings += findings.len();
+
+        if self.enabled && self.show_packages {
+            if let Err(err) = self.write_package(target, findings) {
+                debug!("Failed to write to terminal: {:#}", err);
+            }
+        }
+    }
+
+    pub fn error(&mut self) {
+        self.packages += 1;
+        self.errors += 1;
+    }
+
+    pub fn finish(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut w = self.stream.lock();
+        let mut summary = format!(
+            "{} findings in {} of {} packages",
+            self.findings, self.packages_with_findings, self.packages
+        );
+        if self.errors > 0 {
+            summary.push_str(&format!(", {} packages failed to be checked", self.errors));
+        }
+
+        let mut spec = ColorSpec::new();
+        spec.set_bold(true);
+        if self.findings > 0 || self.errors > 0 {
+            spec.set_fg(Some(Color::Yellow));
+        } else {
+            spec.set_fg(Some(Color::Green));
+        }
+        w.set_color(&spec).ok();
+        writeln!(w, "{}", summary).ok();
+        w.reset().ok();
+    }
+}
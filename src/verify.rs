@@ -0,0 +1,101 @@
+use crate::errors::*;
+use crate::fsck::Checksum;
+use crate::golang::hex;
+use blake2::Blake2b512;
+use md5::Md5;
+use reqwest::Client;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+enum Hasher {
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha224(Sha224),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+    B2(Blake2b512),
+}
+
+impl Hasher {
+    fn new(checksum: &Checksum) -> Option<Hasher> {
+        Some(match checksum {
+            Checksum::Md5(_) => Hasher::Md5(Md5::new()),
+            Checksum::Sha1(_) => Hasher::Sha1(Sha1::new()),
+            Checksum::Sha224(_) => Hasher::Sha224(Sha224::new()),
+            Checksum::Sha256(_) => Hasher::Sha256(Sha256::new()),
+            Checksum::Sha384(_) => Hasher::Sha384(Sha384::new()),
+            Checksum::Sha512(_) => Hasher::Sha512(Sha512::new()),
+            Checksum::B2(_) => Hasher::B2(Blake2b512::new()),
+            Checksum::Cksum(_) => return None,
+        })
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Sha224(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha384(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+            Hasher::B2(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Md5(h) => hex(&h.finalize()),
+            Hasher::Sha1(h) => hex(&h.finalize()),
+            Hasher::Sha224(h) => hex(&h.finalize()),
+            Hasher::Sha256(h) => hex(&h.finalize()),
+            Hasher::Sha384(h) => hex(&h.finalize()),
+            Hasher::Sha512(h) => hex(&h.finalize()),
+            Hasher::B2(h) => hex(&h.finalize()),
+        }
+    }
+}
+
+/// Download an url and calculate the checksums with the same algorithms as the declared ones
+pub async fn download_checksums(
+    client: &Client,
+    url: &str,
+    declared: &[Checksum],
+) -> Result<Vec<(Checksum, Checksum)>> {
+    let mut hashers = declared
+        .iter()
+        .filter_map(|checksum| Some((checksum, Hasher::new(checksum)?)))
+        .collect::<Vec<_>>();
+
+    info!("Downloading {:?} to verify checksums", url);
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    while let Some(chunk) = response.chunk().await? {
+        for (_, hasher) in &mut hashers {
+            hasher.update(&chunk);
+        }
+    }
+
+    let mut checksums = Vec::new();
+    for (declared, hasher) in hashers {
+        let actual = Checksum::new(declared.alg(), hasher.finalize())
+            .context("Failed to construct checksum")?;
+        checksums.push((declared.clone(), actual));
+    }
+    Ok(checksums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hasher() {
+        let mut hasher = Hasher::new(&Checksum::Sha256(String::new())).unwrap();
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(
+            hasher.finalize(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::errors::*;
+use regex::Regex;
+
+const ARCHIVE_EXTENSIONS: &str = r"(?:tar\.gz|tar\.bz2|tar\.xz|tar\.zst|tgz|zip)";
+
+/// The version part of a tag, eg. `1.2.3` for `v1.2.3` or `release-1_2_3`
+pub fn tag_version(tag: &str) -> Option<&str> {
+    // a commit hash in `#tag=` doesn't name a version
+    if tag.len() == 40 && tag.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    // the version starts after a prefix like `v`, `release-` or `foo-`, the parts of
+    // the prefix don't start with a digit so the version isn't cut short
+    let re = Regex::new(r"^(?:[^\d/_-][^/_-]*[-_/])*?[vV]?(\d.*)$").ok()?;
+    let caps = re.captures(tag)?;
+    Some(caps.get(1)?.as_str())
+}
+
+/// Detect the version of a release archive, either from a forge archive url or a `<pkgname>-<version>.tar.gz` filename
+pub fn url_version(
+    url: &str,
+    filename: Option<&str>,
+    pkgnames: &[String],
+) -> Result<Option<String>> {
+    let re = Regex::new(&format!(
+        r"^https?://(?:github\.com|gitlab\.com|codeberg\.org)/.+/archive/(?:refs/tags/)?(?:.+/)?([^/]+?)\.{}$",
+        ARCHIVE_EXTENSIONS
+    ))?;
+    if let Some(caps) = re.captures(url) {
+        return Ok(tag_version(&caps[1]).map(String::from));
+    }
+
+    let filename = filename.unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url));
+    for pkgname in pkgnames {
+        let re = Regex::new(&format!(
+            r"^{}-(\d[^/]*?)\.{}$",
+            regex::escape(pkgname),
+            ARCHIVE_EXTENSIONS
+        ))?;
+        if let Some(caps) = re.captures(filename) {
+            return Ok(Some(caps[1].to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+const MOVING_KEYWORDS: &[&str] = &["latest", "head", "master", "main", "trunk", "nightly"];
+
+/// Strip the scheme and host, only the path and query of a url can name a version
+fn url_path(url: &str) -> &str {
+    let url = url.split_once("://").map(|(_, url)| url).unwrap_or(url);
+    url.find('/').map(|idx| &url[idx..]).unwrap_or_default()
+}
+
+/// Find keywords in a url that refer to whatever is the most recent version upstream
+pub fn moving_keyword(url: &str) -> Option<&'static str> {
+    url_path(url)
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .find_map(|component| {
+            MOVING_KEYWORDS
+                .iter()
+                .find(|keyword| component.eq_ignore_ascii_case(keyword))
+                .copied()
+        })
+}
+
+/// Check if a url has any component that could be a version or a commit
+pub fn has_version(url: &str) -> bool {
+    url_path(url).chars().any(|c| c.is_ascii_digit())
+}
+
+fn components(version: &str) -> Vec<String> {
+    version
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Check if a url contains the pkgver, allowing `.`, `_` and `-` to be used interchangeably
+pub fn contains_pkgver(url: &str, pkgver: &str) -> bool {
+    let re = Regex::new(r"[0-9]+|[A-Za-z]+").expect("token regex is valid");
+    let tokens = re
+        .find_iter(pkgver)
+        .map(|m| regex::escape(m.as_str()))
+        .collect::<Vec<_>>();
+    if tokens.is_empty() {
+        return false;
+    }
+    let re = format!(r"(?i)(?:^|[^0-9]){}(?:$|[^0-9])", tokens.join("[._+-]?"));
+    Regex::new(&re).is_ok_and(|re| re.is_match(url))
+}
+
+/// Check if a version from a tag or url plausibly belongs to the declared pkgver
+pub fn matches_pkgver(version: &str, pkgver: &str) -> bool {
+    let version = components(version);
+    let pkgver = components(pkgver);
+    if version.is_empty() || pkgver.is_empty() {
+        return true;
+    }
+
+    // 1.2.3 vs 1.2.3.r4, 1.2.3-rc1 vs 1.2.3rc1, 2023-01-01 vs 20230101
+    version.starts_with(&pkgver)
+        || pkgver.starts_with(&version)
+        || version.concat() == pkgver.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_version() {
+        assert_eq!(tag_version("v1.2.3"), Some("1.2.3"));
+        assert_eq!(tag_version("release-1_2_3"), Some("1_2_3"));
+        assert_eq!(tag_version("1.2.3"), Some("1.2.3"));
+        assert_eq!(tag_version("latest"), None);
+        assert_eq!(tag_version("gtk3-1.2.3"), Some("1.2.3"));
+        assert_eq!(tag_version("v2.4.0-1"), Some("2.4.0-1"));
+        assert_eq!(tag_version("1.0-2"), Some("1.0-2"));
+        assert_eq!(tag_version("2024-03-15"), Some("2024-03-15"));
+        assert_eq!(
+            tag_version("0123456789abcdef0123456789abcdef01234567"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_url_version() -> Result<()> {
+        let pkgnames = ["foo".to_string()];
+        assert_eq!(
+            url_version(
+                "https://github.com/foo/foo/archive/v1.2.3.tar.gz",
+                None,
+                &pkgnames
+            )?,
+            Some("1.2.3".to_string())
+        );
+        assert_eq!(
+            url_version(
+                "https://github.com/foo/foo/archive/refs/tags/v1.2.3.tar.gz",
+                None,
+                &pkgnames
+            )?,
+            Some("1.2.3".to_string())
+        );
+        assert_eq!(
+            url_version(
+                "https://example.com/releases/foo-1.2.3.tar.xz",
+                None,
+                &pkgnames
+            )?,
+            Some("1.2.3".to_string())
+        );
+        assert_eq!(
+            url_version(
+                "https://example.com/download?id=1",
+                Some("foo-1.2.3.tar.gz"),
+                &pkgnames
+            )?,
+            Some("1.2.3".to_string())
+        );
+        assert_eq!(
+            url_version(
+                "https://example.com/releases/bar-1.2.3.tar.xz",
+                None,
+                &pkgnames
+            )?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unversioned_urls() {
+        assert_eq!(
+            moving_keyword("https://example.com/download.php?file=latest"),
+            Some("latest")
+        );
+        assert_eq!(
+            moving_keyword("https://github.com/foo/bar/archive/master.tar.gz"),
+            Some("master")
+        );
+        assert_eq!(
+            moving_keyword("https://raw.githubusercontent.com/foo/bar/HEAD/LICENSE"),
+            Some("head")
+        );
+        assert_eq!(
+            moving_keyword("https://latest.example.com/foo-1.2.3.tar.gz"),
+            None
+        );
+        assert_eq!(
+            moving_keyword("https://example.com/headers-1.2.tar.gz"),
+            None
+        );
+
+        assert!(has_version("https://example.com/foo-1.2.3.tar.gz"));
+        assert!(!has_version("https://example.com/foo.tar.gz"));
+        assert!(!has_version("https://example2.com/foo.tar.gz"));
+    }
+
+    #[test]
+    fn test_contains_pkgver() {
+        assert!(contains_pkgver(
+            "https://example.com/foo-1.2.3.tar.gz",
+            "1.2.3"
+        ));
+        assert!(contains_pkgver(
+            "https://example.com/foo_1_2_3.tar.gz",
+            "1.2.3"
+        ));
+        assert!(contains_pkgver(
+            "https://example.com/v1.2.3-rc1.tar.gz",
+            "1.2.3rc1"
+        ));
+        assert!(!contains_pkgver(
+            "https://example.com/foo-1.2.2.tar.gz",
+            "1.2.3"
+        ));
+    }
+
+    #[test]
+    fn test_matches_pkgver() {
+        assert!(matches_pkgver("1.2.3", "1.2.3"));
+        assert!(matches_pkgver("1.2.3-rc1", "1.2.3rc1"));
+        assert!(matches_pkgver("1_2_3", "1.2.3"));
+        assert!(matches_pkgver("1.2.3", "1.2.3.r4"));
+        assert!(matches_pkgver("2023-01-01", "20230101"));
+        assert!(!matches_pkgver("1.2.3", "1.2.4"));
+        assert!(!matches_pkgver("1.20", "1.2"));
+    }
+}
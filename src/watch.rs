@@ -0,0 +1,100 @@
+use crate::args::{self, Check, Outcome, Scan};
+use crate::errors::*;
+use crate::fsck::{self, Target};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time;
+
+/// Editors tend to write a file in multiple steps, wait for it to settle
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The package directory of a changed file, if it's a PKGBUILD or .SRCINFO
+fn changed_package(path: &Path) -> Option<PathBuf> {
+    let filename = path.file_name()?;
+    if filename != "PKGBUILD" && filename != ".SRCINFO" {
+        return None;
+    }
+    path.parent().map(Path::to_path_buf)
+}
+
+fn collect_changes(changed: &mut BTreeSet<PathBuf>, event: notify::Result<Event>) {
+    match event {
+        Ok(event) => {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                changed.extend(event.paths.iter().filter_map(|path| changed_package(path)));
+            }
+        }
+        Err(err) => warn!("Failed to watch for changes: {:#}", err),
+    }
+}
+
+/// Check all targets once, then check packages again whenever they change
+pub async fn run<S: Scan + Sync>(scan: &S, check: &Check) -> Result<Outcome> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })?;
+
+    for path in &check.paths {
+        if !path.is_dir() {
+            bail!("--watch only supports directories: {:?}", path);
+        }
+        watcher
+            .watch(path, RecursiveMode::NonRecursive)
+            .with_context(|| anyhow!("Failed to watch directory: {:?}", path))?;
+    }
+    for dir in &check.scan_directory {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| anyhow!("Failed to watch directory: {:?}", dir))?;
+    }
+
+    scan.run(check).await?;
+
+    let client = fsck::http_client()?;
+
+    info!("Watching for changes, press ctrl-c to stop");
+    while let Some(event) = rx.recv().await {
+        let mut changed = BTreeSet::new();
+        collect_changes(&mut changed, event);
+        while let Ok(Some(event)) = time::timeout(DEBOUNCE, rx.recv()).await {
+            collect_changes(&mut changed, event);
+        }
+
+        if changed.is_empty() {
+            continue;
+        }
+        let queue = changed
+            .into_iter()
+            .map(Target::BuildPath)
+            .filter(|target| !args::is_excluded(&check.exclude, target))
+            .collect::<VecDeque<_>>();
+        if let Err(err) = scan.run_targets(check, &client, queue).await {
+            error!("Failed to check changed packages: {:#}", err);
+        }
+    }
+
+    Ok(Outcome::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_package() {
+        assert_eq!(
+            changed_package(Path::new("/aur/paru/PKGBUILD")),
+            Some(PathBuf::from("/aur/paru"))
+        );
+        assert_eq!(
+            changed_package(Path::new("/aur/paru/.SRCINFO")),
+            Some(PathBuf::from("/aur/paru"))
+        );
+        assert_eq!(changed_package(Path::new("/aur/paru/.PKGBUILD.swp")), None);
+        assert_eq!(changed_package(Path::new("/aur/paru")), None);
+    }
+}